@@ -1,5 +1,8 @@
 cosmic-terminal = COSMIC Terminal
 new-terminal = New terminal
+spawn-error = Failed to start {$command}: {$error}
+spawn-error-unknown = Failed to start terminal: {$error}
+retry = Retry
 
 # Context Pages
 
@@ -13,6 +16,29 @@ export = Export
 delete = Delete
 import = Import
 import-errors = Import errors
+new-color-scheme = New color scheme
+done = Done
+color-automatic = Automatic
+foreground = Foreground
+background = Background
+cursor = Cursor
+cursor-text = Cursor text
+color-black = Black
+color-red = Red
+color-green = Green
+color-yellow = Yellow
+color-blue = Blue
+color-magenta = Magenta
+color-cyan = Cyan
+color-white = White
+color-bright-black = Bright black
+color-bright-red = Bright red
+color-bright-green = Bright green
+color-bright-yellow = Bright yellow
+color-bright-blue = Bright blue
+color-bright-magenta = Bright magenta
+color-bright-cyan = Bright cyan
+color-bright-white = Bright white
 
 ## Profiles
 profiles = Profiles
@@ -24,8 +50,43 @@ add-profile = Add profile
 new-profile = New profile
 make-default = Make default
 working-directory = Working directory
-hold = Hold
-remain-open = Remain open after child process exits.
+ssh-host = Remote host
+ssh-host-description = Connect to this host with ssh instead of running the command locally. The command, if any, is run on the host.
+ssh-user = User
+ssh-port = Port
+ssh-identity-file = Identity file
+serial-device = Serial device
+serial-device-description = Connect the tab directly to this serial device instead of running a command.
+serial-baud-rate = Baud rate
+serial-parity = Parity
+serial-parity-none = None
+serial-parity-even = Even
+serial-parity-odd = Odd
+remote-connecting = Connecting to {$destination}…
+remote-disconnected = {$destination} (disconnected)
+environment = Environment variables
+environment-description = Space separated KEY=value pairs, quoted like a command line.
+exit-action = When the command exits
+exit-action-description = Keeping the tab open shows the exit code, restarting is useful for watching flaky scripts.
+exit-action-close = Close the tab
+exit-action-hold = Keep the tab open
+exit-action-restart = Restart the command
+process-exited = [process exited with code {$code}]
+process-restarting = [process exited with code {$code}, restarting in {$seconds}s]
+login-shell = Login shell
+login-shell-description = Start your shell with -l so it reads login startup files. Other commands are run as given.
+output-log = Output log
+output-log-description = Output of every tab opened with this profile is appended to this file.
+output-log-format-raw = With escape sequences
+output-log-format-plain = Plain text
+background-image = Background image
+background-image-description = Path to an image drawn behind the text.
+background-image-scale = Background image size
+background-image-fill = Fill
+background-image-fit = Fit
+background-image-stretch = Stretch
+background-image-center = Center
+background-image-dim = Background image dim
 
 ## Settings
 settings = Settings
@@ -40,17 +101,62 @@ syntax-dark = Color scheme dark
 syntax-light = Color scheme light
 default-zoom-step = Zoom steps
 opacity = Background opacity
+padding = Space around text
+cursor-shape = Cursor shape
+cursor-shape-block = Block
+cursor-shape-beam = Beam
+cursor-shape-underline = Underline
+cursor-blink = Blinking cursor
+cursor-blink-description = Programs can still choose their own cursor shape and blinking.
+cursor-color = Cursor color
+cursor-color-description = When automatic, the cursor swaps the colors of the text under it.
+cursor-text-color = Cursor text color
 
 ### Font
 font = Font
 advanced-font-settings = Advanced font settings
 default-font = Font
 default-font-size = Font size
+default-line-height = Line height
+font-ligatures = Ligatures
+font-ligatures-description = Join sequences like -> into one glyph when the font has ligatures for them.
 default-font-stretch = Font stretch
 default-font-weight = Normal font weight
 default-dim-font-weight = Dim font weight
 default-bold-font-weight = Bold font weight
-use-bright-bold = Make bold text brighter
+use-bright-bold = Use bright colors for bold text
+minimum-contrast = Minimum contrast
+minimum-contrast-description = Lighten or darken text that is hard to read against its background.
+minimum-contrast-off = Off
+minimum-contrast-ratio = {$ratio}:1
+
+### Bell
+bell = Bell
+bell-visual = Flash the terminal
+bell-audible = Play a sound
+bell-urgent = Request attention
+bell-urgent-description = Highlight the window when it is not focused.
+bell-tab-badge = Show an icon on background tabs
+tab-activity-indicator = Show a dot on background tabs with new output
+
+### Notifications
+notifications = Notifications
+command-notify = Notify when a command finishes
+command-notify-description = Needs shell integration. Only commands in tabs that are not focused are reported.
+command-notify-never = Never
+command-notify-seconds = After {$seconds} seconds
+command-finished = Command finished
+command-failed = Command failed with exit status {$status}
+
+### Tabs
+tabs = Tabs
+tab-bar-visibility = Show tab bar
+tab-bar-always = Always
+tab-bar-multiple = With more than one tab
+tab-bar-never = Never
+tab-bar-position = Tab bar position
+tab-bar-top = Top
+tab-bar-bottom = Bottom
 
 ### Splits
 splits = Splits
@@ -60,40 +166,151 @@ focus-follow-mouse = Typing focus follows mouse
 advanced = Advanced
 show-headerbar = Show header
 show-header-description = Reveal the header from the right-click menu.
+new-tab-inherit-cwd = Open new tabs, splits and windows in the current directory
+new-tab-inherit-cwd-description = Applies to the new tab button, splits and new windows. The new tab and new tab here shortcuts are not affected.
+confirm-close-running = Confirm closing tabs with running programs
+confirm-close-running-description = Ask before closing a tab when a program other than the shell is running in it.
+restore-session = Restore tabs from the last session
+restore-session-description = Remember open tabs and their directories when the window is closed, and offer to open them again at startup.
+new-tab-on-last-close = Keep window open
+new-tab-on-last-close-description = Open a new tab when the last tab is closed.
+tab-title-format = Tab title
+tab-title-format-description = Used until a program sets a title. {"{"}process{"}"} and {"{"}directory{"}"} are replaced, leave empty to always show "New terminal".
+file-ref-command = Open file references with
+file-ref-command-description = Ctrl+click opens references like src/main.rs:12:5 by running this command in a new tab. {"{"}file{"}"}, {"{"}line{"}"} and {"{"}column{"}"} are replaced, leave empty to use the default app.
+copy-on-select = Copy selected text automatically
+copy-on-select-description = Selected text goes to the primary selection, which is pasted with middle click. Copy and paste with Ctrl+Shift+C and Ctrl+Shift+V use the clipboard instead.
+copy-html = Copy text with colors
+copy-html-description = Also copy selected text as HTML with its colors and styles, for pasting into documents and chat apps.
+clipboard-read = Allow programs to read the clipboard
+clipboard-read-description = Programs can always copy to the clipboard. Reading lets any program, including ones on remote hosts, see what you copied.
+paste-filter = Pasted control characters
+paste-filter-description = Protects against pasted text that secretly contains escape sequences or carriage returns.
+paste-filter-none = Keep
+paste-filter-strip = Remove
+paste-filter-escape = Show escaped
+paste-lone-cr-to-lf = Convert pasted carriage returns to newlines
+paste-confirm-prompt = Confirm risky pastes
+paste-confirm-prompt-description = Shows text with line breaks or control characters before it is pasted, so commands are not run by accident.
+alternate-scroll = Scroll with arrow keys in full screen apps
+alternate-scroll-description = Mouse wheel sends up and down arrows in programs like less and man.
+word-separators = Word separators
+word-separators-description = Characters that end a word when double-click selecting, and a file path when Ctrl+clicking.
+scrollback = Scrollback
+scrollback-lines = {$lines} lines
+scrollback-archive = Archive old scrollback to disk
+scrollback-archive-description = Lines pushed out of the scrollback are kept compressed in a temporary folder until the tab is closed. They are shown in a tab of their own and included when exporting the scrollback, but scrolling and find do not reach them.
+scroll-on-keypress = Scroll to the bottom when typing
+scroll-on-output = Scroll to the bottom on new output
 
 # Find
 find-placeholder = Find...
 find-previous = Find previous
 find-next = Find next
+find-match-case = Match case
+find-regex = Regex
+find-alt-screen = A full screen program is running, only the visible screen can be searched
+
+# Close
+close-running = {$process} is still running in this tab
+close-running-unknown = A program may still be running in this tab
+cancel = Cancel
+close-anyway = Close anyway
+
+# Session
+session-restore-offer = {$count ->
+    [one] Restore the tab from the last session?
+    *[other] Restore {$count} tabs from the last session?
+}
+dismiss = Dismiss
+restore = Restore
+
+# Pause
+output-paused = Output paused
+output-paused-overflow = Output paused, the oldest output is being discarded
+read-only-tab = Read-only, keyboard, mouse and pasted input is ignored
+allow-input = Allow input
+broadcast-tabs-active = Typing is sent to all tabs
+broadcast-panes-active = Typing is sent to all panes
+broadcast-stop = Stop broadcasting
+recording-session = Recording to {$file}
+stop-recording = Stop recording
+logging-output = Logging output to {$file}
+stop-logging = Stop logging
+archived-lines = {$lines} older lines are archived
+show-archived-history = Show archived history
+archived-history = Archived history
+resume-output = Resume
 
 # Menu
 
 ## File
 file = File
 new-tab = New tab
+new-tab-here = New tab here
 new-window = New window
 profile = Profile
+remote-profile = Remote host
 menu-profiles = Profiles...
+rename-tab = Rename tab
+open-working-directory = Open current directory in Files
+copy-working-directory = Copy current directory path
 close-tab = Close tab
+close-other-tabs = Close other tabs
+close-tabs-right = Close tabs to the right
+duplicate-tab = Duplicate tab
+open-tab-in-window = Open in new window
 quit = Quit
+window-minimize = Minimize window
+window-maximize = Maximize window
+window-close = Close window
 
 ## Edit
 edit = Edit
 copy = Copy
+copy-last-output = Copy last command output
 paste = Paste
+paste-confirm = Paste {$lines ->
+    [one] 1 line
+    *[other] {$lines} lines
+}?
+paste-strip-newline = Remove trailing newline
+paste-join-lines = Join lines
 select-all = Select all
+export-scrollback-text = Export scrollback as text...
+export-scrollback-html = Export scrollback as HTML...
+record-session = Record session...
+log-output = Log output...
 find = Find
+copy-mode = Copy mode
+hints = Select from screen
+pause-output = Pause output
+read-only = Read-only
+broadcast-tabs = Broadcast input to all tabs
+broadcast-panes = Broadcast input to all panes
 
 ## View
 view = View
 zoom-in = Larger text
+zoom-level = Zoom {$percent}%
 zoom-reset = Default text size
+fullscreen = Fullscreen
 zoom-out = Smaller text
+performance-overlay = {$fps} FPS, {$kib} KiB/s from PTY, {$events} queued events, {$latency} ms redraw latency
 next-tab = Next tab
 previous-tab = Previous tab
+move-tab-left = Move tab left
+move-tab-right = Move tab right
+previous-prompt = Previous prompt
+next-prompt = Next prompt
+toggle-mark = Mark line
+previous-mark = Previous mark
+next-mark = Next mark
 split-horizontal = Split horizontal
 split-vertical = Split vertical
 pane-toggle-maximize = Toggle maximized
+pane-close = Close split
+open-link = Open link
 menu-color-schemes = Color schemes...
 menu-settings = Settings...
 menu-about = About COSMIC Terminal...
@@ -0,0 +1,388 @@
+// Box drawing, block element, and powerline characters drawn as rectangles, so that they line up
+// at any font size instead of relying on the glyphs of the font
+
+use cosmic::iced::{Point, Rectangle, Size};
+
+// Weights of the left, right, up, and down arms of U+2500 to U+257F: 0 is none, 1 is light,
+// 2 is heavy, and 3 is double. Dashed lines, arcs, and diagonals are handled separately.
+const BOX_ARMS: [[u8; 4]; 128] = [
+    [1, 1, 0, 0], // ─
+    [2, 2, 0, 0], // ━
+    [0, 0, 1, 1], // │
+    [0, 0, 2, 2], // ┃
+    [1, 1, 0, 0], // ┄
+    [2, 2, 0, 0], // ┅
+    [0, 0, 1, 1], // ┆
+    [0, 0, 2, 2], // ┇
+    [1, 1, 0, 0], // ┈
+    [2, 2, 0, 0], // ┉
+    [0, 0, 1, 1], // ┊
+    [0, 0, 2, 2], // ┋
+    [0, 1, 0, 1], // ┌
+    [0, 2, 0, 1], // ┍
+    [0, 1, 0, 2], // ┎
+    [0, 2, 0, 2], // ┏
+    [1, 0, 0, 1], // ┐
+    [2, 0, 0, 1], // ┑
+    [1, 0, 0, 2], // ┒
+    [2, 0, 0, 2], // ┓
+    [0, 1, 1, 0], // └
+    [0, 2, 1, 0], // ┕
+    [0, 1, 2, 0], // ┖
+    [0, 2, 2, 0], // ┗
+    [1, 0, 1, 0], // ┘
+    [2, 0, 1, 0], // ┙
+    [1, 0, 2, 0], // ┚
+    [2, 0, 2, 0], // ┛
+    [0, 1, 1, 1], // ├
+    [0, 2, 1, 1], // ┝
+    [0, 1, 2, 1], // ┞
+    [0, 1, 1, 2], // ┟
+    [0, 1, 2, 2], // ┠
+    [0, 2, 2, 1], // ┡
+    [0, 2, 1, 2], // ┢
+    [0, 2, 2, 2], // ┣
+    [1, 0, 1, 1], // ┤
+    [2, 0, 1, 1], // ┥
+    [1, 0, 2, 1], // ┦
+    [1, 0, 1, 2], // ┧
+    [1, 0, 2, 2], // ┨
+    [2, 0, 2, 1], // ┩
+    [2, 0, 1, 2], // ┪
+    [2, 0, 2, 2], // ┫
+    [1, 1, 0, 1], // ┬
+    [2, 1, 0, 1], // ┭
+    [1, 2, 0, 1], // ┮
+    [2, 2, 0, 1], // ┯
+    [1, 1, 0, 2], // ┰
+    [2, 1, 0, 2], // ┱
+    [1, 2, 0, 2], // ┲
+    [2, 2, 0, 2], // ┳
+    [1, 1, 1, 0], // ┴
+    [2, 1, 1, 0], // ┵
+    [1, 2, 1, 0], // ┶
+    [2, 2, 1, 0], // ┷
+    [1, 1, 2, 0], // ┸
+    [2, 1, 2, 0], // ┹
+    [1, 2, 2, 0], // ┺
+    [2, 2, 2, 0], // ┻
+    [1, 1, 1, 1], // ┼
+    [2, 1, 1, 1], // ┽
+    [1, 2, 1, 1], // ┾
+    [2, 2, 1, 1], // ┿
+    [1, 1, 2, 1], // ╀
+    [1, 1, 1, 2], // ╁
+    [1, 1, 2, 2], // ╂
+    [2, 1, 2, 1], // ╃
+    [1, 2, 2, 1], // ╄
+    [2, 1, 1, 2], // ╅
+    [1, 2, 1, 2], // ╆
+    [2, 2, 2, 1], // ╇
+    [2, 2, 1, 2], // ╈
+    [2, 1, 2, 2], // ╉
+    [1, 2, 2, 2], // ╊
+    [2, 2, 2, 2], // ╋
+    [1, 1, 0, 0], // ╌
+    [2, 2, 0, 0], // ╍
+    [0, 0, 1, 1], // ╎
+    [0, 0, 2, 2], // ╏
+    [3, 3, 0, 0], // ═
+    [0, 0, 3, 3], // ║
+    [0, 3, 0, 1], // ╒
+    [0, 1, 0, 3], // ╓
+    [0, 3, 0, 3], // ╔
+    [3, 0, 0, 1], // ╕
+    [1, 0, 0, 3], // ╖
+    [3, 0, 0, 3], // ╗
+    [0, 3, 1, 0], // ╘
+    [0, 1, 3, 0], // ╙
+    [0, 3, 3, 0], // ╚
+    [3, 0, 1, 0], // ╛
+    [1, 0, 3, 0], // ╜
+    [3, 0, 3, 0], // ╝
+    [0, 3, 1, 1], // ╞
+    [0, 1, 3, 3], // ╟
+    [0, 3, 3, 3], // ╠
+    [3, 0, 1, 1], // ╡
+    [1, 0, 3, 3], // ╢
+    [3, 0, 3, 3], // ╣
+    [3, 3, 0, 1], // ╤
+    [1, 1, 0, 3], // ╥
+    [3, 3, 0, 3], // ╦
+    [3, 3, 1, 0], // ╧
+    [1, 1, 3, 0], // ╨
+    [3, 3, 3, 0], // ╩
+    [3, 3, 1, 1], // ╪
+    [1, 1, 3, 3], // ╫
+    [3, 3, 3, 3], // ╬
+    [0, 1, 0, 1], // ╭
+    [1, 0, 0, 1], // ╮
+    [1, 0, 1, 0], // ╯
+    [0, 1, 1, 0], // ╰
+    [0, 0, 0, 0], // ╱
+    [0, 0, 0, 0], // ╲
+    [0, 0, 0, 0], // ╳
+    [1, 0, 0, 0], // ╴
+    [0, 0, 1, 0], // ╵
+    [0, 1, 0, 0], // ╶
+    [0, 0, 0, 1], // ╷
+    [2, 0, 0, 0], // ╸
+    [0, 0, 2, 0], // ╹
+    [0, 2, 0, 0], // ╺
+    [0, 0, 0, 2], // ╻
+    [1, 2, 0, 0], // ╼
+    [0, 0, 1, 2], // ╽
+    [2, 1, 0, 0], // ╾
+    [0, 0, 2, 1], // ╿
+];
+
+/// Whether a character is drawn by the terminal instead of taken from the font
+pub fn is_drawn(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{2570}' | '\u{2574}'..='\u{259F}' | '\u{E0B0}'..='\u{E0B3}')
+}
+
+/// Rectangles covering a character in a cell of the given size, relative to the top left of the
+/// cell, with the opacity to fill them with
+pub fn rects(c: char, width: f32, height: f32) -> Vec<(Rectangle, f32)> {
+    match c {
+        '\u{2500}'..='\u{257F}' => lines(c, width, height)
+            .into_iter()
+            .map(|rect| (rect, 1.0))
+            .collect(),
+        '\u{2580}'..='\u{259F}' => blocks(c, width, height),
+        '\u{E0B0}'..='\u{E0B3}' => powerline(c, width, height)
+            .into_iter()
+            .map(|rect| (rect, 1.0))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn lines(c: char, width: f32, height: f32) -> Vec<Rectangle> {
+    let arms = BOX_ARMS[(c as u32 - 0x2500) as usize];
+    let light = (width / 8.0).round().max(1.0);
+    let stroke = |weight: u8| if weight == 2 { light * 2.0 } else { light };
+    // Distance from the center to the far edge of an arm
+    let far = |weight: u8| match weight {
+        0 => 0.0,
+        3 => light * 1.5,
+        _ => stroke(weight) / 2.0,
+    };
+    let [left, right, up, down] = arms;
+    let center_x = (width / 2.0).floor();
+    let center_y = (height / 2.0).floor();
+    let vertical = up.max(down);
+    let horizontal = left.max(right);
+
+    let mut rects = Vec::new();
+    // Horizontal arms, with the line above and below the center for double lines
+    for (weight, opposite, is_left) in [(left, right, true), (right, left, false)] {
+        if weight == 0 {
+            continue;
+        }
+        let offsets = if weight == 3 {
+            vec![(-1.5, Some(true)), (0.5, Some(false))]
+        } else {
+            vec![(-0.5 * stroke(weight) / light, None)]
+        };
+        for (offset, above_opt) in offsets {
+            // How far the line reaches past the center to meet the vertical arms, double lines
+            // on the outside of a corner reach further than the ones on the inside
+            let reach = match above_opt {
+                Some(above) => {
+                    let (same, other) = if above { (up, down) } else { (down, up) };
+                    if same == 3 {
+                        // Stop at the inner line of a double arm on the same side
+                        -light / 2.0
+                    } else if same != 0 {
+                        far(same)
+                    } else if other != 0 {
+                        far(other)
+                    } else {
+                        0.0
+                    }
+                }
+                // Stop at the near line of a double arm, unless continuing to the other side
+                None if vertical == 3 && opposite == 0 => -light / 2.0,
+                None if vertical == 3 => 0.0,
+                None => far(vertical),
+            };
+            let y = center_y + offset * light;
+            let h = if weight == 3 { light } else { stroke(weight) };
+            let rect = if is_left {
+                Rectangle::new(Point::new(0.0, y), Size::new(center_x + reach, h))
+            } else {
+                Rectangle::new(
+                    Point::new(center_x - reach, y),
+                    Size::new(width - center_x + reach, h),
+                )
+            };
+            rects.push(rect);
+        }
+    }
+    // Vertical arms, with the line left and right of the center for double lines
+    for (weight, opposite, is_up) in [(up, down, true), (down, up, false)] {
+        if weight == 0 {
+            continue;
+        }
+        let offsets = if weight == 3 {
+            vec![(-1.5, Some(true)), (0.5, Some(false))]
+        } else {
+            vec![(-0.5 * stroke(weight) / light, None)]
+        };
+        for (offset, left_of_center_opt) in offsets {
+            let reach = match left_of_center_opt {
+                Some(left_of_center) => {
+                    let (same, other) = if left_of_center {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    if same == 3 {
+                        // Stop at the inner line of a double arm on the same side
+                        -light / 2.0
+                    } else if same != 0 {
+                        far(same)
+                    } else if other != 0 {
+                        far(other)
+                    } else {
+                        0.0
+                    }
+                }
+                None if horizontal == 3 && opposite == 0 => -light / 2.0,
+                None if horizontal == 3 => 0.0,
+                None => far(horizontal),
+            };
+            let x = center_x + offset * light;
+            let w = if weight == 3 { light } else { stroke(weight) };
+            let rect = if is_up {
+                Rectangle::new(Point::new(x, 0.0), Size::new(w, center_y + reach))
+            } else {
+                Rectangle::new(
+                    Point::new(x, center_y - reach),
+                    Size::new(w, height - center_y + reach),
+                )
+            };
+            rects.push(rect);
+        }
+    }
+
+    // Dashed lines are split into segments with gaps between them
+    let dashes = match c {
+        '\u{2504}'..='\u{2507}' => 3,
+        '\u{2508}'..='\u{250B}' => 4,
+        '\u{254C}'..='\u{254F}' => 2,
+        _ => return rects,
+    };
+    let horizontal_dashes = horizontal != 0;
+    let length = if horizontal_dashes { width } else { height };
+    let segment = length / dashes as f32;
+    let mut dashed = Vec::with_capacity(dashes);
+    let line = rects.iter().fold(None::<Rectangle>, |line_opt, rect| {
+        Some(line_opt.map_or(*rect, |line| line.union(rect)))
+    });
+    if let Some(line) = line {
+        for i in 0..dashes {
+            let start = i as f32 * segment + segment / 4.0;
+            let end = start + segment / 2.0;
+            dashed.push(if horizontal_dashes {
+                Rectangle {
+                    x: start.round(),
+                    width: (end - start).round().max(1.0),
+                    ..line
+                }
+            } else {
+                Rectangle {
+                    y: start.round(),
+                    height: (end - start).round().max(1.0),
+                    ..line
+                }
+            });
+        }
+    }
+    dashed
+}
+
+fn blocks(c: char, width: f32, height: f32) -> Vec<(Rectangle, f32)> {
+    let rect = |x: f32, y: f32, w: f32, h: f32| {
+        Rectangle::new(
+            Point::new((x * width).round(), (y * height).round()),
+            Size::new((w * width).round(), (h * height).round()),
+        )
+    };
+    let eighth = |n: u32| n as f32 / 8.0;
+    let quadrants = |upper_left: bool, upper_right: bool, lower_left: bool, lower_right: bool| {
+        let mut rects = Vec::with_capacity(4);
+        for (filled, x, y) in [
+            (upper_left, 0.0, 0.0),
+            (upper_right, 0.5, 0.0),
+            (lower_left, 0.0, 0.5),
+            (lower_right, 0.5, 0.5),
+        ] {
+            if filled {
+                rects.push((rect(x, y, 0.5, 0.5), 1.0));
+            }
+        }
+        rects
+    };
+    match c {
+        '\u{2580}' => vec![(rect(0.0, 0.0, 1.0, 0.5), 1.0)],
+        // Lower one eighth to full block
+        '\u{2581}'..='\u{2588}' => {
+            let n = c as u32 - 0x2580;
+            vec![(rect(0.0, 1.0 - eighth(n), 1.0, eighth(n)), 1.0)]
+        }
+        // Left seven eighths to one eighth
+        '\u{2589}'..='\u{258F}' => {
+            let n = 0x2590 - c as u32;
+            vec![(rect(0.0, 0.0, eighth(n), 1.0), 1.0)]
+        }
+        '\u{2590}' => vec![(rect(0.5, 0.0, 0.5, 1.0), 1.0)],
+        // Light, medium, and dark shades
+        '\u{2591}' => vec![(rect(0.0, 0.0, 1.0, 1.0), 0.25)],
+        '\u{2592}' => vec![(rect(0.0, 0.0, 1.0, 1.0), 0.5)],
+        '\u{2593}' => vec![(rect(0.0, 0.0, 1.0, 1.0), 0.75)],
+        '\u{2594}' => vec![(rect(0.0, 0.0, 1.0, eighth(1)), 1.0)],
+        '\u{2595}' => vec![(rect(1.0 - eighth(1), 0.0, eighth(1), 1.0), 1.0)],
+        '\u{2596}' => quadrants(false, false, true, false),
+        '\u{2597}' => quadrants(false, false, false, true),
+        '\u{2598}' => quadrants(true, false, false, false),
+        '\u{2599}' => quadrants(true, false, true, true),
+        '\u{259A}' => quadrants(true, false, false, true),
+        '\u{259B}' => quadrants(true, true, true, false),
+        '\u{259C}' => quadrants(true, true, false, true),
+        '\u{259D}' => quadrants(false, true, false, false),
+        '\u{259E}' => quadrants(false, true, true, false),
+        '\u{259F}' => quadrants(false, true, true, true),
+        _ => Vec::new(),
+    }
+}
+
+// Powerline triangles and arrows, drawn one pixel row at a time
+fn powerline(c: char, width: f32, height: f32) -> Vec<Rectangle> {
+    let solid = matches!(c, '\u{E0B0}' | '\u{E0B2}');
+    let pointing_right = matches!(c, '\u{E0B0}' | '\u{E0B1}');
+    let stroke = (width / 8.0).round().max(1.0);
+    let rows = height.ceil() as usize;
+    let half = height / 2.0;
+    let mut rects = Vec::with_capacity(rows);
+    for row in 0..rows {
+        let y = row as f32 + 0.5;
+        // Distance from the flat side to the edge of the triangle in this row
+        let extent = width * (1.0 - (y - half).abs() / half).max(0.0);
+        let (start, end) = if solid {
+            (0.0, extent)
+        } else {
+            ((extent - stroke).max(0.0), extent)
+        };
+        if end <= start {
+            continue;
+        }
+        let x = if pointing_right { start } else { width - end };
+        rects.push(Rectangle::new(
+            Point::new(x, row as f32),
+            Size::new(end - start, 1.0),
+        ));
+    }
+    rects
+}
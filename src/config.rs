@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use alacritty_terminal::vte::ansi;
 use cosmic::{
     cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry},
     theme,
@@ -9,9 +10,10 @@ use hex_color::HexColor;
 use serde::{Deserialize, Serialize};
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::OnceLock;
 
-use crate::fl;
+use crate::{fl, Action};
 
 pub const CONFIG_VERSION: u64 = 1;
 pub const COSMIC_THEME_DARK: &str = "COSMIC Dark";
@@ -44,6 +46,102 @@ pub enum ColorSchemeKind {
 #[serde(transparent)]
 pub struct ColorSchemeId(pub u64);
 
+// How control characters in pasted text are handled
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum PasteFilter {
+    None,
+    Strip,
+    Escape,
+}
+
+// Default cursor shape, programs can still change it with DECSCUSR
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum CursorShape {
+    Block,
+    Beam,
+    Underline,
+}
+
+// How a profile background image is sized to the terminal
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum BackgroundImageScale {
+    // Cover the terminal, cropping the image
+    #[default]
+    Fill,
+    // Show the whole image
+    Fit,
+    Stretch,
+    // Original size
+    Center,
+}
+
+// What a profile terminal does when its process exits
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ExitAction {
+    #[default]
+    Close,
+    // Keep the tab open with the exit code shown
+    Hold,
+    // Run the command again
+    Restart,
+}
+
+// Parity of a serial connection, which always uses 8 data bits and 1 stop bit
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum SerialParity {
+    #[default]
+    None,
+    Even,
+    Odd,
+}
+
+// Whether output logs keep escape sequences, so they can be replayed with cat
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum OutputLogFormat {
+    #[default]
+    Raw,
+    Plain,
+}
+
+/// Baud rates offered for serial connections
+pub const SERIAL_BAUD_RATES: [u32; 8] = [9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600];
+
+fn default_serial_baud_rate() -> u32 {
+    115200
+}
+
+// When the tab bar of a pane is shown
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum TabBarVisibility {
+    Always,
+    // Only when the pane has more than one tab
+    #[default]
+    Multiple,
+    Never,
+}
+
+// Which side of a pane the tab bar is on
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum TabBarPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+// What to do when the last tab of the last pane is closed
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum LastTabClose {
+    CloseWindow,
+    NewTab,
+}
+
+// Expand an argument naming an environment variable. The value is split like a command line, so
+// variables such as EDITOR="code --wait" can hold arguments.
+fn expand_env_arg(var: &str) -> Result<Vec<String>, String> {
+    let value = std::env::var(var).map_err(|err| err.to_string())?;
+    shlex::split(&value).ok_or_else(|| format!("failed to parse {:?}", value))
+}
+
 //TODO: there is a lot of extra code to keep the exported color scheme clean,
 //consider how to reduce this
 fn de_color_opt<'de, D>(deserializer: D) -> Result<Option<HexColor>, D::Error>
@@ -119,6 +217,33 @@ pub struct ColorSchemeAnsi {
 }
 
 impl ColorSchemeAnsi {
+    // Colors in ANSI order: black, red, green, yellow, blue, magenta, cyan, white
+    pub fn color(&self, index: usize) -> Option<HexColor> {
+        match index {
+            0 => self.black,
+            1 => self.red,
+            2 => self.green,
+            3 => self.yellow,
+            4 => self.blue,
+            5 => self.magenta,
+            6 => self.cyan,
+            _ => self.white,
+        }
+    }
+
+    pub fn color_mut(&mut self, index: usize) -> &mut Option<HexColor> {
+        match index {
+            0 => &mut self.black,
+            1 => &mut self.red,
+            2 => &mut self.green,
+            3 => &mut self.yellow,
+            4 => &mut self.blue,
+            5 => &mut self.magenta,
+            6 => &mut self.cyan,
+            _ => &mut self.white,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.black.is_none()
             && self.red.is_none()
@@ -153,6 +278,13 @@ pub struct ColorScheme {
         skip_serializing_if = "Option::is_none"
     )]
     pub cursor: Option<HexColor>,
+    // Text under a block cursor, the background color is used if not set
+    #[serde(
+        deserialize_with = "de_color_opt",
+        serialize_with = "ser_color_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cursor_text: Option<HexColor>,
     #[serde(
         deserialize_with = "de_color_opt",
         serialize_with = "ser_color_opt",
@@ -173,6 +305,55 @@ pub struct ColorScheme {
     pub dim: ColorSchemeAnsi,
 }
 
+/// A color that can be edited in a color scheme, ANSI colors 0-7 are normal and 8-15 bright
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorSchemeField {
+    Foreground,
+    Background,
+    Cursor,
+    CursorText,
+    Ansi(usize),
+}
+
+impl ColorSchemeField {
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            Self::Foreground,
+            Self::Background,
+            Self::Cursor,
+            Self::CursorText,
+        ]
+        .into_iter()
+        .chain((0..16).map(Self::Ansi))
+    }
+}
+
+impl ColorScheme {
+    pub fn color(&self, field: ColorSchemeField) -> Option<HexColor> {
+        match field {
+            ColorSchemeField::Foreground => self.foreground,
+            ColorSchemeField::Background => self.background,
+            ColorSchemeField::Cursor => self.cursor,
+            ColorSchemeField::CursorText => self.cursor_text,
+            ColorSchemeField::Ansi(index @ 0..=7) => self.normal.color(index),
+            ColorSchemeField::Ansi(index @ 8..=15) => self.bright.color(index - 8),
+            ColorSchemeField::Ansi(_) => None,
+        }
+    }
+
+    pub fn color_mut(&mut self, field: ColorSchemeField) -> Option<&mut Option<HexColor>> {
+        match field {
+            ColorSchemeField::Foreground => Some(&mut self.foreground),
+            ColorSchemeField::Background => Some(&mut self.background),
+            ColorSchemeField::Cursor => Some(&mut self.cursor),
+            ColorSchemeField::CursorText => Some(&mut self.cursor_text),
+            ColorSchemeField::Ansi(index @ 0..=7) => Some(self.normal.color_mut(index)),
+            ColorSchemeField::Ansi(index @ 8..=15) => Some(self.bright.color_mut(index - 8)),
+            ColorSchemeField::Ansi(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 #[serde(transparent)]
 pub struct ProfileId(pub u64);
@@ -191,7 +372,94 @@ pub struct Profile {
     #[serde(default)]
     pub working_directory: String,
     #[serde(default)]
-    pub hold: bool,
+    pub environment: String,
+    #[serde(default)]
+    pub background_image: String,
+    #[serde(default)]
+    pub background_image_scale: BackgroundImageScale,
+    #[serde(default)]
+    pub background_image_dim: u8,
+    #[serde(default)]
+    pub exit_action: ExitAction,
+    // Replaced by exit_action, only read to migrate older profiles
+    #[serde(default, skip_serializing)]
+    pub hold: Option<bool>,
+    #[serde(default)]
+    pub login_shell: bool,
+    #[serde(default)]
+    pub ssh_host: String,
+    #[serde(default)]
+    pub ssh_user: String,
+    #[serde(default)]
+    pub ssh_port: String,
+    #[serde(default)]
+    pub ssh_identity_file: String,
+    #[serde(default)]
+    pub serial_device: String,
+    #[serde(default = "default_serial_baud_rate")]
+    pub serial_baud_rate: u32,
+    #[serde(default)]
+    pub serial_parity: SerialParity,
+    #[serde(default)]
+    pub output_log_file: String,
+    #[serde(default)]
+    pub output_log_format: OutputLogFormat,
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+}
+
+impl Profile {
+    /// Serial profiles read and write a serial device directly, without running a command
+    pub fn is_serial(&self) -> bool {
+        !self.serial_device.trim().is_empty()
+    }
+
+    /// Remote profiles connect to a host with ssh instead of running a local command
+    pub fn is_remote(&self) -> bool {
+        !self.ssh_host.trim().is_empty()
+    }
+
+    /// Host given to ssh, prefixed by the user if there is one
+    pub fn ssh_destination(&self) -> Option<String> {
+        let host = self.ssh_host.trim();
+        if host.is_empty() {
+            return None;
+        }
+        let user = self.ssh_user.trim();
+        Some(if user.is_empty() {
+            host.to_string()
+        } else {
+            format!("{}@{}", user, host)
+        })
+    }
+
+    /// Arguments for ssh, the command of the profile is run on the host if set
+    pub fn ssh_args(&self) -> Option<Vec<String>> {
+        let destination = self.ssh_destination()?;
+        let mut args = Vec::new();
+        let port = self.ssh_port.trim();
+        if !port.is_empty() {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        let identity_file = self.ssh_identity_file.trim();
+        if !identity_file.is_empty() {
+            args.push("-i".to_string());
+            args.push(identity_file.to_string());
+        }
+        let command = self.command.trim();
+        if !command.is_empty() {
+            // ssh only allocates a terminal for commands when asked to
+            args.push("-t".to_string());
+        }
+        // Ends the options, so a host starting with - is not read as one
+        args.push("--".to_string());
+        args.push(destination);
+        if !command.is_empty() {
+            args.push(command.to_string());
+        }
+        Some(args)
+    }
 }
 
 impl Default for Profile {
@@ -203,59 +471,259 @@ impl Default for Profile {
             syntax_theme_light: COSMIC_THEME_LIGHT.to_string(),
             tab_title: String::new(),
             working_directory: String::new(),
-            hold: true,
+            environment: String::new(),
+            background_image: String::new(),
+            background_image_scale: BackgroundImageScale::Fill,
+            background_image_dim: 50,
+            exit_action: ExitAction::Hold,
+            hold: None,
+            login_shell: false,
+            ssh_host: String::new(),
+            ssh_user: String::new(),
+            ssh_port: String::new(),
+            ssh_identity_file: String::new(),
+            serial_device: String::new(),
+            serial_baud_rate: default_serial_baud_rate(),
+            serial_parity: SerialParity::None,
+            output_log_file: String::new(),
+            output_log_format: OutputLogFormat::Raw,
+            highlights: Vec::new(),
         }
     }
 }
 
+// What happens when the label of a hint is typed
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum HintAction {
+    Copy,
+    Open,
+    Paste,
+}
+
+/// Pattern labeled in hints mode, earlier hints win where matches overlap
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Hint {
+    pub regex: String,
+    pub action: HintAction,
+}
+
+impl Hint {
+    fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                regex: crate::terminal::URL_REGEX.to_string(),
+                action: HintAction::Open,
+            },
+            // Paths with at least one slash
+            Self {
+                regex: "[A-Za-z0-9_.~+-]*(/[A-Za-z0-9_.~+-]+)+/?".to_string(),
+                action: HintAction::Copy,
+            },
+            // Git hashes
+            Self {
+                regex: "(?-u:\\b)[0-9a-f]{7,40}(?-u:\\b)".to_string(),
+                action: HintAction::Copy,
+            },
+        ]
+    }
+}
+
+/// Cells colored wherever a pattern matches, like errors when following a log. The color is
+/// drawn as a translucent background, over whole lines if `line` is set. Earlier highlights win
+/// where matches overlap.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Highlight {
+    pub regex: String,
+    pub color: HexColor,
+    #[serde(default)]
+    pub line: bool,
+}
+
+/// What a trigger does when its pattern matches a line of output
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TriggerAction {
+    /// Show a desktop notification with the line
+    Notify,
+    /// Ring the bell of the tab
+    Bell,
+    /// Run a command, split like a shell would but never run by one, with the line in the
+    /// COSMIC_TERM_TRIGGER_LINE environment variable
+    Command(String),
+    /// Color the matching text, like a profile highlight
+    Highlight(HexColor),
+}
+
+/// Pattern matched against each line of output, without escape sequences
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Trigger {
+    pub regex: String,
+    pub action: TriggerAction,
+}
+
+/// A tab saved with the session
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SessionTab {
+    pub title: String,
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub profile_id: Option<ProfileId>,
+}
+
+/// Tabs that were open when the window was closed, stored in the state directory
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Session {
+    pub tabs: Vec<SessionTab>,
+    #[serde(default)]
+    pub active: usize,
+}
+
+/// Size of the window when it was closed, stored in the state directory
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WindowState {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
 #[derive(Clone, CosmicConfigEntry, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Config {
+    pub alternate_scroll: bool,
     pub app_theme: AppTheme,
+    pub bell_audible: bool,
+    pub bell_tab_badge: bool,
+    pub bell_urgent: bool,
+    pub bell_visual: bool,
     pub color_schemes_dark: BTreeMap<ColorSchemeId, ColorScheme>,
     pub color_schemes_light: BTreeMap<ColorSchemeId, ColorScheme>,
+    pub command_notify_seconds: u32,
+    pub confirm_close_running: bool,
+    pub copy_on_select: bool,
+    pub copy_html: bool,
+    // Let programs read the clipboard with OSC 52, storing to it is always allowed
+    pub clipboard_read: bool,
+    pub cursor_blink: bool,
+    pub cursor_color: Option<HexColor>,
+    pub cursor_shape: CursorShape,
+    pub cursor_text_color: Option<HexColor>,
+    pub file_ref_command: String,
+    pub font_ligatures: bool,
     pub font_name: String,
     pub font_size: u16,
     pub font_weight: u16,
     pub dim_font_weight: u16,
     pub bold_font_weight: u16,
+    pub use_bright_bold: bool,
     pub font_stretch: u16,
     pub font_size_zoom_step_mul_100: u16,
+    pub hints: Vec<Hint>,
+    pub line_height_mul_100: u16,
+    pub minimum_contrast_mul_10: u16,
+    pub key_binds: BTreeMap<String, Option<Action>>,
+    pub new_tab_inherit_cwd: bool,
+    pub on_last_tab_close: LastTabClose,
     pub opacity: u8,
+    pub padding: u16,
+    pub paste_confirm: bool,
+    pub paste_filter: PasteFilter,
+    pub paste_lone_cr_to_lf: bool,
     pub profiles: BTreeMap<ProfileId, Profile>,
+    pub restore_session: bool,
+    pub scroll_on_keypress: bool,
+    pub scroll_on_output: bool,
+    pub scrollback_archive: bool,
+    pub scrollback_lines: u32,
     pub show_headerbar: bool,
-    pub use_bright_bold: bool,
     pub syntax_theme_dark: String,
     pub syntax_theme_light: String,
+    pub tab_activity_indicator: bool,
+    pub tab_bar_position: TabBarPosition,
+    pub tab_bar_visibility: TabBarVisibility,
+    pub tab_title_format: String,
+    pub triggers: Vec<Trigger>,
+    pub word_separators: String,
     pub focus_follow_mouse: bool,
     pub default_profile: Option<ProfileId>,
+    pub openers: Vec<(String, String)>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            alternate_scroll: true,
             app_theme: AppTheme::System,
+            bell_audible: false,
+            bell_tab_badge: true,
+            bell_urgent: true,
+            bell_visual: true,
             bold_font_weight: Weight::BOLD.0,
+            use_bright_bold: false,
             color_schemes_dark: BTreeMap::new(),
             color_schemes_light: BTreeMap::new(),
+            command_notify_seconds: 10,
+            confirm_close_running: true,
+            copy_on_select: true,
+            copy_html: false,
+            clipboard_read: false,
+            cursor_blink: false,
+            cursor_color: None,
+            cursor_shape: CursorShape::Block,
+            cursor_text_color: None,
+            file_ref_command: "$EDITOR +{line} {file}".to_string(),
             dim_font_weight: Weight::NORMAL.0,
             focus_follow_mouse: false,
+            font_ligatures: true,
             font_name: "Fira Mono".to_string(),
             font_size: 14,
             font_size_zoom_step_mul_100: 100,
             font_stretch: Stretch::Normal.to_number(),
+            hints: Hint::defaults(),
+            key_binds: BTreeMap::new(),
+            line_height_mul_100: 140,
+            minimum_contrast_mul_10: 10,
             font_weight: Weight::NORMAL.0,
+            new_tab_inherit_cwd: false,
+            on_last_tab_close: LastTabClose::CloseWindow,
             opacity: 100,
+            padding: 4,
+            paste_confirm: true,
+            paste_filter: PasteFilter::Strip,
+            paste_lone_cr_to_lf: false,
             profiles: BTreeMap::new(),
+            restore_session: true,
+            scroll_on_keypress: true,
+            scroll_on_output: false,
+            scrollback_archive: false,
+            scrollback_lines: 10_000,
             show_headerbar: true,
             syntax_theme_dark: COSMIC_THEME_DARK.to_string(),
             syntax_theme_light: COSMIC_THEME_LIGHT.to_string(),
-            use_bright_bold: false,
+            tab_activity_indicator: true,
+            tab_bar_position: TabBarPosition::Top,
+            tab_bar_visibility: TabBarVisibility::Multiple,
+            tab_title_format: "{process} — {directory}".to_string(),
+            triggers: Vec::new(),
+            // Same as alacritty
+            word_separators: ",│`|:\"' ()[]{}<>\t".to_string(),
             default_profile: None,
+            openers: Vec::new(),
         }
     }
 }
 
 impl Config {
+    /// Convert settings saved by older versions
+    pub fn migrate(&mut self) {
+        for profile in self.profiles.values_mut() {
+            if let Some(hold) = profile.hold.take() {
+                if hold && profile.exit_action == ExitAction::Close {
+                    profile.exit_action = ExitAction::Hold;
+                }
+            }
+        }
+    }
+
     pub fn color_schemes(
         &self,
         color_scheme_kind: ColorSchemeKind,
@@ -314,13 +782,105 @@ impl Config {
         (font_size + adj * adj_step).max(1.0)
     }
 
-    // Calculate metrics from font size
+    // Cursor style used until a program requests a different one
+    pub fn cursor_style(&self) -> ansi::CursorStyle {
+        ansi::CursorStyle {
+            shape: match self.cursor_shape {
+                CursorShape::Block => ansi::CursorShape::Block,
+                CursorShape::Beam => ansi::CursorShape::Beam,
+                CursorShape::Underline => ansi::CursorShape::Underline,
+            },
+            blinking: self.cursor_blink,
+        }
+    }
+
+    // Calculate metrics from font size and line height
     pub fn metrics(&self, zoom_adj: i8) -> Metrics {
         let font_size = self.font_size_adjusted(zoom_adj);
-        let line_height = (font_size * 1.4).ceil();
+        let line_height_mul = f32::from(self.line_height_mul_100.max(100)) / 100.0;
+        let line_height = (font_size * line_height_mul).ceil();
         Metrics::new(font_size, line_height)
     }
 
+    // Get the argv of the first opener whose pattern is a prefix of the url, with {} and
+    // environment variables like $EDITOR substituted. Returns None to fall back to xdg-open
+    pub fn opener_command(&self, url: &str) -> Option<Vec<String>> {
+        for (pattern, command) in &self.openers {
+            if !url.starts_with(pattern.as_str()) {
+                continue;
+            }
+
+            let Some(args) = shlex::split(command) else {
+                log::warn!("failed to parse opener command {:?}", command);
+                continue;
+            };
+
+            let mut argv = Vec::with_capacity(args.len() + 1);
+            let mut substituted = false;
+            for arg in args {
+                if let Some(var) = arg.strip_prefix('$') {
+                    match expand_env_arg(var) {
+                        Ok(words) => argv.extend(words),
+                        Err(err) => {
+                            log::warn!("failed to expand {:?} in opener command: {}", arg, err);
+                            argv.clear();
+                            break;
+                        }
+                    }
+                } else if arg.contains("{}") {
+                    argv.push(arg.replace("{}", url));
+                    substituted = true;
+                } else {
+                    argv.push(arg);
+                }
+            }
+            if argv.is_empty() {
+                continue;
+            }
+            if !substituted {
+                argv.push(url.to_string());
+            }
+            return Some(argv);
+        }
+        None
+    }
+
+    // Get the argv to open a file at a line, with {file}, {line}, {column} and environment
+    // variables substituted. Returns None to fall back to xdg-open
+    pub fn file_ref_command(&self, file: &str, line: u32, column: u32) -> Option<Vec<String>> {
+        let Some(args) = shlex::split(&self.file_ref_command) else {
+            log::warn!(
+                "failed to parse file reference command {:?}",
+                self.file_ref_command
+            );
+            return None;
+        };
+
+        let mut argv = Vec::with_capacity(args.len());
+        for arg in args {
+            if let Some(var) = arg.strip_prefix('$') {
+                match expand_env_arg(var) {
+                    Ok(words) => argv.extend(words),
+                    Err(err) => {
+                        log::warn!(
+                            "failed to expand {:?} in file reference command: {}",
+                            arg,
+                            err
+                        );
+                        return None;
+                    }
+                }
+            } else {
+                argv.push(
+                    arg.replace("{file}", file)
+                        .replace("{line}", &line.to_string())
+                        .replace("{column}", &column.to_string()),
+                );
+            }
+        }
+        (!argv.is_empty()).then_some(argv)
+    }
+
     pub fn opacity_ratio(&self) -> f32 {
         f32::from(self.opacity) / 100.0
     }
@@ -339,10 +899,43 @@ impl Config {
 
             profile_names.push((name, *profile_id));
         }
-        profile_names.sort_by(|a, b| lexical_sort::natural_lexical_cmp(&a.0, &b.0));
+        // Remote profiles are grouped after the local ones
+        let is_remote = |profile_id| {
+            self.profiles
+                .get(profile_id)
+                .is_some_and(|profile| profile.is_remote())
+        };
+        profile_names.sort_by(|a, b| {
+            is_remote(&a.1)
+                .cmp(&is_remote(&b.1))
+                .then_with(|| lexical_sort::natural_lexical_cmp(&a.0, &b.0))
+        });
         profile_names
     }
 
+    // Configured cursor color, then the one from a custom color scheme. None means the cursor
+    // reverses the colors of the cell under it.
+    pub fn cursor_color(&self, profile_id_opt: Option<ProfileId>) -> Option<HexColor> {
+        self.cursor_color.or_else(|| {
+            let (theme_name, color_scheme_kind) = self.syntax_theme(profile_id_opt);
+            self.color_schemes(color_scheme_kind)
+                .values()
+                .find(|color_scheme| color_scheme.name == theme_name)
+                .and_then(|color_scheme| color_scheme.cursor)
+        })
+    }
+
+    // Configured cursor text color, then the one from a custom color scheme
+    pub fn cursor_text_color(&self, profile_id_opt: Option<ProfileId>) -> Option<HexColor> {
+        self.cursor_text_color.or_else(|| {
+            let (theme_name, color_scheme_kind) = self.syntax_theme(profile_id_opt);
+            self.color_schemes(color_scheme_kind)
+                .values()
+                .find(|color_scheme| color_scheme.name == theme_name)
+                .and_then(|color_scheme| color_scheme.cursor_text)
+        })
+    }
+
     // Get current syntax theme based on dark mode
     pub fn syntax_theme(&self, profile_id_opt: Option<ProfileId>) -> (String, ColorSchemeKind) {
         let color_scheme_kind = self.color_scheme_kind();
@@ -2,17 +2,83 @@ use cosmic::widget::menu::key_bind::{KeyBind, Modifier};
 use cosmic::{iced::keyboard::Key, iced_core::keyboard::key::Named};
 use std::collections::HashMap;
 
-use crate::Action;
+use crate::{config::Config, Action};
 
-//TODO: load from config
-pub fn key_binds() -> HashMap<KeyBind, Action> {
+// Parse a key chord such as "Ctrl+Shift+T" or "Ctrl+Tab". Modifiers may be in any order.
+fn parse_key_bind(chord: &str) -> Option<KeyBind> {
+    let (modifier_names, key_name) = match chord.strip_suffix("++") {
+        Some(modifier_names) => (modifier_names, "+"),
+        None => chord.rsplit_once('+').unwrap_or(("", chord)),
+    };
+
+    let (mut logo, mut ctrl, mut alt, mut shift) = (false, false, false, false);
+    for modifier_name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        match modifier_name.to_lowercase().as_str() {
+            "super" | "logo" => logo = true,
+            "ctrl" | "control" => ctrl = true,
+            "alt" => alt = true,
+            "shift" => shift = true,
+            _ => return None,
+        }
+    }
+    // Use the same modifier order as the default bindings so they compare equal
+    let mut modifiers = Vec::new();
+    if logo {
+        modifiers.push(Modifier::Super);
+    }
+    if ctrl {
+        modifiers.push(Modifier::Ctrl);
+    }
+    if alt {
+        modifiers.push(Modifier::Alt);
+    }
+    if shift {
+        modifiers.push(Modifier::Shift);
+    }
+
+    let key = match key_name {
+        "ArrowDown" => Key::Named(Named::ArrowDown),
+        "ArrowLeft" => Key::Named(Named::ArrowLeft),
+        "ArrowRight" => Key::Named(Named::ArrowRight),
+        "ArrowUp" => Key::Named(Named::ArrowUp),
+        "Backspace" => Key::Named(Named::Backspace),
+        "Delete" => Key::Named(Named::Delete),
+        "End" => Key::Named(Named::End),
+        "Enter" => Key::Named(Named::Enter),
+        "Escape" => Key::Named(Named::Escape),
+        "F1" => Key::Named(Named::F1),
+        "F2" => Key::Named(Named::F2),
+        "F3" => Key::Named(Named::F3),
+        "F4" => Key::Named(Named::F4),
+        "F5" => Key::Named(Named::F5),
+        "F6" => Key::Named(Named::F6),
+        "F7" => Key::Named(Named::F7),
+        "F8" => Key::Named(Named::F8),
+        "F9" => Key::Named(Named::F9),
+        "F10" => Key::Named(Named::F10),
+        "F11" => Key::Named(Named::F11),
+        "F12" => Key::Named(Named::F12),
+        "Home" => Key::Named(Named::Home),
+        "Insert" => Key::Named(Named::Insert),
+        "PageDown" => Key::Named(Named::PageDown),
+        "PageUp" => Key::Named(Named::PageUp),
+        "Space" => Key::Named(Named::Space),
+        "Tab" => Key::Named(Named::Tab),
+        _ if key_name.chars().count() == 1 => Key::Character(key_name.into()),
+        _ => return None,
+    };
+
+    Some(KeyBind { modifiers, key })
+}
+
+pub fn key_binds(config: &Config) -> HashMap<KeyBind, Action> {
     let mut key_binds = HashMap::new();
 
     macro_rules! bind {
-        ([$($modifier:ident),+ $(,)?], $key:expr, $action:ident) => {{
+        ([$($modifier:ident),* $(,)?], $key:expr, $action:ident) => {{
             key_binds.insert(
                 KeyBind {
-                    modifiers: vec![$(Modifier::$modifier),+],
+                    modifiers: vec![$(Modifier::$modifier),*],
                     key: $key,
                 },
                 Action::$action,
@@ -23,29 +89,81 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
     // Standard key bindings
     bind!([Ctrl, Shift], Key::Character("A".into()), SelectAll);
     bind!([Ctrl, Shift], Key::Character("C".into()), Copy);
+    bind!([Ctrl, Shift], Key::Character("E".into()), Hints);
     bind!([Ctrl, Shift], Key::Character("F".into()), Find);
     bind!([Ctrl, Shift], Key::Character("N".into()), WindowNew);
     bind!([Ctrl, Shift], Key::Character("Q".into()), WindowClose);
+    // Pausing output is only supported on Unix
+    #[cfg(unix)]
+    bind!([Ctrl, Shift], Key::Character("S".into()), TogglePause);
+    bind!([Ctrl, Shift], Key::Named(Named::Space), ToggleCopyMode);
     bind!([Ctrl, Shift], Key::Character("T".into()), TabNew);
+    bind!([Ctrl, Alt, Shift], Key::Character("T".into()), TabNewHere);
+    bind!([Ctrl, Shift], Key::Character("D".into()), TabDuplicate);
     bind!([Ctrl, Shift], Key::Character("V".into()), Paste);
     bind!([Shift], Key::Named(Named::Insert), PastePrimary);
     bind!([Ctrl, Shift], Key::Character("W".into()), TabClose);
     bind!([Ctrl], Key::Character(",".into()), Settings);
+    // Ctrl+Shift+F12 shows the performance overlay, used for debugging
+    bind!([Ctrl, Shift], Key::Named(Named::F12), TogglePerfOverlay);
+
+    // Ctrl+Shift+F11 toggles fullscreen, plain F11 is left to terminal programs
+    bind!([Ctrl, Shift], Key::Named(Named::F11), ToggleFullscreen);
+
+    // Ctrl+Shift+Z and Ctrl+Alt+Shift+Z jump between shell prompts, Ctrl+Shift+G copies the
+    // output of the last command
+    bind!([Ctrl, Shift], Key::Character("Z".into()), PromptPrevious);
+    bind!([Ctrl, Alt, Shift], Key::Character("Z".into()), PromptNext);
+    bind!([Ctrl, Shift], Key::Character("G".into()), CopyLastOutput);
+
+    // Ctrl+Shift+M marks a line, Ctrl+Shift+< and Ctrl+Shift+> jump between marks and prompts
+    bind!([Ctrl, Shift], Key::Character("M".into()), MarkToggle);
+    bind!([Ctrl, Shift], Key::Character("<".into()), MarkPrevious);
+    bind!([Ctrl, Shift], Key::Character(">".into()), MarkNext);
 
-    // Ctrl+Alt+D splits horizontally, Ctrl+Alt+R splits vertically, Ctrl+Shift+X maximizes split
+    // Ctrl+Shift+O opens the working directory in the file manager, Ctrl+Alt+Shift+C copies it
+    bind!(
+        [Ctrl, Shift],
+        Key::Character("O".into()),
+        OpenWorkingDirectory
+    );
+    bind!(
+        [Ctrl, Alt, Shift],
+        Key::Character("C".into()),
+        CopyWorkingDirectory
+    );
+
+    // Ctrl+Alt+B broadcasts input to every tab, Ctrl+Alt+Shift+B to the active tab of every pane
+    bind!([Ctrl, Alt], Key::Character("b".into()), BroadcastTabs);
+    bind!(
+        [Ctrl, Alt, Shift],
+        Key::Character("B".into()),
+        BroadcastPanes
+    );
+
+    // Ctrl+Alt+D splits horizontally, Ctrl+Alt+R splits vertically, Ctrl+Alt+W closes split,
+    // Ctrl+Shift+X maximizes split
     //TODO: Adjust bindings as desired by UX
     bind!([Ctrl, Alt], Key::Character("d".into()), PaneSplitHorizontal);
     bind!([Ctrl, Alt], Key::Character("r".into()), PaneSplitVertical);
+    bind!([Ctrl, Alt], Key::Character("w".into()), PaneClose);
     bind!(
         [Ctrl, Shift],
         Key::Character("X".into()),
         PaneToggleMaximized
     );
 
-    // Ctrl+Tab and Ctrl+Shift+Tab cycle through tabs
+    // Ctrl+Tab switches to the most recently used tab, pressing Tab again while holding Ctrl
+    // goes further back. Ctrl+Shift+Tab, Ctrl+PageUp and Ctrl+PageDown cycle through tabs in order.
     // Ctrl+Tab is not a special key for terminals and is free to use
-    bind!([Ctrl], Key::Named(Named::Tab), TabNext);
+    bind!([Ctrl], Key::Named(Named::Tab), TabRecent);
     bind!([Ctrl, Shift], Key::Named(Named::Tab), TabPrev);
+    bind!([Ctrl], Key::Named(Named::PageUp), TabPrev);
+    bind!([Ctrl], Key::Named(Named::PageDown), TabNext);
+
+    // Ctrl+Shift+PageUp and Ctrl+Shift+PageDown move the active tab
+    bind!([Ctrl, Shift], Key::Named(Named::PageUp), TabMoveLeft);
+    bind!([Ctrl, Shift], Key::Named(Named::PageDown), TabMoveRight);
 
     // Ctrl+Shift+# activates tabs by index
     bind!([Ctrl, Shift], Key::Character("!".into()), TabActivate0);
@@ -58,6 +176,9 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
     bind!([Ctrl, Shift], Key::Character("*".into()), TabActivate7);
     bind!([Ctrl, Shift], Key::Character("(".into()), TabActivate8);
 
+    // Alt+# is left to programs, such as readline numeric arguments and window switching in
+    // chat clients. It can be bound to TabActivate0 through TabActivate8 in the config.
+
     // Ctrl+0, Ctrl+-, and Ctrl+= are not special keys for terminals and are free to use
     bind!([Ctrl], Key::Character("0".into()), ZoomReset);
     bind!([Ctrl], Key::Character("-".into()), ZoomOut);
@@ -73,5 +194,22 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
     bind!([Ctrl, Shift], Key::Named(Named::ArrowRight), PaneFocusRight);
     bind!([Ctrl, Shift], Key::Character("L".into()), PaneFocusRight);
 
+    // Key bindings from config replace defaults with the same chord, and a chord bound to None
+    // is removed so that it is sent to the terminal instead
+    for (chord, action_opt) in config.key_binds.iter() {
+        let Some(key_bind) = parse_key_bind(chord) else {
+            log::warn!("failed to parse key binding {:?}", chord);
+            continue;
+        };
+        match action_opt {
+            Some(action) => {
+                key_binds.insert(key_bind, *action);
+            }
+            None => {
+                key_binds.remove(&key_bind);
+            }
+        }
+    }
+
     key_binds
 }
@@ -0,0 +1,342 @@
+// Kitty graphics protocol
+//
+// https://sw.kovidgoyal.net/kitty/graphics-protocol/
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use cosmic::iced::advanced::image;
+use flate2::read::ZlibDecoder;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    fs::{self, OpenOptions},
+    io::Read,
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+};
+
+use crate::shell_integration::{ImageCursor, ImageEvent, ShellState, TerminalImage};
+
+// Some programs leave out the base64 padding
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+// Images larger than this in either dimension are rejected
+const MAX_DIMENSION: u32 = 10_000;
+// Longest transmission that will be buffered, after decoding
+const MAX_LEN: usize = 64 * 1024 * 1024;
+// Files are never read from the kernel and device file systems
+const FORBIDDEN_DIRS: [&str; 3] = ["/proc", "/sys", "/dev"];
+// Transmitted images kept for later placement, the lowest IDs are dropped first
+const MAX_STORED_IMAGES: usize = 64;
+// Most columns or rows a placement may ask for, the terminal clamps it further to its size
+const MAX_CELLS: u32 = 1_000;
+
+#[derive(Clone, Debug)]
+struct StoredImage {
+    handle: image::Handle,
+    width: u32,
+    height: u32,
+}
+
+/// Control data of a command, like `a=T,f=100,i=1`
+#[derive(Debug, Default)]
+struct Controls(HashMap<u8, String>);
+
+impl Controls {
+    fn parse(data: &[u8]) -> Self {
+        let mut controls = HashMap::new();
+        for pair in data.split(|&b| b == b',') {
+            if let [key, b'=', value @ ..] = pair {
+                controls.insert(*key, String::from_utf8_lossy(value).into_owned());
+            }
+        }
+        Self(controls)
+    }
+
+    fn char(&self, key: u8) -> Option<char> {
+        self.0.get(&key)?.chars().next()
+    }
+
+    fn number(&self, key: u8) -> Option<u32> {
+        self.0.get(&key)?.parse().ok()
+    }
+}
+
+// Read the size from the header of a PNG image
+fn png_size(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(..8)? != b"\x89PNG\r\n\x1A\n" || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+// Read an image from a regular file, up to the transmission limit. The path must already be
+// canonical so links cannot lead into forbidden directories.
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    if FORBIDDEN_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return Err("EPERM:file not allowed".to_string());
+    }
+    // Check before opening, as opening a FIFO would block, then again on the opened file in
+    // case the path was replaced in between
+    let not_regular = || "EPERM:not a regular file".to_string();
+    if !fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+        return Err(not_regular());
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .map_err(|err| format!("EBADF:{}", err))?;
+    if !file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return Err(not_regular());
+    }
+    let mut contents = Vec::new();
+    file.take(MAX_LEN as u64)
+        .read_to_end(&mut contents)
+        .map_err(|err| format!("EBADF:{}", err))?;
+    Ok(contents)
+}
+
+/// Images transmitted by a program, and any transmission that is still being received
+#[derive(Debug, Default)]
+pub struct KittyGraphics {
+    images: BTreeMap<u32, StoredImage>,
+    // Controls of the first chunk and the base64 payload received so far
+    chunked_opt: Option<(Controls, Vec<u8>)>,
+}
+
+impl KittyGraphics {
+    /// Handle a command, which follows the `G` of the APC sequence
+    pub fn command(&mut self, command: &[u8], shell_state: &mut ShellState, output: &mut Vec<u8>) {
+        let (control_data, payload) = match command.iter().position(|&b| b == b';') {
+            Some(i) => (&command[..i], &command[i + 1..]),
+            None => (command, &[][..]),
+        };
+        let mut controls = Controls::parse(control_data);
+        let mut payload = payload.to_vec();
+
+        // Chunks after the first only have the m key, which is 1 until the last chunk
+        let more = controls.number(b'm') == Some(1);
+        if let Some((first_controls, mut data)) = self.chunked_opt.take() {
+            if data.len() + payload.len() <= MAX_LEN / 3 * 4 {
+                data.extend_from_slice(&payload);
+            }
+            if more {
+                self.chunked_opt = Some((first_controls, data));
+                return;
+            }
+            controls = first_controls;
+            payload = data;
+        } else if more {
+            self.chunked_opt = Some((controls, payload));
+            return;
+        }
+
+        let id_opt = controls.number(b'i');
+        let result = match controls.char(b'a').unwrap_or('t') {
+            // Transmit
+            't' => self.load(&controls, &payload).map(|image| {
+                if let Some(id) = id_opt {
+                    self.store(id, image);
+                }
+            }),
+            // Transmit and place
+            'T' => self.load(&controls, &payload).map(|image| {
+                place(&controls, id_opt, &image, shell_state, output);
+                if let Some(id) = id_opt {
+                    self.store(id, image);
+                }
+            }),
+            // Place a previously transmitted image
+            'p' => match id_opt.and_then(|id| self.images.get(&id)) {
+                Some(image) => {
+                    place(&controls, id_opt, image, shell_state, output);
+                    Ok(())
+                }
+                None => Err("ENOENT:image not found".to_string()),
+            },
+            // Check if an image would load, used to detect support for the protocol
+            'q' => self.load(&controls, &payload).map(|_| ()),
+            'd' => {
+                self.delete(&controls, shell_state);
+                return;
+            }
+            action => Err(format!("EINVAL:unsupported action {}", action)),
+        };
+
+        // Replies are only sent for commands with an image ID, and can be silenced
+        if let Some(id) = id_opt {
+            let quiet = controls.number(b'q').unwrap_or(0);
+            let message_opt = match result {
+                Ok(()) if quiet < 1 => Some("OK".to_string()),
+                Err(err) if quiet < 2 => Some(err),
+                _ => None,
+            };
+            if let Some(message) = message_opt {
+                shell_state.push_response(format!("\x1B_Gi={};{}\x1B\\", id, message).into_bytes());
+            }
+        }
+    }
+
+    fn load(&self, controls: &Controls, payload: &[u8]) -> Result<StoredImage, String> {
+        let data = BASE64
+            .decode(payload)
+            .map_err(|err| format!("EINVAL:{}", err))?;
+
+        let medium = controls.char(b't').unwrap_or('d');
+        let data = match medium {
+            'd' => data,
+            'f' | 't' => {
+                let path =
+                    String::from_utf8(data).map_err(|_| "EINVAL:invalid file path".to_string())?;
+                let path = fs::canonicalize(path).map_err(|err| format!("EBADF:{}", err))?;
+                let contents = read_file(&path)?;
+                // Temporary files are removed once read, as the protocol asks, but only from
+                // the temporary directory itself
+                let in_temp_dir = fs::canonicalize(env::temp_dir())
+                    .is_ok_and(|temp_dir| path.parent() == Some(temp_dir.as_path()));
+                let is_temp_file = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains("tty-graphics-protocol"));
+                if medium == 't' && in_temp_dir && is_temp_file {
+                    let _ = fs::remove_file(&path);
+                }
+                contents
+            }
+            _ => return Err(format!("EINVAL:unsupported transmission medium {}", medium)),
+        };
+
+        let data = if controls.char(b'o') == Some('z') {
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(data.as_slice())
+                .take(MAX_LEN as u64)
+                .read_to_end(&mut decompressed)
+                .map_err(|err| format!("EINVAL:{}", err))?;
+            decompressed
+        } else {
+            data
+        };
+
+        let format = controls.number(b'f').unwrap_or(32);
+        let (width, height) = match format {
+            100 => png_size(&data).ok_or_else(|| "EBADPNG:invalid PNG image".to_string())?,
+            _ => (
+                controls.number(b's').unwrap_or(0),
+                controls.number(b'v').unwrap_or(0),
+            ),
+        };
+        if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(format!("EINVAL:invalid image size {}x{}", width, height));
+        }
+
+        let handle = match format {
+            100 => image::Handle::from_memory(data),
+            24 | 32 => {
+                let pixel_count = width as usize * height as usize;
+                let bytes_per_pixel = if format == 24 { 3 } else { 4 };
+                if data.len() < pixel_count * bytes_per_pixel {
+                    return Err("ENODATA:insufficient image data".to_string());
+                }
+                let pixels = if format == 24 {
+                    data[..pixel_count * 3]
+                        .chunks_exact(3)
+                        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                        .collect()
+                } else {
+                    let mut data = data;
+                    data.truncate(pixel_count * 4);
+                    data
+                };
+                image::Handle::from_pixels(width, height, pixels)
+            }
+            _ => return Err(format!("EINVAL:unsupported format {}", format)),
+        };
+
+        Ok(StoredImage {
+            handle,
+            width,
+            height,
+        })
+    }
+
+    fn store(&mut self, id: u32, image: StoredImage) {
+        self.images.insert(id, image);
+        while self.images.len() > MAX_STORED_IMAGES {
+            self.images.pop_first();
+        }
+    }
+
+    fn delete(&mut self, controls: &Controls, shell_state: &mut ShellState) {
+        // Uppercase variants also free the transmitted image data
+        match controls.char(b'd').unwrap_or('a') {
+            'a' => shell_state.push_image_event(ImageEvent::DeleteKitty(None)),
+            'A' => {
+                self.images.clear();
+                shell_state.push_image_event(ImageEvent::DeleteKitty(None));
+            }
+            'i' | 'I' => {
+                if let Some(id) = controls.number(b'i') {
+                    if controls.char(b'd') == Some('I') {
+                        self.images.remove(&id);
+                    }
+                    shell_state.push_image_event(ImageEvent::DeleteKitty(Some(id)));
+                }
+            }
+            delete => log::debug!("unsupported kitty graphics delete {:?}", delete),
+        }
+    }
+}
+
+// Place an image at the cursor, scaled to the requested number of columns and rows
+fn place(
+    controls: &Controls,
+    id_opt: Option<u32>,
+    image: &StoredImage,
+    shell_state: &mut ShellState,
+    output: &mut Vec<u8>,
+) {
+    let (cell_width, cell_height) = shell_state.cell_size();
+    let aspect = image.width as f32 / image.height as f32;
+    let columns_opt = controls
+        .number(b'c')
+        .filter(|columns| *columns > 0)
+        .map(|columns| columns.min(MAX_CELLS));
+    let rows_opt = controls
+        .number(b'r')
+        .filter(|rows| *rows > 0)
+        .map(|rows| rows.min(MAX_CELLS));
+    let (width, height) = match (columns_opt, rows_opt) {
+        (Some(columns), Some(rows)) => (columns as f32 * cell_width, rows as f32 * cell_height),
+        (Some(columns), None) => {
+            let width = columns as f32 * cell_width;
+            (width, width / aspect)
+        }
+        (None, Some(rows)) => {
+            let height = rows as f32 * cell_height;
+            (height * aspect, height)
+        }
+        (None, None) => (image.width as f32, image.height as f32),
+    };
+    let cursor = if controls.number(b'C') == Some(1) {
+        ImageCursor::Unchanged
+    } else {
+        ImageCursor::After
+    };
+    shell_state.place_image(
+        TerminalImage {
+            kitty_id_opt: id_opt,
+            handle: image.handle.clone(),
+            width: (width.round() as u32).max(1),
+            height: (height.round() as u32).max(1),
+        },
+        cursor,
+        output,
+    );
+}
@@ -0,0 +1,146 @@
+// Kitty keyboard protocol
+//
+// https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+
+use alacritty_terminal::term::TermMode;
+use cosmic::iced::keyboard::{key::Named, Key, Location, Modifiers};
+
+// Functional keys without a legacy encoding use code points from this private use area
+const PRIVATE_USE_START: u32 = 57344;
+
+// Code and final byte of a functional key
+fn functional_key(named: Named, location: Location) -> Option<(u32, char)> {
+    let right = location == Location::Right;
+    Some(match named {
+        Named::Escape => (27, 'u'),
+        Named::Enter => (13, 'u'),
+        Named::Tab => (9, 'u'),
+        Named::Backspace => (127, 'u'),
+        Named::Space => (32, 'u'),
+        Named::Insert => (2, '~'),
+        Named::Delete => (3, '~'),
+        Named::ArrowLeft => (1, 'D'),
+        Named::ArrowRight => (1, 'C'),
+        Named::ArrowUp => (1, 'A'),
+        Named::ArrowDown => (1, 'B'),
+        Named::PageUp => (5, '~'),
+        Named::PageDown => (6, '~'),
+        Named::Home => (1, 'H'),
+        Named::End => (1, 'F'),
+        Named::CapsLock => (57358, 'u'),
+        Named::ScrollLock => (57359, 'u'),
+        Named::NumLock => (57360, 'u'),
+        Named::PrintScreen => (57361, 'u'),
+        Named::Pause => (57362, 'u'),
+        Named::ContextMenu => (57363, 'u'),
+        Named::F1 => (1, 'P'),
+        Named::F2 => (1, 'Q'),
+        Named::F3 => (13, '~'),
+        Named::F4 => (1, 'S'),
+        Named::F5 => (15, '~'),
+        Named::F6 => (17, '~'),
+        Named::F7 => (18, '~'),
+        Named::F8 => (19, '~'),
+        Named::F9 => (20, '~'),
+        Named::F10 => (21, '~'),
+        Named::F11 => (23, '~'),
+        Named::F12 => (24, '~'),
+        Named::Shift => (if right { 57447 } else { 57441 }, 'u'),
+        Named::Control => (if right { 57448 } else { 57442 }, 'u'),
+        Named::Alt => (if right { 57449 } else { 57443 }, 'u'),
+        Named::Super => (if right { 57450 } else { 57444 }, 'u'),
+        _ => return None,
+    })
+}
+
+/// Encode a key event for a program that enabled the kitty keyboard protocol. Returns None
+/// when the key keeps its legacy encoding with the current flags.
+pub fn encode(
+    key: &Key,
+    location: Location,
+    modifiers: Modifiers,
+    text_opt: Option<&str>,
+    release: bool,
+    mode: TermMode,
+) -> Option<Vec<u8>> {
+    if release && !mode.contains(TermMode::REPORT_EVENT_TYPES) {
+        return None;
+    }
+
+    let mut mod_no = 0;
+    if modifiers.shift() {
+        mod_no |= 1;
+    }
+    if modifiers.alt() {
+        mod_no |= 2;
+    }
+    if modifiers.control() {
+        mod_no |= 4;
+    }
+    if modifiers.logo() {
+        mod_no |= 8;
+    }
+
+    // Text keys are reported by their unshifted code point, with the shifted one as an alternate
+    let (code, shifted_opt, suffix) = match key {
+        Key::Character(c) => {
+            let c = c.chars().next()?;
+            let base = c.to_lowercase().next().unwrap_or(c);
+            let shifted_opt = Some(c as u32).filter(|_| base != c && modifiers.shift());
+            (base as u32, shifted_opt, 'u')
+        }
+        Key::Named(named) => {
+            let (code, suffix) = functional_key(*named, location)?;
+            (code, None, suffix)
+        }
+        _ => return None,
+    };
+
+    let use_protocol = if mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) {
+        true
+    } else {
+        match key {
+            // Left alone so that a shell stays usable if a program exits without resetting the flags
+            Key::Named(Named::Enter | Named::Tab | Named::Backspace) => !release && mod_no != 0,
+            Key::Named(Named::Escape) => true,
+            // Shifted text is still sent as text
+            Key::Character(_) | Key::Named(Named::Space) => release || mod_no & !1 != 0,
+            // Lock and modifier keys are only reported along with all other keys
+            _ if code >= PRIVATE_USE_START => false,
+            _ => release,
+        }
+    };
+    if !use_protocol {
+        return None;
+    }
+
+    let text_opt = text_opt.filter(|text| {
+        !release
+            && mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC | TermMode::REPORT_ASSOCIATED_TEXT)
+            && !text.is_empty()
+            && !text.chars().any(char::is_control)
+    });
+
+    let mut sequence = String::from("\x1B[");
+    let has_params = mod_no != 0 || release || text_opt.is_some();
+    if suffix == 'u' || suffix == '~' || has_params {
+        sequence.push_str(&code.to_string());
+    }
+    if let Some(shifted) = shifted_opt {
+        if mode.contains(TermMode::REPORT_ALTERNATE_KEYS) {
+            sequence.push_str(&format!(":{}", shifted));
+        }
+    }
+    if has_params {
+        sequence.push_str(&format!(";{}", mod_no + 1));
+        if release {
+            sequence.push_str(":3");
+        }
+    }
+    if let Some(text) = text_opt {
+        let code_points: Vec<String> = text.chars().map(|c| (c as u32).to_string()).collect();
+        sequence.push_str(&format!(";{}", code_points.join(":")));
+    }
+    sequence.push(suffix);
+    Some(sequence.into_bytes())
+}
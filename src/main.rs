@@ -5,38 +5,54 @@ use alacritty_terminal::{event::Event as TermEvent, term, term::color::Colors as
 use cosmic::widget::menu::action::MenuAction;
 use cosmic::widget::menu::key_bind::KeyBind;
 use cosmic::{
-    app::{message, Command, Core, Settings},
-    cosmic_config::{self, ConfigSet, CosmicConfigEntry},
+    app::{message, Command, Core, DbusActivationDetails, DbusActivationMessage, Settings},
+    cosmic_config::{self, ConfigGet, ConfigSet, CosmicConfigEntry},
     cosmic_theme, executor,
     iced::{
         self,
         advanced::graphics::text::font_system,
-        clipboard, event,
+        clipboard::{self, mime::AsMimeTypes},
+        event,
         futures::SinkExt,
         keyboard::{Event as KeyEvent, Key, Modifiers},
         mouse::{Button as MouseButton, Event as MouseEvent},
         subscription::{self, Subscription},
-        window, Alignment, Color, Event, Length, Limits, Padding, Point,
+        window, Alignment, Background, Color, Event, Length, Limits, Padding, Point, Size,
     },
+    iced_core::Border,
     style,
     widget::{self, button, pane_grid, segmented_button, PaneGrid},
     Application, ApplicationExt, Element,
 };
 use cosmic_files::dialog::{Dialog, DialogKind, DialogMessage, DialogResult};
 use cosmic_text::{fontdb::FaceInfo, Family, Stretch, Weight};
+use hex_color::HexColor;
+use serde::{Deserialize, Serialize};
 use std::{
     any::TypeId,
+    borrow::Cow,
     cmp,
     collections::{BTreeMap, BTreeSet, HashMap},
-    env, fs, process,
-    sync::{atomic::Ordering, Mutex},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 use tokio::sync::mpsc;
 
 use config::{
-    AppTheme, ColorScheme, ColorSchemeId, ColorSchemeKind, Config, Profile, ProfileId,
-    CONFIG_VERSION,
+    AppTheme, BackgroundImageScale, ColorScheme, ColorSchemeField, ColorSchemeId, ColorSchemeKind,
+    Config, CursorShape, ExitAction, HintAction, LastTabClose, OutputLogFormat, PasteFilter,
+    Profile, ProfileId, SerialParity, Session, SessionTab, TabBarPosition, TabBarVisibility,
+    TriggerAction, WindowState, CONFIG_VERSION, SERIAL_BAUD_RATES,
 };
+mod box_drawing;
+
 mod config;
 mod mouse_reporter;
 
@@ -46,19 +62,71 @@ mod icon_cache;
 use key_bind::key_binds;
 mod key_bind;
 
+#[cfg(unix)]
+mod kitty_graphics;
+
+mod kitty_keyboard;
+
 mod localize;
 
 use menu::menu_bar;
 mod menu;
 
-use terminal::{Terminal, TerminalPaneGrid, TerminalScroll};
+use notification::Notifier;
+mod notification;
+
+mod output_log;
+
+#[cfg(unix)]
+use playback::{PlaybackControl, PlaybackEvent, PlaybackPty};
+#[cfg(unix)]
+mod playback;
+
+mod recording;
+
+mod scrollback_archive;
+
+#[cfg(unix)]
+mod serial;
+
+mod shell_integration;
+
+mod sixel;
+
+use terminal::{
+    FileRef, PerfStats, RemoteConnection, RemoteState, Terminal, TerminalPaneGrid, TerminalScroll,
+    TerminalSpawnError,
+};
 mod terminal;
 
-use terminal_box::terminal_box;
+use terminal_box::{terminal_box, KeyInput};
 mod terminal_box;
 
 mod terminal_theme;
 
+mod trigger;
+
+// How long the zoom level is shown after changing it
+const ZOOM_INDICATOR_DURATION: Duration = Duration::from_secs(1);
+// How long the terminal flashes for a visual bell
+const BELL_FLASH_DURATION: Duration = Duration::from_millis(150);
+const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+// Longest time between two clicks on a tab to rename it
+const TAB_DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+// How often tab titles are updated from the running process
+const PROCESS_TITLE_INTERVAL: Duration = Duration::from_secs(1);
+// Delay before restarting a command that exited, doubled while it keeps exiting within
+// the longest delay
+const RESTART_DELAY_MIN: Duration = Duration::from_secs(1);
+const RESTART_DELAY_MAX: Duration = Duration::from_secs(30);
+// Speeds offered for playing recordings
+#[cfg(unix)]
+const PLAYBACK_SPEEDS: [f64; 5] = [0.5, 1.0, 1.5, 2.0, 4.0];
+// How often the performance overlay is refreshed, its rates are per this interval
+const PERF_INTERVAL: Duration = Duration::from_secs(1);
+// Lines of pasted text shown when asking to confirm a paste
+const PASTE_PREVIEW_LINES: usize = 8;
+
 lazy_static::lazy_static! {
     static ref ICON_CACHE: Mutex<IconCache> = Mutex::new(IconCache::new());
 }
@@ -87,13 +155,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (config_handler, config) = match cosmic_config::Config::new(App::APP_ID, CONFIG_VERSION) {
         Ok(config_handler) => {
-            let config = match Config::get_entry(&config_handler) {
+            let mut config = match Config::get_entry(&config_handler) {
                 Ok(ok) => ok,
                 Err((errs, config)) => {
                     log::info!("errors loading config: {:?}", errs);
                     config
                 }
             };
+            config.migrate();
             (Some(config_handler), config)
         }
         Err(err) => {
@@ -102,20 +171,101 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    let (state_handler, session_opt, window_state_opt) =
+        match cosmic_config::Config::new_state(App::APP_ID, CONFIG_VERSION) {
+            Ok(state_handler) => {
+                let session_opt = if config.restore_session {
+                    state_handler
+                        .get::<Session>("session")
+                        .ok()
+                        .filter(|session| !session.tabs.is_empty())
+                } else {
+                    None
+                };
+                // This window offers the saved tabs, windows closed from now on save theirs anew
+                if session_opt.is_some() {
+                    if let Err(err) = state_handler.set("session", Session::default()) {
+                        log::error!("failed to clear session: {}", err);
+                    }
+                }
+                let window_state_opt = state_handler.get::<WindowState>("window").ok();
+                (Some(state_handler), session_opt, window_state_opt)
+            }
+            Err(err) => {
+                log::error!("failed to create state handler: {}", err);
+                (None, None, None)
+            }
+        };
+
     let mut shell_program_opt = None;
     let mut shell_args = Vec::new();
+    let mut working_directory_opt = None;
+    let mut startup_title_opt = None;
+    let mut perf_overlay = false;
+    let mut new_window = false;
+    let mut separate = false;
+    let mut maximized = false;
+    let mut fullscreen = false;
+    #[cfg(unix)]
+    let mut playback_opt: Option<PathBuf> = None;
     let mut parse_flags = true;
-    for arg in env::args().skip(1) {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
         if parse_flags {
-            match arg.as_str() {
-                // These flags indicate the end of parsing flags
-                "-e" | "--command" | "--" => {
-                    parse_flags = false;
+            match arg.split_once('=') {
+                Some(("--working-directory", value)) => {
+                    working_directory_opt = Some(value.into());
                 }
-                _ => {
-                    //TODO: should this throw an error?
-                    log::warn!("ignored argument {:?}", arg);
+                Some(("--title", value)) => {
+                    startup_title_opt = Some(value.to_string());
                 }
+                #[cfg(unix)]
+                Some(("--play", value)) => {
+                    playback_opt = Some(value.into());
+                }
+                _ => match arg.as_str() {
+                    // These flags indicate the end of parsing flags
+                    "-e" | "--command" | "--" => {
+                        parse_flags = false;
+                    }
+                    "--working-directory" => match args.next() {
+                        Some(value) => working_directory_opt = Some(value.into()),
+                        None => log::warn!("missing value for {:?}", arg),
+                    },
+                    "--title" => match args.next() {
+                        Some(value) => startup_title_opt = Some(value),
+                        None => log::warn!("missing value for {:?}", arg),
+                    },
+                    #[cfg(unix)]
+                    "--play" => match args.next() {
+                        Some(value) => playback_opt = Some(value.into()),
+                        None => log::warn!("missing value for {:?}", arg),
+                    },
+                    "--perf" => {
+                        perf_overlay = true;
+                    }
+                    "--maximized" => {
+                        maximized = true;
+                    }
+                    "--fullscreen" => {
+                        fullscreen = true;
+                    }
+                    // Where a running instance opens the terminal, a tab is the default
+                    "--new-tab" => {
+                        new_window = false;
+                    }
+                    "--new-window" => {
+                        new_window = true;
+                    }
+                    // Run in a new process even if an instance is running
+                    "--separate" => {
+                        separate = true;
+                    }
+                    _ => {
+                        //TODO: should this throw an error?
+                        log::warn!("ignored argument {:?}", arg);
+                    }
+                },
             }
         } else if shell_program_opt.is_none() {
             shell_program_opt = Some(arg);
@@ -124,9 +274,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    let startup_options = if let Some(shell_program) = shell_program_opt {
+    // The running instance has its own current directory, so relative paths are resolved here
+    let working_directory_opt = working_directory_opt
+        .map(|path: PathBuf| fs::canonicalize(&path).unwrap_or(path));
+
+    // Sent to an instance that is already running, which opens the terminal instead
+    let launch_request = LaunchRequest {
+        new_window,
+        current_dir_opt: env::current_dir().ok(),
+        working_directory_opt: working_directory_opt.clone(),
+        title_opt: startup_title_opt.clone(),
+        command: shell_program_opt
+            .iter()
+            .chain(shell_args.iter())
+            .cloned()
+            .collect(),
+    };
+    let launch_action = match ron::to_string(&launch_request) {
+        Ok(launch_action) => launch_action,
+        Err(err) => {
+            log::error!("failed to serialize launch request: {}", err);
+            String::new()
+        }
+    };
+
+    let startup_options = if shell_program_opt.is_some() || working_directory_opt.is_some() {
         let options = tty::Options {
-            shell: Some(tty::Shell::new(shell_program, shell_args)),
+            shell: shell_program_opt
+                .map(|shell_program| tty::Shell::new(shell_program, shell_args)),
+            working_directory: working_directory_opt,
             ..tty::Options::default()
         };
         Some(options)
@@ -134,7 +310,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
-    let term_config = term::Config::default();
+    let term_config = term::Config {
+        scrolling_history: config.scrollback_lines as usize,
+        default_cursor_style: config.cursor_style(),
+        semantic_escape_chars: config.word_separators.clone(),
+        kitty_keyboard: true,
+        ..term::Config::default()
+    };
     // Set up environmental variables for terminal
     tty::setup_env();
     // Override TERM for better compatibility
@@ -144,13 +326,43 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     settings = settings.theme(config.app_theme.theme());
     settings = settings.size_limits(Limits::NONE.min_width(360.0).min_height(180.0));
 
+    // The window opens as it was last closed
+    let mut window_size = Size::new(1024.0, 768.0);
+    if let Some(window_state) = window_state_opt {
+        window_size = Size::new(
+            (window_state.width as f32).max(360.0),
+            (window_state.height as f32).max(180.0),
+        );
+        maximized |= window_state.maximized;
+    }
+    settings = settings.size(window_size);
+
+    // Recordings are played in a window of their own
+    #[cfg(unix)]
+    let playing = playback_opt.is_some();
+    #[cfg(not(unix))]
+    let playing = false;
     let flags = Flags {
         config_handler,
         config,
+        state_handler,
+        session_opt,
         startup_options,
+        startup_title_opt,
         term_config,
+        perf_overlay,
+        launch_action,
+        #[cfg(unix)]
+        playback_opt,
+        window_size,
+        maximized,
+        fullscreen,
     };
-    cosmic::app::run::<App>(settings, flags)?;
+    if separate || playing {
+        cosmic::app::run::<App>(settings, flags)?;
+    } else {
+        cosmic::app::run_single_instance::<App>(settings, flags)?;
+    }
 
     Ok(())
 }
@@ -159,17 +371,61 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 pub struct Flags {
     config_handler: Option<cosmic_config::Config>,
     config: Config,
+    state_handler: Option<cosmic_config::Config>,
+    session_opt: Option<Session>,
     startup_options: Option<tty::Options>,
+    startup_title_opt: Option<String>,
     term_config: term::Config,
+    perf_overlay: bool,
+    // Serialized LaunchRequest
+    launch_action: String,
+    // Recording played in the first tab
+    #[cfg(unix)]
+    playback_opt: Option<PathBuf>,
+    window_size: Size,
+    maximized: bool,
+    fullscreen: bool,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+impl cosmic::app::CosmicFlags for Flags {
+    type SubCommand = String;
+    type Args = Vec<String>;
+
+    fn action(&self) -> Option<&String> {
+        Some(&self.launch_action)
+    }
+}
+
+/// Terminal requested on the command line, opened by the running instance if there is one
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LaunchRequest {
+    new_window: bool,
+    // Directory the launching process ran in, where relative paths in the command point to
+    current_dir_opt: Option<PathBuf>,
+    working_directory_opt: Option<PathBuf>,
+    title_opt: Option<String>,
+    command: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Action {
     About,
+    BroadcastPanes,
+    BroadcastTabs,
     ColorSchemes(ColorSchemeKind),
     Copy,
+    CopyLastOutput,
     CopyPrimary,
+    CopyWorkingDirectory,
+    ExportScrollback { html: bool },
     Find,
+    Hints,
+    LogOutput,
+    MarkNext,
+    MarkPrevious,
+    MarkToggle,
+    OpenWorkingDirectory,
+    PaneClose,
     PaneFocusDown,
     PaneFocusLeft,
     PaneFocusRight,
@@ -181,6 +437,9 @@ pub enum Action {
     PastePrimary,
     ProfileOpen(ProfileId),
     Profiles,
+    PromptNext,
+    PromptPrevious,
+    RecordSession,
     SelectAll,
     Settings,
     ShowHeaderBar(bool),
@@ -194,10 +453,26 @@ pub enum Action {
     TabActivate7,
     TabActivate8,
     TabClose,
+    TabCloseOthers,
+    TabCloseRight,
+    TabDuplicate,
+    TabMoveLeft,
+    TabMoveRight,
     TabNew,
+    TabNewHere,
     TabNext,
+    TabOpenInWindow,
     TabPrev,
+    TabRecent,
+    TabRename,
+    ToggleCopyMode,
+    ToggleFullscreen,
+    TogglePause,
+    TogglePerfOverlay,
+    ToggleReadOnly,
     WindowClose,
+    WindowMaximize,
+    WindowMinimize,
     WindowNew,
     ZoomIn,
     ZoomOut,
@@ -210,12 +485,24 @@ impl MenuAction for Action {
     fn message(&self, entity_opt: Option<segmented_button::Entity>) -> Message {
         match self {
             Self::About => Message::ToggleContextPage(ContextPage::About),
+            Self::BroadcastPanes => Message::BroadcastToggle(Broadcast::Panes),
+            Self::BroadcastTabs => Message::BroadcastToggle(Broadcast::Tabs),
             Self::ColorSchemes(color_scheme_kind) => {
                 Message::ToggleContextPage(ContextPage::ColorSchemes(*color_scheme_kind))
             }
             Self::Copy => Message::Copy(entity_opt),
+            Self::CopyLastOutput => Message::CopyLastOutput(entity_opt),
             Self::CopyPrimary => Message::CopyPrimary(entity_opt),
+            Self::CopyWorkingDirectory => Message::CopyWorkingDirectory(entity_opt),
+            Self::ExportScrollback { html } => Message::ExportScrollback(entity_opt, *html),
             Self::Find => Message::Find(true),
+            Self::Hints => Message::Hints(entity_opt),
+            Self::LogOutput => Message::LogOutput(entity_opt),
+            Self::MarkNext => Message::MarkJump(entity_opt, false),
+            Self::MarkPrevious => Message::MarkJump(entity_opt, true),
+            Self::MarkToggle => Message::MarkToggle(entity_opt),
+            Self::OpenWorkingDirectory => Message::OpenWorkingDirectory(entity_opt),
+            Self::PaneClose => Message::PaneClose,
             Self::PaneFocusDown => Message::PaneFocusAdjacent(pane_grid::Direction::Down),
             Self::PaneFocusLeft => Message::PaneFocusAdjacent(pane_grid::Direction::Left),
             Self::PaneFocusRight => Message::PaneFocusAdjacent(pane_grid::Direction::Right),
@@ -227,6 +514,9 @@ impl MenuAction for Action {
             Self::PastePrimary => Message::PastePrimary(entity_opt),
             Self::ProfileOpen(profile_id) => Message::ProfileOpen(*profile_id),
             Self::Profiles => Message::ToggleContextPage(ContextPage::Profiles),
+            Self::PromptNext => Message::PromptJump(entity_opt, false),
+            Self::PromptPrevious => Message::PromptJump(entity_opt, true),
+            Self::RecordSession => Message::RecordSession(entity_opt),
             Self::SelectAll => Message::SelectAll(entity_opt),
             Self::Settings => Message::ToggleContextPage(ContextPage::Settings),
             Self::ShowHeaderBar(show_headerbar) => Message::ShowHeaderBar(*show_headerbar),
@@ -240,10 +530,26 @@ impl MenuAction for Action {
             Self::TabActivate7 => Message::TabActivateJump(7),
             Self::TabActivate8 => Message::TabActivateJump(8),
             Self::TabClose => Message::TabClose(entity_opt),
-            Self::TabNew => Message::TabNew,
+            Self::TabCloseOthers => Message::TabCloseOthers(entity_opt),
+            Self::TabCloseRight => Message::TabCloseRight(entity_opt),
+            Self::TabDuplicate => Message::TabDuplicate(entity_opt),
+            Self::TabMoveLeft => Message::TabMoveLeft,
+            Self::TabMoveRight => Message::TabMoveRight,
+            Self::TabNew => Message::TabNew { inherit_cwd: false },
+            Self::TabNewHere => Message::TabNew { inherit_cwd: true },
             Self::TabNext => Message::TabNext,
+            Self::TabOpenInWindow => Message::TabOpenInWindow(entity_opt),
             Self::TabPrev => Message::TabPrev,
+            Self::TabRecent => Message::TabRecent,
+            Self::TabRename => Message::TabRename(entity_opt),
+            Self::ToggleCopyMode => Message::ToggleCopyMode(entity_opt),
+            Self::ToggleFullscreen => Message::ToggleFullscreen,
+            Self::TogglePause => Message::TogglePause(entity_opt),
+            Self::TogglePerfOverlay => Message::TogglePerfOverlay,
+            Self::ToggleReadOnly => Message::ToggleReadOnly(entity_opt),
             Self::WindowClose => Message::WindowClose,
+            Self::WindowMaximize => Message::WindowMaximize,
+            Self::WindowMinimize => Message::WindowMinimize,
             Self::WindowNew => Message::WindowNew,
             Self::ZoomIn => Message::ZoomIn,
             Self::ZoomOut => Message::ZoomOut,
@@ -255,84 +561,260 @@ impl MenuAction for Action {
 /// Messages that are used specifically by our [`App`].
 #[derive(Clone, Debug)]
 pub enum Message {
+    AlternateScroll(bool),
     AppTheme(AppTheme),
+    BellAudible(bool),
+    BellFlashTick,
+    BellTabBadge(bool),
+    BellUrgent(bool),
+    BellVisual(bool),
+    BlinkTick,
+    BroadcastInput(pane_grid::Pane, segmented_button::Entity, KeyInput),
+    BroadcastToggle(Broadcast),
+    ClipboardRead(bool),
     ColorSchemeCollapse,
     ColorSchemeDelete(ColorSchemeKind, ColorSchemeId),
+    ColorSchemeEdit(ColorSchemeKind, ColorSchemeId),
+    ColorSchemeEditColor(ColorSchemeField, String),
+    ColorSchemeEditDone,
     ColorSchemeExpand(ColorSchemeKind, ColorSchemeId),
     ColorSchemeExport(ColorSchemeKind, ColorSchemeId),
     ColorSchemeExportResult(ColorSchemeKind, ColorSchemeId, DialogResult),
     ColorSchemeImport(ColorSchemeKind),
     ColorSchemeImportResult(ColorSchemeKind, DialogResult),
+    ColorSchemeNew(ColorSchemeKind),
     ColorSchemeRename(ColorSchemeKind, ColorSchemeId, String),
     ColorSchemeRenameSubmit,
     ColorSchemeTabActivate(widget::segmented_button::Entity),
+    CommandNotify(usize),
     Config(Config),
+    ConfirmCloseRunning(bool),
     Copy(Option<segmented_button::Entity>),
+    CopyHtml(bool),
+    CopyLastOutput(Option<segmented_button::Entity>),
+    CopyModeYank(segmented_button::Entity),
+    CopyOnSelect(bool),
     CopyPrimary(Option<segmented_button::Entity>),
+    CopyText(Option<String>),
+    CopyWorkingDirectory(Option<segmented_button::Entity>),
+    CursorBlink(bool),
+    CursorColor(String),
+    CursorShape(CursorShape),
+    CursorTextColor(String),
     DefaultBoldFontWeight(usize),
     DefaultDimFontWeight(usize),
     DefaultFont(usize),
     DefaultFontSize(usize),
     DefaultFontStretch(usize),
     DefaultFontWeight(usize),
+    DefaultLineHeight(usize),
     DefaultZoomStep(usize),
     DialogMessage(DialogMessage),
+    ExportScrollback(Option<segmented_button::Entity>, bool),
+    ExportScrollbackResult(segmented_button::Entity, bool, DialogResult),
+    FileDropped(PathBuf),
+    FileRefCommand(String),
     Find(bool),
+    FindCaseSensitive(bool),
     FindNext,
     FindPrevious,
+    FindRegex(bool),
     FindSearchValueChanged(String),
     MiddleClick(pane_grid::Pane, Option<segmented_button::Entity>),
     FocusFollowMouse(bool),
+    FontLigatures(bool),
+    HintActivate(segmented_button::Entity, HintAction, String),
+    Hints(Option<segmented_button::Entity>),
     Key(Modifiers, Key),
+    LaunchFileRef(FileRef),
     LaunchUrl(String),
+    LogOutput(Option<segmented_button::Entity>),
+    LogOutputResult(segmented_button::Entity, DialogResult),
+    MarkJump(Option<segmented_button::Entity>, bool),
+    MarkToggle(Option<segmented_button::Entity>),
+    MinimumContrast(usize),
     Modifiers(Modifiers),
     MouseEnter(pane_grid::Pane),
+    MouseReleased,
+    NewTabInheritCwd(bool),
+    OnLastTabClose(LastTabClose),
     Opacity(u8),
+    OpenWorkingDirectory(Option<segmented_button::Entity>),
+    Padding(u16),
     PaneClicked(pane_grid::Pane),
+    PaneClose,
     PaneDragged(pane_grid::DragEvent),
     PaneFocusAdjacent(pane_grid::Direction),
     PaneResized(pane_grid::ResizeEvent),
     PaneSplit(pane_grid::Axis),
     PaneToggleMaximized,
     Paste(Option<segmented_button::Entity>),
+    PasteConfirm(segmented_button::Entity),
+    PasteConfirmCancel(segmented_button::Entity),
+    PasteConfirmPrompt(bool),
+    PasteFilter(PasteFilter),
+    PasteJoinLines(segmented_button::Entity),
+    PasteLoneCrToLf(bool),
     PastePrimary(Option<segmented_button::Entity>),
+    PasteStripNewline(segmented_button::Entity),
     PasteValue(Option<segmented_button::Entity>, String),
+    PerfTick,
+    #[cfg(unix)]
+    PlaybackPause(segmented_button::Entity),
+    #[cfg(unix)]
+    PlaybackSeek(segmented_button::Entity, f32),
+    #[cfg(unix)]
+    PlaybackSpeed(segmented_button::Entity, usize),
+    #[cfg(unix)]
+    PlaybackTick,
+    ProcessTitleTick,
+    ProfileBackgroundImage(ProfileId, String),
+    ProfileBackgroundImageDim(ProfileId, u8),
+    ProfileBackgroundImageScale(ProfileId, usize),
     ProfileCollapse(ProfileId),
     ProfileCommand(ProfileId, String),
     ProfileDirectory(ProfileId, String),
+    ProfileEnvironment(ProfileId, String),
+    ProfileExitAction(ProfileId, usize),
     ProfileExpand(ProfileId),
-    ProfileHold(ProfileId, bool),
+    ProfileLoginShell(ProfileId, bool),
     ProfileName(ProfileId, String),
     ProfileNew,
     ProfileOpen(ProfileId),
+    ProfileOutputLogFile(ProfileId, String),
+    ProfileOutputLogFormat(ProfileId, usize),
     ProfileRemove(ProfileId),
+    ProfileSerialBaudRate(ProfileId, usize),
+    ProfileSerialDevice(ProfileId, String),
+    ProfileSerialParity(ProfileId, usize),
+    ProfileSshHost(ProfileId, String),
+    ProfileSshIdentityFile(ProfileId, String),
+    ProfileSshPort(ProfileId, String),
+    ProfileSshUser(ProfileId, String),
     ProfileSyntaxTheme(ProfileId, ColorSchemeKind, usize),
     ProfileTabTitle(ProfileId, String),
+    PromptJump(Option<segmented_button::Entity>, bool),
+    RecordSession(Option<segmented_button::Entity>),
+    RecordSessionResult(segmented_button::Entity, DialogResult),
+    RestartTick,
+    RestoreSession(bool),
+    Scrollback(usize),
+    ScrollbackArchive(bool),
+    ScrollbackArchiveShow(segmented_button::Entity),
+    ScrollOnKeypress(bool),
+    ScrollOnOutput(bool),
     SelectAll(Option<segmented_button::Entity>),
+    SessionDismiss(pane_grid::Pane, segmented_button::Entity),
+    SessionRestore(pane_grid::Pane, segmented_button::Entity),
     ShowAdvancedFontSettings(bool),
     ShowHeaderBar(bool),
     SyntaxTheme(ColorSchemeKind, usize),
     SystemThemeChange,
     TabActivate(segmented_button::Entity),
     TabActivateJump(usize),
+    TabActivityIndicator(bool),
+    TabBarPosition(TabBarPosition),
+    TabBarVisibility(TabBarVisibility),
+    TabClicked(segmented_button::Entity),
     TabClose(Option<segmented_button::Entity>),
+    TabCloseCancel(segmented_button::Entity),
+    TabCloseForce(segmented_button::Entity),
+    TabCloseOthers(Option<segmented_button::Entity>),
+    TabCloseRight(Option<segmented_button::Entity>),
+    TabContext(segmented_button::Entity),
     TabContextAction(segmented_button::Entity, Action),
     TabContextMenu(pane_grid::Pane, Option<Point>),
-    TabNew,
+    TabContextUrl(segmented_button::Entity, String),
+    TabDuplicate(Option<segmented_button::Entity>),
+    TabMoveLeft,
+    TabMoveRight,
+    TabNew {
+        inherit_cwd: bool,
+    },
     TabNext,
+    TabOpenInWindow(Option<segmented_button::Entity>),
     TabPrev,
+    TabRecent,
+    TabRename(Option<segmented_button::Entity>),
+    TabRenameCancel(segmented_button::Entity),
+    TabRenameInput(segmented_button::Entity, String),
+    TabRenameSubmit(segmented_button::Entity),
+    TabRespawn(pane_grid::Pane, segmented_button::Entity),
+    TabTitleFormat(String),
     TermEvent(pane_grid::Pane, segmented_button::Entity, TermEvent),
     TermEventTx(mpsc::Sender<(pane_grid::Pane, segmented_button::Entity, TermEvent)>),
     ToggleContextPage(ContextPage),
+    ToggleCopyMode(Option<segmented_button::Entity>),
+    ToggleFullscreen,
+    TogglePause(Option<segmented_button::Entity>),
+    TogglePerfOverlay,
+    ToggleReadOnly(Option<segmented_button::Entity>),
     UpdateDefaultProfile((bool, ProfileId)),
     UseBrightBold(bool),
     WindowClose,
+    WindowFocused(bool),
+    WindowMaximize,
+    WindowMaximized(Size, bool),
+    WindowMinimize,
     WindowNew,
+    WindowResized(Size),
+    WordSeparators(String),
     ZoomIn,
+    ZoomIndicatorTick,
     ZoomOut,
     ZoomReset,
 }
 
+/// Which terminals receive the input typed into the focused one
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Broadcast {
+    /// The active tab of every pane
+    Panes,
+    /// Every tab of every pane
+    Tabs,
+}
+
+/// Marks the tab that offers to restore the previous session
+struct SessionRestoreOffer;
+
+/// Name of the program that was running when closing a tab was requested
+struct TabCloseConfirm(String);
+
+/// Pasted text waiting for the user to confirm it
+struct PasteConfirm(String);
+
+/// Title being entered for a tab
+struct TabRename(String);
+
+/// Controls of a tab that plays a recording
+#[cfg(unix)]
+struct Playback(std::sync::Arc<Mutex<PlaybackControl>>);
+
+/// Copied text along with an HTML version, for apps that paste rich text
+struct RichText {
+    text: String,
+    html: String,
+}
+
+impl AsMimeTypes for RichText {
+    fn available(&self) -> Cow<'static, [String]> {
+        Cow::Owned(vec![
+            "text/html".to_string(),
+            "text/plain;charset=utf-8".to_string(),
+            "text/plain".to_string(),
+            "UTF8_STRING".to_string(),
+        ])
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        let data = match mime_type {
+            "text/html" => &self.html,
+            _ => &self.text,
+        };
+        Some(Cow::Owned(data.clone().into_bytes()))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ContextPage {
     About,
@@ -352,14 +834,111 @@ impl ContextPage {
     }
 }
 
+// Tab title of a remote profile terminal, showing the state of its connection
+fn remote_title(remote: &RemoteConnection) -> String {
+    let destination = remote.destination.as_str();
+    match remote.state {
+        RemoteState::Connecting => fl!("remote-connecting", destination = destination),
+        RemoteState::Connected => remote.destination.clone(),
+        RemoteState::Disconnected => fl!("remote-disconnected", destination = destination),
+    }
+}
+
+// Position in a recording as minutes and seconds
+#[cfg(unix)]
+fn format_playback_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Run the command of a trigger. The line comes from output, so it is only passed in the
+// environment and the command is never run by a shell.
+fn trigger_command(command: &str, line: &str) {
+    let Some(argv) = shlex::split(command).filter(|argv| !argv.is_empty()) else {
+        log::warn!("failed to parse trigger command {:?}", command);
+        return;
+    };
+    match process::Command::new(&argv[0])
+        .args(&argv[1..])
+        .env("COSMIC_TERM_TRIGGER_LINE", line)
+        .spawn()
+    {
+        Ok(mut child) => {
+            // Reap the child so it does not linger as a zombie
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => log::warn!("failed to run trigger command {:?}: {}", command, err),
+    }
+}
+
+fn command_notification_summary(exit_code_opt: Option<i32>) -> String {
+    match exit_code_opt {
+        Some(0) | None => fl!("command-finished"),
+        Some(status) => fl!("command-failed", status = status),
+    }
+}
+
+// Text shown in a color input, empty means automatic
+fn color_input_value(color_opt: Option<HexColor>) -> String {
+    color_opt.map_or(String::new(), |color| {
+        format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+    })
+}
+
+fn color_scheme_field_name(field: ColorSchemeField) -> String {
+    match field {
+        ColorSchemeField::Foreground => fl!("foreground"),
+        ColorSchemeField::Background => fl!("background"),
+        ColorSchemeField::Cursor => fl!("cursor"),
+        ColorSchemeField::CursorText => fl!("cursor-text"),
+        ColorSchemeField::Ansi(index) => match index {
+            0 => fl!("color-black"),
+            1 => fl!("color-red"),
+            2 => fl!("color-green"),
+            3 => fl!("color-yellow"),
+            4 => fl!("color-blue"),
+            5 => fl!("color-magenta"),
+            6 => fl!("color-cyan"),
+            7 => fl!("color-white"),
+            8 => fl!("color-bright-black"),
+            9 => fl!("color-bright-red"),
+            10 => fl!("color-bright-green"),
+            11 => fl!("color-bright-yellow"),
+            12 => fl!("color-bright-blue"),
+            13 => fl!("color-bright-magenta"),
+            14 => fl!("color-bright-cyan"),
+            _ => fl!("color-bright-white"),
+        },
+    }
+}
+
 /// The [`App`] stores application-specific state.
 pub struct App {
     core: Core,
     pane_model: TerminalPaneGrid,
     config_handler: Option<cosmic_config::Config>,
     config: Config,
+    state_handler: Option<cosmic_config::Config>,
+    session_opt: Option<Session>,
+    // Set once the tabs of this window are saved, so closing does not save them twice
+    session_saved: AtomicBool,
     key_binds: HashMap<KeyBind, Action>,
     app_themes: Vec<String>,
+    paste_filter_names: Vec<String>,
+    tab_bar_visibility_names: Vec<String>,
+    tab_bar_position_names: Vec<String>,
+    cursor_shape_names: Vec<String>,
+    background_image_scale_names: Vec<String>,
+    exit_action_names: Vec<String>,
+    output_log_format_names: Vec<String>,
+    serial_baud_rate_names: Vec<String>,
+    serial_parity_names: Vec<String>,
+    scrollback_names: Vec<String>,
+    scrollbacks: Vec<u32>,
+    command_notify_names: Vec<String>,
+    command_notify_seconds: Vec<u32>,
+    minimum_contrast_names: Vec<String>,
+    minimum_contrasts: Vec<u16>,
     font_names: Vec<String>,
     font_size_names: Vec<String>,
     font_sizes: Vec<u16>,
@@ -370,6 +949,8 @@ pub struct App {
     curr_font_weights: Vec<u16>,
     curr_font_stretch_names: Vec<String>,
     curr_font_stretches: Vec<Stretch>,
+    line_height_names: Vec<String>,
+    line_heights: Vec<u16>,
     zoom_adj: i8,
     zoom_step_names: Vec<String>,
     zoom_steps: Vec<u16>,
@@ -380,19 +961,58 @@ pub struct App {
     dialog_opt: Option<Dialog<Message>>,
     terminal_ids: HashMap<pane_grid::Pane, widget::Id>,
     find: bool,
+    find_case_sensitive: bool,
+    find_regex: bool,
     find_search_id: widget::Id,
     find_search_value: String,
     term_event_tx_opt: Option<mpsc::Sender<(pane_grid::Pane, segmented_button::Entity, TermEvent)>>,
     startup_options: Option<tty::Options>,
+    startup_title_opt: Option<String>,
+    #[cfg(unix)]
+    startup_playback_opt: Option<PathBuf>,
+    #[cfg(unix)]
+    playback_speed_names: Vec<String>,
     term_config: term::Config,
     color_scheme_errors: Vec<String>,
+    // Cursor color inputs, kept separately so partial values can be typed
+    cursor_color_input: String,
+    cursor_text_color_input: String,
     color_scheme_expanded: Option<(ColorSchemeKind, ColorSchemeId)>,
     color_scheme_renaming: Option<(ColorSchemeKind, ColorSchemeId, String)>,
+    color_scheme_editing: Option<(
+        ColorSchemeKind,
+        ColorSchemeId,
+        Vec<(ColorSchemeField, String)>,
+    )>,
     color_scheme_rename_id: widget::Id,
+    tab_rename_id: widget::Id,
+    // Last clicked tab and when, two clicks in a row rename it
+    tab_click_opt: Option<(segmented_button::Entity, Instant)>,
+    // Tabs of each pane from most to least recently used, and the position reached while cycling
+    // through those of the focused pane
+    tab_history: HashMap<pane_grid::Pane, Vec<segmented_button::Entity>>,
+    tab_history_pos_opt: Option<usize>,
     color_scheme_tab_model: widget::segmented_button::SingleSelectModel,
     profile_expanded: Option<ProfileId>,
+    profile_names: Vec<String>,
+    profile_ids: Vec<ProfileId>,
     show_advanced_font_settings: bool,
     modifiers: Modifiers,
+    window_focused: bool,
+    zoom_indicator_opt: Option<Instant>,
+    bell_flash_opt: Option<(segmented_button::Entity, Instant)>,
+    broadcast_opt: Option<Broadcast>,
+    // Last restart delay of each tab, and the restarts waiting for their delay to pass
+    restart_delays: HashMap<segmented_button::Entity, Duration>,
+    restarts_pending: Vec<(pane_grid::Pane, segmented_button::Entity, Instant)>,
+    blinking: bool,
+    notifier: Notifier,
+    perf_overlay: bool,
+    perf_stats: PerfStats,
+    fullscreen: bool,
+    // Size of the window from before it was maximized, which is saved when it closes
+    window_size: Size,
+    window_maximized: bool,
 }
 
 impl App {
@@ -403,8 +1023,30 @@ impl App {
         }
     }
 
+    // Show the color scheme being edited on the active terminal
+    fn preview_color_scheme(&mut self) {
+        let Some((color_scheme_kind, color_scheme_id, _values)) = &self.color_scheme_editing else {
+            return;
+        };
+        let Some(color_scheme) = self
+            .config
+            .color_schemes(*color_scheme_kind)
+            .get(color_scheme_id)
+        else {
+            return;
+        };
+        let colors = TermColors::from(color_scheme);
+        if let Some(tab_model) = self.pane_model.active() {
+            if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(tab_model.active()) {
+                let mut terminal = terminal.lock().unwrap();
+                terminal.set_colors(&colors, &self.config);
+            }
+        }
+    }
+
     fn update_color_schemes(&mut self) {
         self.themes = terminal_theme::terminal_themes();
+        self.themes.extend(terminal_theme::user_terminal_themes());
         for &color_scheme_kind in &[ColorSchemeKind::Dark, ColorSchemeKind::Light] {
             for (color_scheme_name, color_scheme_id) in
                 self.config.color_scheme_names(color_scheme_kind)
@@ -467,18 +1109,33 @@ impl App {
             terminal::WINDOW_BG_COLOR.store(data, Ordering::SeqCst);
         }
 
+        // Update key bindings
+        self.key_binds = key_binds(&self.config);
+
+        // Update profiles shown next to the new tab button
+        self.update_profile_names();
+
+        // Update terminal options used for new and existing tabs
+        self.term_config.scrolling_history = self.config.scrollback_lines as usize;
+        self.term_config.default_cursor_style = self.config.cursor_style();
+        self.term_config
+            .semantic_escape_chars
+            .clone_from(&self.config.word_separators);
+        self.blinking = true;
+
         // Set config of all tabs
         for (_pane, tab_model) in self.pane_model.panes.iter() {
             for entity in tab_model.iter() {
                 if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                     let mut terminal = terminal.lock().unwrap();
+                    terminal.set_term_config(&self.term_config);
                     terminal.set_config(&self.config, &self.themes, self.zoom_adj);
                 }
             }
         }
 
         // Set headerbar state
-        self.core.window.show_headerbar = self.config.show_headerbar;
+        self.core.window.show_headerbar = self.config.show_headerbar && !self.fullscreen;
 
         // Update application theme
         cosmic::app::command::set_theme(theme)
@@ -513,7 +1170,13 @@ impl App {
         Command::none()
     }
 
+    fn update_profile_names(&mut self) {
+        (self.profile_names, self.profile_ids) = self.config.profile_names().into_iter().unzip();
+    }
+
     fn save_profiles(&mut self) -> Command<Message> {
+        self.update_profile_names();
+
         // Optimized for just saving profiles
         if let Some(ref config_handler) = self.config_handler {
             match config_handler.set("profiles", &self.config.profiles) {
@@ -526,8 +1189,28 @@ impl App {
         Command::none()
     }
 
+    // Copy the selection of a terminal to the clipboard, with an HTML version if enabled. Plain
+    // text is built on another thread, while the HTML version is still built here.
+    fn copy_selection(&self, terminal: &Terminal) -> Command<Message> {
+        if self.config.copy_html {
+            if let Some(html) = terminal.selection_html() {
+                let text = terminal.selection_text().unwrap_or_default();
+                return clipboard::write_data(RichText { text, html });
+            }
+        }
+        Command::perform(terminal.selection_text_task(), |text_opt| {
+            message::app(Message::CopyText(text_opt))
+        })
+    }
+
     fn update_focus(&self) -> Command<Message> {
-        if self.find {
+        let renaming = self
+            .pane_model
+            .active()
+            .is_some_and(|tab_model| tab_model.data::<TabRename>(tab_model.active()).is_some());
+        if renaming {
+            widget::text_input::focus(self.tab_rename_id.clone())
+        } else if self.find {
             widget::text_input::focus(self.find_search_id.clone())
         } else if let Some(terminal_id) = self.terminal_ids.get(&self.pane_model.focus).cloned() {
             widget::text_input::focus(terminal_id)
@@ -540,6 +1223,17 @@ impl App {
     fn update_title(&mut self, pane: Option<pane_grid::Pane>) -> Command<Message> {
         let pane = pane.unwrap_or(self.pane_model.focus);
         if let Some(tab_model) = self.pane_model.panes.get(pane) {
+            // Every change of the active tab ends up here, which makes it the most recent,
+            // unless cycling through recent tabs which is committed when Ctrl is released
+            if self.tab_history_pos_opt.is_none() {
+                let entity = tab_model.active();
+                let history = self.tab_history.entry(pane).or_default();
+                history.retain(|e| *e != entity);
+                history.insert(0, entity);
+            }
+            self.tab_history
+                .retain(|pane, _| self.pane_model.panes.get(*pane).is_some());
+
             let (header_title, window_title) = match tab_model.text(tab_model.active()) {
                 Some(tab_title) => (
                     tab_title.to_string(),
@@ -741,15 +1435,78 @@ impl App {
             sections.push(section.into());
         }
 
-        sections.push(
-            widget::row::with_children(vec![
-                widget::horizontal_space(Length::Fill).into(),
-                widget::button::standard(fl!("import"))
-                    .on_press(Message::ColorSchemeImport(color_scheme_kind))
+        match &self.color_scheme_editing {
+            Some((kind, color_scheme_id, values)) if kind == &color_scheme_kind => {
+                let name = self
+                    .config
+                    .color_schemes(color_scheme_kind)
+                    .get(color_scheme_id)
+                    .map_or(String::new(), |color_scheme| color_scheme.name.clone());
+                let mut section = widget::settings::view_section(name);
+                for (field, value) in values {
+                    let field = *field;
+                    let color_opt = HexColor::parse_rgb(value.trim()).ok();
+                    let swatch = widget::container(widget::Space::new(
+                        Length::Fixed(24.0),
+                        Length::Fixed(24.0),
+                    ))
+                    .style(style::Container::custom(move |theme| {
+                        let cosmic = theme.cosmic();
+                        widget::container::Appearance {
+                            background: color_opt.map(|color| {
+                                Background::Color(Color::from_rgb8(color.r, color.g, color.b))
+                            }),
+                            border: Border {
+                                radius: 4.0.into(),
+                                width: 1.0,
+                                color: cosmic.background.component.divider.into(),
+                            },
+                            ..Default::default()
+                        }
+                    }));
+                    section = section.add(
+                        widget::settings::item::builder(color_scheme_field_name(field)).control(
+                            widget::row::with_children(vec![
+                                swatch.into(),
+                                widget::text_input(fl!("color-automatic"), value)
+                                    .on_input(move |value| {
+                                        Message::ColorSchemeEditColor(field, value)
+                                    })
+                                    .width(Length::Fixed(96.0))
+                                    .into(),
+                            ])
+                            .align_items(Alignment::Center)
+                            .spacing(space_xxxs),
+                        ),
+                    );
+                }
+                sections.push(section.into());
+                sections.push(
+                    widget::row::with_children(vec![
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::suggested(fl!("done"))
+                            .on_press(Message::ColorSchemeEditDone)
+                            .into(),
+                    ])
                     .into(),
-            ])
-            .into(),
-        );
+                );
+            }
+            _ => {
+                sections.push(
+                    widget::row::with_children(vec![
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("new-color-scheme"))
+                            .on_press(Message::ColorSchemeNew(color_scheme_kind))
+                            .into(),
+                        widget::button::standard(fl!("import"))
+                            .on_press(Message::ColorSchemeImport(color_scheme_kind))
+                            .into(),
+                    ])
+                    .spacing(space_xxxs)
+                    .into(),
+                );
+            }
+        }
 
         for error in &self.color_scheme_errors {
             sections.push(
@@ -855,6 +1612,99 @@ impl App {
                                 ])
                                 .spacing(space_xxxs)
                                 .into(),
+                                widget::column::with_children(vec![
+                                    widget::text(fl!("ssh-host")).into(),
+                                    widget::text_input("", &profile.ssh_host)
+                                        .on_input(move |text| {
+                                            Message::ProfileSshHost(profile_id, text)
+                                        })
+                                        .into(),
+                                    widget::text::caption(fl!("ssh-host-description")).into(),
+                                ])
+                                .spacing(space_xxxs)
+                                .into(),
+                                widget::row::with_children(vec![
+                                    widget::column::with_children(vec![
+                                        widget::text(fl!("ssh-user")).into(),
+                                        widget::text_input("", &profile.ssh_user)
+                                            .on_input(move |text| {
+                                                Message::ProfileSshUser(profile_id, text)
+                                            })
+                                            .into(),
+                                    ])
+                                    .spacing(space_xxxs)
+                                    .into(),
+                                    widget::column::with_children(vec![
+                                        widget::text(fl!("ssh-port")).into(),
+                                        widget::text_input("22", &profile.ssh_port)
+                                            .on_input(move |text| {
+                                                Message::ProfileSshPort(profile_id, text)
+                                            })
+                                            .width(Length::Fixed(96.0))
+                                            .into(),
+                                    ])
+                                    .spacing(space_xxxs)
+                                    .into(),
+                                ])
+                                .spacing(space_xs)
+                                .into(),
+                                widget::column::with_children(vec![
+                                    widget::text(fl!("ssh-identity-file")).into(),
+                                    widget::text_input("", &profile.ssh_identity_file)
+                                        .on_input(move |text| {
+                                            Message::ProfileSshIdentityFile(profile_id, text)
+                                        })
+                                        .into(),
+                                ])
+                                .spacing(space_xxxs)
+                                .into(),
+                                widget::column::with_children(vec![
+                                    widget::text(fl!("serial-device")).into(),
+                                    widget::text_input("/dev/ttyUSB0", &profile.serial_device)
+                                        .on_input(move |text| {
+                                            Message::ProfileSerialDevice(profile_id, text)
+                                        })
+                                        .into(),
+                                    widget::text::caption(fl!("serial-device-description")).into(),
+                                ])
+                                .spacing(space_xxxs)
+                                .into(),
+                                widget::row::with_children(vec![
+                                    widget::column::with_children(vec![
+                                        widget::text(fl!("serial-baud-rate")).into(),
+                                        widget::dropdown(
+                                            &self.serial_baud_rate_names,
+                                            SERIAL_BAUD_RATES.iter().position(|baud_rate| {
+                                                *baud_rate == profile.serial_baud_rate
+                                            }),
+                                            move |index| {
+                                                Message::ProfileSerialBaudRate(profile_id, index)
+                                            },
+                                        )
+                                        .into(),
+                                    ])
+                                    .spacing(space_xxxs)
+                                    .into(),
+                                    widget::column::with_children(vec![
+                                        widget::text(fl!("serial-parity")).into(),
+                                        widget::dropdown(
+                                            &self.serial_parity_names,
+                                            Some(match profile.serial_parity {
+                                                SerialParity::None => 0,
+                                                SerialParity::Even => 1,
+                                                SerialParity::Odd => 2,
+                                            }),
+                                            move |index| {
+                                                Message::ProfileSerialParity(profile_id, index)
+                                            },
+                                        )
+                                        .into(),
+                                    ])
+                                    .spacing(space_xxxs)
+                                    .into(),
+                                ])
+                                .spacing(space_xs)
+                                .into(),
                                 widget::column::with_children(vec![
                                     widget::text(fl!("working-directory")).into(),
                                     widget::text_input("", &profile.working_directory)
@@ -865,6 +1715,17 @@ impl App {
                                 ])
                                 .spacing(space_xxxs)
                                 .into(),
+                                widget::column::with_children(vec![
+                                    widget::text(fl!("environment")).into(),
+                                    widget::text_input("", &profile.environment)
+                                        .on_input(move |text| {
+                                            Message::ProfileEnvironment(profile_id, text)
+                                        })
+                                        .into(),
+                                    widget::text::caption(fl!("environment-description")).into(),
+                                ])
+                                .spacing(space_xxxs)
+                                .into(),
                                 widget::column::with_children(vec![
                                     widget::text(fl!("tab-title")).into(),
                                     widget::text_input("", &profile.tab_title)
@@ -876,6 +1737,18 @@ impl App {
                                 ])
                                 .spacing(space_xxxs)
                                 .into(),
+                                widget::column::with_children(vec![
+                                    widget::text(fl!("background-image")).into(),
+                                    widget::text_input("", &profile.background_image)
+                                        .on_input(move |text| {
+                                            Message::ProfileBackgroundImage(profile_id, text)
+                                        })
+                                        .into(),
+                                    widget::text::caption(fl!("background-image-description"))
+                                        .into(),
+                                ])
+                                .spacing(space_xxxs)
+                                .into(),
                             ])
                             .padding([0, space_s])
                             .spacing(space_xs),
@@ -912,6 +1785,31 @@ impl App {
                                 ),
                             ),
                         )
+                        .add(
+                            widget::settings::item::builder(fl!("background-image-scale")).control(
+                                widget::dropdown(
+                                    &self.background_image_scale_names,
+                                    Some(match profile.background_image_scale {
+                                        BackgroundImageScale::Fill => 0,
+                                        BackgroundImageScale::Fit => 1,
+                                        BackgroundImageScale::Stretch => 2,
+                                        BackgroundImageScale::Center => 3,
+                                    }),
+                                    move |index| {
+                                        Message::ProfileBackgroundImageScale(profile_id, index)
+                                    },
+                                ),
+                            ),
+                        )
+                        .add(
+                            widget::settings::item::builder(fl!("background-image-dim"))
+                                .description(format!("{}%", profile.background_image_dim))
+                                .control(widget::slider(
+                                    0..=100,
+                                    profile.background_image_dim,
+                                    move |dim| Message::ProfileBackgroundImageDim(profile_id, dim),
+                                )),
+                        )
                         .add(
                             widget::settings::item::builder(fl!("make-default")).control(
                                 widget::toggler(
@@ -921,22 +1819,64 @@ impl App {
                                 ),
                             ),
                         )
+                        .add(
+                            widget::settings::item::builder(fl!("exit-action"))
+                                .description(fl!("exit-action-description"))
+                                .control(widget::dropdown(
+                                    &self.exit_action_names,
+                                    Some(match profile.exit_action {
+                                        ExitAction::Close => 0,
+                                        ExitAction::Hold => 1,
+                                        ExitAction::Restart => 2,
+                                    }),
+                                    move |index| Message::ProfileExitAction(profile_id, index),
+                                )),
+                        )
                         .add(
                             widget::row::with_children(vec![
                                 widget::column::with_children(vec![
-                                    widget::text(fl!("hold")).into(),
-                                    widget::text::caption(fl!("remain-open")).into(),
+                                    widget::text(fl!("login-shell")).into(),
+                                    widget::text::caption(fl!("login-shell-description")).into(),
                                 ])
                                 .spacing(space_xxxs)
                                 .into(),
                                 widget::horizontal_space(Length::Fill).into(),
-                                widget::toggler(None, profile.hold, move |t| {
-                                    Message::ProfileHold(profile_id, t)
+                                widget::toggler(None, profile.login_shell, move |t| {
+                                    Message::ProfileLoginShell(profile_id, t)
                                 })
                                 .into(),
                             ])
                             .align_items(Alignment::Center)
                             .padding([0, space_s]),
+                        )
+                        .add(
+                            widget::column::with_children(vec![
+                                widget::text(fl!("output-log")).into(),
+                                widget::row::with_children(vec![
+                                    widget::text_input("", &profile.output_log_file)
+                                        .on_input(move |text| {
+                                            Message::ProfileOutputLogFile(profile_id, text)
+                                        })
+                                        .into(),
+                                    widget::dropdown(
+                                        &self.output_log_format_names,
+                                        Some(match profile.output_log_format {
+                                            OutputLogFormat::Raw => 0,
+                                            OutputLogFormat::Plain => 1,
+                                        }),
+                                        move |index| {
+                                            Message::ProfileOutputLogFormat(profile_id, index)
+                                        },
+                                    )
+                                    .into(),
+                                ])
+                                .align_items(Alignment::Center)
+                                .spacing(space_xs)
+                                .into(),
+                                widget::text::caption(fl!("output-log-description")).into(),
+                            ])
+                            .spacing(space_xxxs)
+                            .padding([0, space_s]),
                         );
 
                     let padding = Padding {
@@ -1004,6 +1944,41 @@ impl App {
             .curr_font_weights
             .iter()
             .position(|font_weight| font_weight == &self.config.bold_font_weight);
+        let cursor_shape_selected = match self.config.cursor_shape {
+            CursorShape::Block => 0,
+            CursorShape::Beam => 1,
+            CursorShape::Underline => 2,
+        };
+        let paste_filter_selected = match self.config.paste_filter {
+            PasteFilter::None => 0,
+            PasteFilter::Strip => 1,
+            PasteFilter::Escape => 2,
+        };
+        let tab_bar_visibility_selected = match self.config.tab_bar_visibility {
+            TabBarVisibility::Always => 0,
+            TabBarVisibility::Multiple => 1,
+            TabBarVisibility::Never => 2,
+        };
+        let tab_bar_position_selected = match self.config.tab_bar_position {
+            TabBarPosition::Top => 0,
+            TabBarPosition::Bottom => 1,
+        };
+        let command_notify_selected = self
+            .command_notify_seconds
+            .iter()
+            .position(|seconds| seconds == &self.config.command_notify_seconds);
+        let minimum_contrast_selected = self
+            .minimum_contrasts
+            .iter()
+            .position(|contrast| contrast == &self.config.minimum_contrast_mul_10);
+        let scrollback_selected = self
+            .scrollbacks
+            .iter()
+            .position(|scrollback| scrollback == &self.config.scrollback_lines);
+        let line_height_selected = self
+            .line_heights
+            .iter()
+            .position(|line_height| line_height == &self.config.line_height_mul_100);
         let zoom_step_selected = self
             .zoom_steps
             .iter()
@@ -1052,6 +2027,49 @@ impl App {
                     .control(widget::slider(0..=100, self.config.opacity, |opacity| {
                         Message::Opacity(opacity)
                     })),
+            )
+            .add(
+                widget::settings::item::builder(fl!("padding"))
+                    .description(format!("{}px", self.config.padding))
+                    .control(widget::slider(
+                        0..=32,
+                        self.config.padding,
+                        Message::Padding,
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("cursor-shape")).control(widget::dropdown(
+                    &self.cursor_shape_names,
+                    Some(cursor_shape_selected),
+                    |index| {
+                        Message::CursorShape(match index {
+                            1 => CursorShape::Beam,
+                            2 => CursorShape::Underline,
+                            _ => CursorShape::Block,
+                        })
+                    },
+                )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("cursor-blink"))
+                    .description(fl!("cursor-blink-description"))
+                    .toggler(self.config.cursor_blink, Message::CursorBlink),
+            )
+            .add(
+                widget::settings::item::builder(fl!("cursor-color"))
+                    .description(fl!("cursor-color-description"))
+                    .control(
+                        widget::text_input(fl!("color-automatic"), &self.cursor_color_input)
+                            .on_input(Message::CursorColor)
+                            .width(Length::Fixed(96.0)),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("cursor-text-color")).control(
+                    widget::text_input(fl!("color-automatic"), &self.cursor_text_color_input)
+                        .on_input(Message::CursorTextColor)
+                        .width(Length::Fixed(96.0)),
+                ),
             );
 
         let mut font_section = widget::settings::view_section(fl!("font"))
@@ -1069,6 +2087,20 @@ impl App {
                     }),
                 ),
             )
+            .add(
+                widget::settings::item::builder(fl!("default-line-height")).control(
+                    widget::dropdown(
+                        &self.line_height_names,
+                        line_height_selected,
+                        Message::DefaultLineHeight,
+                    ),
+                ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("font-ligatures"))
+                    .description(fl!("font-ligatures-description"))
+                    .toggler(self.config.font_ligatures, Message::FontLigatures),
+            )
             .add(
                 widget::settings::item::builder(fl!("advanced-font-settings")).control(
                     if self.show_advanced_font_settings {
@@ -1123,6 +2155,15 @@ impl App {
                 .add(
                     widget::settings::item::builder(fl!("use-bright-bold"))
                         .toggler(self.config.use_bright_bold, Message::UseBrightBold),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("minimum-contrast"))
+                        .description(fl!("minimum-contrast-description"))
+                        .control(widget::dropdown(
+                            &self.minimum_contrast_names,
+                            minimum_contrast_selected,
+                            Message::MinimumContrast,
+                        )),
                 );
             let padding = Padding {
                 top: 0.0,
@@ -1137,20 +2178,208 @@ impl App {
             font_section = font_section.add(advanced_font_settings());
         }
 
+        let bell_section = widget::settings::view_section(fl!("bell"))
+            .add(
+                widget::settings::item::builder(fl!("bell-visual"))
+                    .toggler(self.config.bell_visual, Message::BellVisual),
+            )
+            .add(
+                widget::settings::item::builder(fl!("bell-audible"))
+                    .toggler(self.config.bell_audible, Message::BellAudible),
+            )
+            .add(
+                widget::settings::item::builder(fl!("bell-urgent"))
+                    .description(fl!("bell-urgent-description"))
+                    .toggler(self.config.bell_urgent, Message::BellUrgent),
+            )
+            .add(
+                widget::settings::item::builder(fl!("bell-tab-badge"))
+                    .toggler(self.config.bell_tab_badge, Message::BellTabBadge),
+            )
+            .add(
+                widget::settings::item::builder(fl!("tab-activity-indicator")).toggler(
+                    self.config.tab_activity_indicator,
+                    Message::TabActivityIndicator,
+                ),
+            );
+
+        let notifications_section = widget::settings::view_section(fl!("notifications")).add(
+            widget::settings::item::builder(fl!("command-notify"))
+                .description(fl!("command-notify-description"))
+                .control(widget::dropdown(
+                    &self.command_notify_names,
+                    command_notify_selected,
+                    Message::CommandNotify,
+                )),
+        );
+
+        let tabs_section = widget::settings::view_section(fl!("tabs"))
+            .add(
+                widget::settings::item::builder(fl!("tab-bar-visibility")).control(
+                    widget::dropdown(
+                        &self.tab_bar_visibility_names,
+                        Some(tab_bar_visibility_selected),
+                        |index| {
+                            Message::TabBarVisibility(match index {
+                                0 => TabBarVisibility::Always,
+                                2 => TabBarVisibility::Never,
+                                _ => TabBarVisibility::Multiple,
+                            })
+                        },
+                    ),
+                ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("tab-bar-position")).control(widget::dropdown(
+                    &self.tab_bar_position_names,
+                    Some(tab_bar_position_selected),
+                    |index| {
+                        Message::TabBarPosition(match index {
+                            1 => TabBarPosition::Bottom,
+                            _ => TabBarPosition::Top,
+                        })
+                    },
+                )),
+            );
+
         let splits_section = widget::settings::view_section(fl!("splits")).add(
             widget::settings::item::builder(fl!("focus-follow-mouse"))
                 .toggler(self.config.focus_follow_mouse, Message::FocusFollowMouse),
         );
 
-        let advanced_section = widget::settings::view_section(fl!("advanced")).add(
-            widget::settings::item::builder(fl!("show-headerbar"))
-                .description(fl!("show-header-description"))
-                .toggler(self.config.show_headerbar, Message::ShowHeaderBar),
-        );
+        let advanced_section = widget::settings::view_section(fl!("advanced"))
+            .add(
+                widget::settings::item::builder(fl!("show-headerbar"))
+                    .description(fl!("show-header-description"))
+                    .toggler(self.config.show_headerbar, Message::ShowHeaderBar),
+            )
+            .add(
+                widget::settings::item::builder(fl!("new-tab-inherit-cwd"))
+                    .description(fl!("new-tab-inherit-cwd-description"))
+                    .toggler(self.config.new_tab_inherit_cwd, Message::NewTabInheritCwd),
+            )
+            .add(
+                widget::settings::item::builder(fl!("confirm-close-running"))
+                    .description(fl!("confirm-close-running-description"))
+                    .toggler(
+                        self.config.confirm_close_running,
+                        Message::ConfirmCloseRunning,
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("restore-session"))
+                    .description(fl!("restore-session-description"))
+                    .toggler(self.config.restore_session, Message::RestoreSession),
+            )
+            .add(
+                widget::settings::item::builder(fl!("new-tab-on-last-close"))
+                    .description(fl!("new-tab-on-last-close-description"))
+                    .toggler(
+                        self.config.on_last_tab_close == LastTabClose::NewTab,
+                        |new_tab| {
+                            Message::OnLastTabClose(if new_tab {
+                                LastTabClose::NewTab
+                            } else {
+                                LastTabClose::CloseWindow
+                            })
+                        },
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("tab-title-format"))
+                    .description(fl!("tab-title-format-description"))
+                    .control(
+                        widget::text_input("", &self.config.tab_title_format)
+                            .on_input(Message::TabTitleFormat),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("file-ref-command"))
+                    .description(fl!("file-ref-command-description"))
+                    .control(
+                        widget::text_input("", &self.config.file_ref_command)
+                            .on_input(Message::FileRefCommand),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("copy-on-select"))
+                    .description(fl!("copy-on-select-description"))
+                    .toggler(self.config.copy_on_select, Message::CopyOnSelect),
+            )
+            .add(
+                widget::settings::item::builder(fl!("copy-html"))
+                    .description(fl!("copy-html-description"))
+                    .toggler(self.config.copy_html, Message::CopyHtml),
+            )
+            .add(
+                widget::settings::item::builder(fl!("clipboard-read"))
+                    .description(fl!("clipboard-read-description"))
+                    .toggler(self.config.clipboard_read, Message::ClipboardRead),
+            )
+            .add(
+                widget::settings::item::builder(fl!("paste-filter"))
+                    .description(fl!("paste-filter-description"))
+                    .control(widget::dropdown(
+                        &self.paste_filter_names,
+                        Some(paste_filter_selected),
+                        |index| {
+                            Message::PasteFilter(match index {
+                                1 => PasteFilter::Strip,
+                                2 => PasteFilter::Escape,
+                                _ => PasteFilter::None,
+                            })
+                        },
+                    )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("paste-lone-cr-to-lf"))
+                    .toggler(self.config.paste_lone_cr_to_lf, Message::PasteLoneCrToLf),
+            )
+            .add(
+                widget::settings::item::builder(fl!("paste-confirm-prompt"))
+                    .description(fl!("paste-confirm-prompt-description"))
+                    .toggler(self.config.paste_confirm, Message::PasteConfirmPrompt),
+            )
+            .add(
+                widget::settings::item::builder(fl!("alternate-scroll"))
+                    .description(fl!("alternate-scroll-description"))
+                    .toggler(self.config.alternate_scroll, Message::AlternateScroll),
+            )
+            .add(
+                widget::settings::item::builder(fl!("word-separators"))
+                    .description(fl!("word-separators-description"))
+                    .control(
+                        widget::text_input("", &self.config.word_separators)
+                            .on_input(Message::WordSeparators),
+                    ),
+            )
+            .add(
+                widget::settings::item::builder(fl!("scrollback")).control(widget::dropdown(
+                    &self.scrollback_names,
+                    scrollback_selected,
+                    Message::Scrollback,
+                )),
+            )
+            .add(
+                widget::settings::item::builder(fl!("scrollback-archive"))
+                    .description(fl!("scrollback-archive-description"))
+                    .toggler(self.config.scrollback_archive, Message::ScrollbackArchive),
+            )
+            .add(
+                widget::settings::item::builder(fl!("scroll-on-keypress"))
+                    .toggler(self.config.scroll_on_keypress, Message::ScrollOnKeypress),
+            )
+            .add(
+                widget::settings::item::builder(fl!("scroll-on-output"))
+                    .toggler(self.config.scroll_on_output, Message::ScrollOnOutput),
+            );
 
         widget::settings::view_column(vec![
             appearance_section.into(),
             font_section.into(),
+            bell_section.into(),
+            notifications_section.into(),
+            tabs_section.into(),
             splits_section.into(),
             advanced_section.into(),
         ])
@@ -1160,111 +2389,572 @@ impl App {
         self.config.default_profile
     }
 
+    // Remember the open tabs so they can be restored on the next start. Tabs from all panes
+    // are saved in order, the active tab is the one in the focused pane.
+    fn save_session(&self) {
+        if !self.config.restore_session {
+            return;
+        }
+        let Some(ref state_handler) = self.state_handler else {
+            return;
+        };
+        if self.session_saved.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // Every window runs in its own process, so the tabs of windows closed before this one
+        // are kept and all of them are offered by the next window to start
+        let mut session = state_handler.get::<Session>("session").unwrap_or_default();
+        for (pane, tab_model) in self.pane_model.panes.iter() {
+            for entity in tab_model.iter() {
+                if *pane == self.pane_model.focus && entity == tab_model.active() {
+                    session.active = session.tabs.len();
+                }
+                let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) else {
+                    continue;
+                };
+                let terminal = terminal.lock().unwrap();
+                session.tabs.push(SessionTab {
+                    title: tab_model.text(entity).unwrap_or_default().to_string(),
+                    working_directory: terminal.current_working_directory(),
+                    profile_id: terminal.profile_id_opt,
+                });
+            }
+        }
+
+        if let Err(err) = state_handler.set("session", session) {
+            log::error!("failed to save session: {}", err);
+        }
+    }
+
+    // Remember the window size for the next start
+    fn save_window_state(&self) {
+        let Some(ref state_handler) = self.state_handler else {
+            return;
+        };
+
+        let window_state = WindowState {
+            width: self.window_size.width.round() as u32,
+            height: self.window_size.height.round() as u32,
+            maximized: self.window_maximized,
+        };
+        if let Err(err) = state_handler.set("window", window_state) {
+            log::error!("failed to save window state: {}", err);
+        }
+    }
+
+    fn close_window(&self) -> Command<Message> {
+        self.save_session();
+        self.save_window_state();
+        window::close(window::Id::MAIN)
+    }
+
+    fn close_tab(&mut self, entity_opt: Option<segmented_button::Entity>) -> Command<Message> {
+        let pane = self.pane_model.focus;
+        if let Some(tab_model) = self.pane_model.active_mut() {
+            let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+
+            // Activate closest item
+            if let Some(position) = tab_model.position(entity) {
+                if position > 0 {
+                    tab_model.activate_position(position - 1);
+                } else {
+                    tab_model.activate_position(position + 1);
+                }
+            }
+
+            // Remove item
+            tab_model.remove(entity);
+            self.restart_delays.remove(&entity);
+            self.restarts_pending.retain(|(_, e, _)| *e != entity);
+            self.notifier.remove((pane, entity));
+
+            // If that was the last tab, close current pane
+            if tab_model.iter().next().is_none() {
+                if let Some((_state, sibling)) = self.pane_model.panes.close(self.pane_model.focus)
+                {
+                    self.terminal_ids.remove(&self.pane_model.focus);
+                    self.pane_model.focus = sibling;
+                } else {
+                    match self.config.on_last_tab_close {
+                        LastTabClose::CloseWindow => {
+                            //Last pane, closing window
+                            return self.close_window();
+                        }
+                        LastTabClose::NewTab => {
+                            //Last pane, keep window open with a fresh tab
+                            return self.update(Message::TabNew { inherit_cwd: false });
+                        }
+                    }
+                }
+            }
+        }
+
+        self.update_title(None)
+    }
+
+    // Close several tabs of the focused pane, asking first for those running a program, and
+    // activate the given tab unless one of them is waiting for confirmation
+    fn close_tabs(
+        &mut self,
+        entities: Vec<segmented_button::Entity>,
+        activate: segmented_button::Entity,
+    ) -> Command<Message> {
+        let mut commands = Vec::with_capacity(entities.len() + 1);
+        for entity in entities {
+            commands.push(self.update(Message::TabClose(Some(entity))));
+        }
+        if let Some(tab_model) = self.pane_model.active_mut() {
+            let confirming = tab_model
+                .iter()
+                .any(|entity| tab_model.data::<TabCloseConfirm>(entity).is_some());
+            if !confirming && tab_model.position(activate).is_some() {
+                tab_model.activate(activate);
+            }
+        }
+        commands.push(self.update_title(None));
+        Command::batch(commands)
+    }
+
+    // Working directory of the focused tab. New terminals fall back to the profile directory
+    // if it cannot be read.
+    fn active_working_directory(&self) -> Option<PathBuf> {
+        let tab_model = self.pane_model.active()?;
+        let terminal = tab_model.data::<Mutex<Terminal>>(tab_model.active())?;
+        let terminal = terminal.lock().unwrap();
+        terminal.current_working_directory()
+    }
+
+    fn terminal_colors(&self, profile_id_opt: Option<ProfileId>) -> Option<&TermColors> {
+        self.themes
+            .get(&self.config.syntax_theme(profile_id_opt))
+            .or_else(|| match self.config.color_scheme_kind() {
+                ColorSchemeKind::Dark => self
+                    .themes
+                    .get(&(config::COSMIC_THEME_DARK.to_string(), ColorSchemeKind::Dark)),
+                ColorSchemeKind::Light => self.themes.get(&(
+                    config::COSMIC_THEME_LIGHT.to_string(),
+                    ColorSchemeKind::Light,
+                )),
+            })
+    }
+
+    /// Open a terminal requested by launching another instance, in a new tab or window
+    fn launch(
+        &mut self,
+        request: LaunchRequest,
+        activation_token_opt: Option<String>,
+    ) -> Command<Message> {
+        if request.new_window {
+            match env::current_exe() {
+                Ok(exe) => {
+                    let mut command = process::Command::new(&exe);
+                    command.arg("--separate");
+                    if let Some(current_dir) = &request.current_dir_opt {
+                        command.current_dir(current_dir);
+                    }
+                    if let Some(working_directory) = &request.working_directory_opt {
+                        command.arg("--working-directory").arg(working_directory);
+                    }
+                    if let Some(title) = &request.title_opt {
+                        command.arg("--title").arg(title);
+                    }
+                    if !request.command.is_empty() {
+                        command.arg("--").args(&request.command);
+                    }
+                    // The new window is focused with the token of the launching process
+                    if let Some(activation_token) = activation_token_opt {
+                        command.env("XDG_ACTIVATION_TOKEN", activation_token);
+                    }
+                    if let Err(err) = command.spawn() {
+                        log::error!("failed to execute {:?}: {}", exe, err);
+                    }
+                }
+                Err(err) => {
+                    log::error!("failed to get current executable path: {}", err);
+                }
+            }
+            return Command::none();
+        }
+
+        let profile_id_opt =
+            if request.command.is_empty() && request.working_directory_opt.is_none() {
+                self.get_default_profile()
+            } else {
+                // Commands run where they were launched from unless a directory is given
+                let working_directory_opt = request.working_directory_opt.or(request
+                    .current_dir_opt
+                    .filter(|_| !request.command.is_empty()));
+                let mut args = request.command.into_iter();
+                self.startup_options = Some(tty::Options {
+                    shell: args
+                        .next()
+                        .map(|program| tty::Shell::new(program, args.collect())),
+                    working_directory: working_directory_opt,
+                    ..tty::Options::default()
+                });
+                None
+            };
+        self.startup_title_opt = request.title_opt;
+        Command::batch([
+            self.create_and_focus_new_terminal(self.pane_model.focus, profile_id_opt, None),
+            //TODO: activate with activation_token_opt. Wayland compositors may refuse focus
+            // requested without it, but the winit backend can only use a token when it creates a
+            // window, so the launching process's token only helps for new windows.
+            window::gain_focus(window::Id::MAIN),
+        ])
+    }
+
     fn create_and_focus_new_terminal(
         &mut self,
         pane: pane_grid::Pane,
-        profile_id_opt: Option<ProfileId>,
+        mut profile_id_opt: Option<ProfileId>,
+        working_directory_opt: Option<PathBuf>,
     ) -> Command<Message> {
         self.pane_model.focus = pane;
-        match &self.term_event_tx_opt {
-            Some(term_event_tx) => {
-                let colors = self
-                    .themes
-                    .get(&self.config.syntax_theme(profile_id_opt))
-                    .or_else(|| match self.config.color_scheme_kind() {
-                        ColorSchemeKind::Dark => self
-                            .themes
-                            .get(&(config::COSMIC_THEME_DARK.to_string(), ColorSchemeKind::Dark)),
-                        ColorSchemeKind::Light => self.themes.get(&(
-                            config::COSMIC_THEME_LIGHT.to_string(),
-                            ColorSchemeKind::Light,
-                        )),
-                    });
-                match colors {
-                    Some(colors) => {
-                        let current_pane = self.pane_model.focus;
-                        if let Some(tab_model) = self.pane_model.active_mut() {
-                            // Use the profile options, startup options, or defaults
-                            let (options, tab_title_override) = match profile_id_opt
-                                .and_then(|profile_id| self.config.profiles.get(&profile_id))
-                            {
-                                Some(profile) => {
-                                    let mut shell = None;
-                                    if let Some(mut args) = shlex::split(&profile.command) {
-                                        if !args.is_empty() {
-                                            let command = args.remove(0);
-                                            shell = Some(tty::Shell::new(command, args));
-                                        }
-                                    }
-                                    let working_directory = (!profile.working_directory.is_empty())
-                                        .then(|| profile.working_directory.clone().into());
-
-                                    let options = tty::Options {
-                                        shell,
-                                        working_directory,
-                                        hold: profile.hold,
-                                        env: HashMap::new(),
-                                    };
-                                    let tab_title_override = if profile.tab_title.is_empty() {
-                                        None
-                                    } else {
-                                        Some(profile.tab_title.clone())
-                                    };
-                                    (options, tab_title_override)
-                                }
-                                None => (self.startup_options.take().unwrap_or_default(), None),
-                            };
-                            let entity = tab_model
-                                .insert()
-                                .text(
-                                    tab_title_override
-                                        .clone()
-                                        .unwrap_or_else(|| fl!("new-terminal")),
-                                )
-                                .closable()
-                                .activate()
-                                .id();
-                            match Terminal::new(
-                                current_pane,
-                                entity,
-                                term_event_tx.clone(),
-                                self.term_config.clone(),
-                                options,
-                                &self.config,
-                                *colors,
-                                profile_id_opt,
-                                tab_title_override,
-                            ) {
-                                Ok(mut terminal) => {
-                                    terminal.set_config(&self.config, &self.themes, self.zoom_adj);
-                                    tab_model
-                                        .data_set::<Mutex<Terminal>>(entity, Mutex::new(terminal));
-                                }
-                                Err(err) => {
-                                    log::error!("failed to open terminal: {}", err);
-                                    // Clean up partially created tab
-                                    return self.update(Message::TabClose(Some(entity)));
-                                }
-                            }
-                        } else {
-                            log::error!("Found no active pane");
+        if self.term_event_tx_opt.is_none() {
+            log::warn!("tried to create new tab before having event channel");
+            return self.update_title(Some(pane));
+        }
+        if self.terminal_colors(profile_id_opt).is_none() {
+            log::error!(
+                "failed to find terminal theme {:?}",
+                self.config.syntax_theme(profile_id_opt)
+            );
+            //TODO: fall back to known good theme
+            return self.update_title(Some(pane));
+        }
+
+        // Command line options replace the default profile of the first tab
+        if self.startup_options.is_some() && profile_id_opt == self.get_default_profile() {
+            profile_id_opt = None;
+        }
+
+        // Use the profile options, startup options, or defaults
+        let (mut options, mut tab_title_override) = match profile_id_opt
+            .and_then(|profile_id| self.config.profiles.get(&profile_id))
+        {
+            Some(profile) => {
+                let mut shell = None;
+                if let Some(args) = profile.ssh_args() {
+                    shell = Some(tty::Shell::new("ssh".to_string(), args));
+                } else if let Some(mut args) = shlex::split(&profile.command) {
+                    if !args.is_empty() {
+                        let command = args.remove(0);
+                        // Only the user's shell is known to take -l, other commands
+                        // run as given
+                        let is_shell = env::var("SHELL").map_or(false, |user_shell| {
+                            Path::new(&user_shell).file_name() == Path::new(&command).file_name()
+                        });
+                        if profile.login_shell && is_shell && args.is_empty() {
+                            args.push("-l".to_string());
                         }
+                        shell = Some(tty::Shell::new(command, args));
                     }
-                    None => {
-                        log::error!(
-                            "failed to find terminal theme {:?}",
-                            self.config.syntax_theme(profile_id_opt)
-                        );
-                        //TODO: fall back to known good theme
+                }
+                if shell.is_none() && profile.login_shell {
+                    let command = env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+                    shell = Some(tty::Shell::new(command, vec!["-l".to_string()]));
+                }
+                // Extra variables are given as KEY=value pairs, quoted like a command line
+                let env = shlex::split(&profile.environment)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|var| match var.split_once('=') {
+                        Some((key, value)) if !key.is_empty() => {
+                            Some((key.to_string(), value.to_string()))
+                        }
+                        _ => {
+                            log::warn!("ignoring invalid environment variable {:?}", var);
+                            None
+                        }
+                    })
+                    .collect();
+                let working_directory = (!profile.working_directory.is_empty())
+                    .then(|| profile.working_directory.clone().into());
+
+                let options = tty::Options {
+                    shell,
+                    working_directory,
+                    hold: profile.exit_action != ExitAction::Close,
+                    env,
+                };
+                let tab_title_override = if profile.tab_title.is_empty() {
+                    None
+                } else {
+                    Some(profile.tab_title.clone())
+                };
+                (options, tab_title_override)
+            }
+            None => (self.startup_options.take().unwrap_or_default(), None),
+        };
+        // A title from the command line is used whichever profile the first tab runs
+        if let Some(title) = self.startup_title_opt.take() {
+            tab_title_override = Some(title);
+        }
+        if working_directory_opt.is_some() {
+            options.working_directory = working_directory_opt;
+        }
+
+        let Some(tab_model) = self.pane_model.active_mut() else {
+            log::error!("Found no active pane");
+            return self.update_title(Some(pane));
+        };
+        let entity = tab_model
+            .insert()
+            .text(
+                tab_title_override
+                    .clone()
+                    .unwrap_or_else(|| fl!("new-terminal")),
+            )
+            .closable()
+            .activate()
+            .id();
+        self.spawn_terminal(pane, entity, options, profile_id_opt, tab_title_override);
+        self.update_title(Some(pane))
+    }
+
+    // Update the connection state of a remote profile terminal, which is shown in the tab title
+    fn remote_state_set(
+        &mut self,
+        pane: pane_grid::Pane,
+        entity: segmented_button::Entity,
+        state: RemoteState,
+    ) -> Command<Message> {
+        let Some(tab_model) = self.pane_model.panes.get_mut(pane) else {
+            return Command::none();
+        };
+        let title_opt = match tab_model.data::<Mutex<Terminal>>(entity) {
+            Some(terminal) => {
+                let mut terminal = terminal.lock().unwrap();
+                let has_override = terminal.tab_title_override.is_some();
+                let title_from_program = terminal.title_from_program;
+                match &mut terminal.remote_opt {
+                    // Reaching the host is only noticed while connecting
+                    Some(remote)
+                        if remote.state != state
+                            && (state != RemoteState::Connected
+                                || remote.state == RemoteState::Connecting) =>
+                    {
+                        remote.state = state;
+                        // Once connected, a title set by the remote shell is kept
+                        (!has_override && !(state == RemoteState::Connected && title_from_program))
+                            .then(|| remote_title(remote))
                     }
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        match title_opt {
+            Some(title) => {
+                tab_model.text_set(entity, title);
+                self.update_title(Some(pane))
+            }
+            None => Command::none(),
+        }
+    }
+
+    // Open a tab that plays an asciicast recording, read-only as there is nothing to type into
+    #[cfg(unix)]
+    fn open_playback(&mut self, path: PathBuf) -> Command<Message> {
+        let events = match playback::load(&path) {
+            Ok(events) => events,
+            Err(err) => {
+                log::error!("failed to load recording {:?}: {}", path, err);
+                return Command::none();
+            }
+        };
+        let title = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        self.open_playback_tab(title, events, self.term_config.clone(), true)
+    }
+
+    // Open a read-only tab showing output events, with controls for playing them if wanted
+    #[cfg(unix)]
+    fn open_playback_tab(
+        &mut self,
+        title: String,
+        events: Vec<PlaybackEvent>,
+        term_config: term::Config,
+        controls: bool,
+    ) -> Command<Message> {
+        let Some(term_event_tx) = self.term_event_tx_opt.clone() else {
+            log::warn!("tried to play a recording before having event channel");
+            return Command::none();
+        };
+        let Some(colors) = self.terminal_colors(None).copied() else {
+            log::error!(
+                "failed to find terminal theme {:?}",
+                self.config.syntax_theme(None)
+            );
+            return Command::none();
+        };
+        let (playback, control) = match PlaybackPty::new(events) {
+            Ok(ok) => ok,
+            Err(err) => {
+                log::error!("failed to play {:?}: {}", title, err);
+                return Command::none();
+            }
+        };
+
+        let pane = self.pane_model.focus;
+        let Some(tab_model) = self.pane_model.active_mut() else {
+            log::error!("Found no active pane");
+            return Command::none();
+        };
+        let entity = tab_model
+            .insert()
+            .text(title.clone())
+            .closable()
+            .activate()
+            .id();
+        match Terminal::new_playback(
+            pane,
+            entity,
+            term_event_tx,
+            term_config,
+            &self.config,
+            colors,
+            playback,
+        ) {
+            Ok(mut terminal) => {
+                terminal.set_config(&self.config, &self.themes, self.zoom_adj);
+                terminal.tab_title_override = Some(title);
+                terminal.set_read_only(true);
+                tab_model.data_set::<Mutex<Terminal>>(entity, Mutex::new(terminal));
+                if controls {
+                    tab_model.data_set(entity, Playback(control));
                 }
             }
-            None => {
-                log::warn!("tried to create new tab before having event channel");
+            Err(err) => {
+                log::error!("failed to play {:?}: {}", title, err);
+                tab_model.remove(entity);
             }
         }
         self.update_title(Some(pane))
     }
+
+    // Whether any tab is playing a recording, so its position needs to be redrawn
+    #[cfg(unix)]
+    fn playback_playing(&self) -> bool {
+        self.pane_model.panes.iter().any(|(_pane, tab_model)| {
+            tab_model.iter().any(|entity| {
+                tab_model
+                    .data::<Playback>(entity)
+                    .is_some_and(|Playback(control)| !control.lock().unwrap().paused)
+            })
+        })
+    }
+
+    #[cfg(unix)]
+    fn playback_control(
+        &self,
+        entity: segmented_button::Entity,
+    ) -> Option<std::sync::Arc<Mutex<PlaybackControl>>> {
+        self.pane_model.panes.iter().find_map(|(_pane, tab_model)| {
+            let Playback(control) = tab_model.data::<Playback>(entity)?;
+            Some(control.clone())
+        })
+    }
+
+    // Spawn a terminal into an existing tab. On failure the error is stored in the tab so it
+    // can be shown along with a retry button.
+    fn spawn_terminal(
+        &mut self,
+        pane: pane_grid::Pane,
+        entity: segmented_button::Entity,
+        options: tty::Options,
+        profile_id_opt: Option<ProfileId>,
+        tab_title_override: Option<String>,
+    ) {
+        let Some(term_event_tx) = self.term_event_tx_opt.clone() else {
+            log::warn!("tried to create new tab before having event channel");
+            return;
+        };
+        let Some(colors) = self.terminal_colors(profile_id_opt).copied() else {
+            log::error!(
+                "failed to find terminal theme {:?}",
+                self.config.syntax_theme(profile_id_opt)
+            );
+            return;
+        };
+        let Some(tab_model) = self.pane_model.panes.get_mut(pane) else {
+            log::error!("failed to find pane for new terminal");
+            return;
+        };
+
+        tab_model.data_remove::<TerminalSpawnError>(entity);
+        match Terminal::new(
+            pane,
+            entity,
+            term_event_tx,
+            self.term_config.clone(),
+            options.clone(),
+            &self.config,
+            colors,
+            profile_id_opt,
+            tab_title_override.clone(),
+        ) {
+            Ok(mut terminal) => {
+                terminal.set_config(&self.config, &self.themes, self.zoom_adj);
+                terminal.remote_opt = profile_id_opt
+                    .and_then(|profile_id| self.config.profiles.get(&profile_id))
+                    .and_then(|profile| profile.ssh_destination())
+                    .map(|destination| RemoteConnection {
+                        destination,
+                        state: RemoteState::Connecting,
+                    });
+                if tab_title_override.is_none() {
+                    if let Some(remote) = &terminal.remote_opt {
+                        tab_model.text_set(entity, remote_title(remote));
+                    } else if let Some(profile) = profile_id_opt
+                        .and_then(|profile_id| self.config.profiles.get(&profile_id))
+                        .filter(|profile| profile.is_serial())
+                    {
+                        // There is no process to take a title from
+                        tab_model.text_set(entity, profile.serial_device.trim().to_string());
+                    }
+                }
+                tab_model.data_set::<Mutex<Terminal>>(entity, Mutex::new(terminal));
+            }
+            Err(err) => {
+                log::error!("failed to open terminal: {}", err);
+                let command_opt = match profile_id_opt
+                    .and_then(|profile_id| self.config.profiles.get(&profile_id))
+                {
+                    Some(profile) if profile.is_serial() => {
+                        Some(profile.serial_device.trim().to_string())
+                    }
+                    Some(profile) if profile.is_remote() => Some("ssh".to_string()),
+                    Some(profile) => {
+                        shlex::split(&profile.command).and_then(|args| args.into_iter().next())
+                    }
+                    None if options.shell.is_none() => env::var("SHELL").ok(),
+                    None => None,
+                };
+                let message = match command_opt {
+                    Some(command) => fl!(
+                        "spawn-error",
+                        command = command.as_str(),
+                        error = err.to_string()
+                    ),
+                    None => fl!("spawn-error-unknown", error = err.to_string()),
+                };
+                tab_model.data_set::<TerminalSpawnError>(
+                    entity,
+                    TerminalSpawnError {
+                        message,
+                        options,
+                        profile_id_opt,
+                        tab_title_override,
+                    },
+                );
+            }
+        }
+    }
 }
 
 /// Implement [`Application`] to integrate with COSMIC.
@@ -1304,6 +2994,70 @@ impl Application for App {
         }
 
         let app_themes = vec![fl!("match-desktop"), fl!("dark"), fl!("light")];
+        let paste_filter_names = vec![
+            fl!("paste-filter-none"),
+            fl!("paste-filter-strip"),
+            fl!("paste-filter-escape"),
+        ];
+        let tab_bar_visibility_names = vec![
+            fl!("tab-bar-always"),
+            fl!("tab-bar-multiple"),
+            fl!("tab-bar-never"),
+        ];
+        let tab_bar_position_names = vec![fl!("tab-bar-top"), fl!("tab-bar-bottom")];
+        let cursor_shape_names = vec![
+            fl!("cursor-shape-block"),
+            fl!("cursor-shape-beam"),
+            fl!("cursor-shape-underline"),
+        ];
+        let background_image_scale_names = vec![
+            fl!("background-image-fill"),
+            fl!("background-image-fit"),
+            fl!("background-image-stretch"),
+            fl!("background-image-center"),
+        ];
+        let exit_action_names = vec![
+            fl!("exit-action-close"),
+            fl!("exit-action-hold"),
+            fl!("exit-action-restart"),
+        ];
+        let output_log_format_names =
+            vec![fl!("output-log-format-raw"), fl!("output-log-format-plain")];
+        let serial_baud_rate_names = SERIAL_BAUD_RATES
+            .iter()
+            .map(|baud_rate| baud_rate.to_string())
+            .collect();
+        let serial_parity_names = vec![
+            fl!("serial-parity-none"),
+            fl!("serial-parity-even"),
+            fl!("serial-parity-odd"),
+        ];
+
+        // The alacritty grid caps scrollback at 100,000 lines
+        let scrollbacks = vec![1_000, 5_000, 10_000, 50_000, 100_000];
+        let scrollback_names = scrollbacks
+            .iter()
+            .map(|lines| fl!("scrollback-lines", lines = *lines))
+            .collect();
+
+        // Zero disables command notifications
+        let command_notify_seconds = vec![0, 5, 10, 30, 60];
+        let command_notify_names = vec![
+            fl!("command-notify-never"),
+            fl!("command-notify-seconds", seconds = 5),
+            fl!("command-notify-seconds", seconds = 10),
+            fl!("command-notify-seconds", seconds = 30),
+            fl!("command-notify-seconds", seconds = 60),
+        ];
+
+        // Contrast ratios multiplied by 10, the WCAG levels for large and normal text and AAA
+        let minimum_contrasts = vec![10, 30, 45, 70];
+        let minimum_contrast_names = vec![
+            fl!("minimum-contrast-off"),
+            fl!("minimum-contrast-ratio", ratio = "3"),
+            fl!("minimum-contrast-ratio", ratio = "4.5"),
+            fl!("minimum-contrast-ratio", ratio = "7"),
+        ];
 
         let font_name_faces_map = {
             let mut font_name_faces_map = BTreeMap::<_, Vec<_>>::new();
@@ -1384,6 +3138,13 @@ impl Application for App {
             Normal, SemiExpanded, Expanded, ExtraExpanded, UltraExpanded,
         };
 
+        let mut line_height_names = Vec::new();
+        let mut line_heights = Vec::new();
+        for line_height in (100..=200).step_by(10) {
+            line_height_names.push(format!("{:.1}", f32::from(line_height) / 100.0));
+            line_heights.push(line_height);
+        }
+
         let mut zoom_step_names = Vec::new();
         let mut zoom_steps = Vec::new();
         for zoom_step in [25, 50, 75, 100, 150, 200] {
@@ -1395,13 +3156,34 @@ impl Application for App {
         let mut terminal_ids = HashMap::new();
         terminal_ids.insert(pane_model.focus, widget::Id::unique());
 
+        let cursor_color_input = color_input_value(flags.config.cursor_color);
+        let cursor_text_color_input = color_input_value(flags.config.cursor_text_color);
+
         let mut app = Self {
             core,
             pane_model,
             config_handler: flags.config_handler,
+            key_binds: key_binds(&flags.config),
             config: flags.config,
-            key_binds: key_binds(),
+            state_handler: flags.state_handler,
+            session_opt: flags.session_opt,
+            session_saved: AtomicBool::new(false),
             app_themes,
+            paste_filter_names,
+            tab_bar_visibility_names,
+            tab_bar_position_names,
+            cursor_shape_names,
+            background_image_scale_names,
+            exit_action_names,
+            output_log_format_names,
+            serial_baud_rate_names,
+            serial_parity_names,
+            scrollback_names,
+            scrollbacks,
+            command_notify_names,
+            command_notify_seconds,
+            minimum_contrast_names,
+            minimum_contrasts,
             font_names,
             font_size_names,
             font_sizes,
@@ -1412,6 +3194,8 @@ impl Application for App {
             curr_font_weights: Vec::new(),
             curr_font_stretch_names: Vec::new(),
             curr_font_stretches: Vec::new(),
+            line_height_names,
+            line_heights,
             zoom_adj: 0,
             zoom_step_names,
             zoom_steps,
@@ -1422,25 +3206,89 @@ impl Application for App {
             dialog_opt: None,
             terminal_ids,
             find: false,
+            find_case_sensitive: false,
+            find_regex: false,
             find_search_id: widget::Id::unique(),
             find_search_value: String::new(),
             startup_options: flags.startup_options,
+            startup_title_opt: flags.startup_title_opt,
+            #[cfg(unix)]
+            startup_playback_opt: flags.playback_opt,
+            #[cfg(unix)]
+            playback_speed_names: PLAYBACK_SPEEDS
+                .iter()
+                .map(|speed| format!("{}×", speed))
+                .collect(),
             term_config: flags.term_config,
             term_event_tx_opt: None,
             color_scheme_errors: Vec::new(),
             color_scheme_expanded: None,
             color_scheme_renaming: None,
+            cursor_color_input,
+            cursor_text_color_input,
+            color_scheme_editing: None,
             color_scheme_rename_id: widget::Id::unique(),
+            tab_rename_id: widget::Id::unique(),
+            tab_click_opt: None,
+            tab_history: HashMap::new(),
+            tab_history_pos_opt: None,
             color_scheme_tab_model: widget::segmented_button::Model::default(),
             profile_expanded: None,
+            profile_names: Vec::new(),
+            profile_ids: Vec::new(),
             show_advanced_font_settings: false,
             modifiers: Modifiers::empty(),
+            window_focused: true,
+            zoom_indicator_opt: None,
+            bell_flash_opt: None,
+            broadcast_opt: None,
+            restart_delays: HashMap::new(),
+            restarts_pending: Vec::new(),
+            blinking: true,
+            notifier: Notifier::default(),
+            perf_overlay: flags.perf_overlay,
+            fullscreen: flags.fullscreen,
+            perf_stats: PerfStats::default(),
+            window_size: flags.window_size,
+            window_maximized: flags.maximized,
         };
 
         app.set_curr_font_weights_and_stretches();
-        let command = Command::batch([app.update_config(), app.update_title(None)]);
+        let mut commands = vec![app.update_config(), app.update_title(None)];
+        if app.fullscreen {
+            commands.push(window::change_mode(
+                window::Id::MAIN,
+                window::Mode::Fullscreen,
+            ));
+        } else if app.window_maximized {
+            commands.push(window::maximize(window::Id::MAIN, true));
+        }
+
+        (app, Command::batch(commands))
+    }
 
-        (app, command)
+    fn dbus_activation(&mut self, msg: DbusActivationMessage) -> Command<Message> {
+        match msg.msg {
+            DbusActivationDetails::Activate => {
+                self.launch(LaunchRequest::default(), msg.activation_token)
+            }
+            DbusActivationDetails::ActivateAction { action, .. } => {
+                match ron::from_str::<LaunchRequest>(&action) {
+                    Ok(request) => self.launch(request, msg.activation_token),
+                    Err(err) => {
+                        log::warn!("invalid launch request {:?}: {}", action, err);
+                        Command::none()
+                    }
+                }
+            }
+            DbusActivationDetails::Open { .. } => Command::none(),
+        }
+    }
+
+    fn on_close_requested(&self, _id: window::Id) -> Option<Message> {
+        self.save_session();
+        self.save_window_state();
+        None
     }
 
     //TODO: currently the first escape unfocuses, and the second calls this function
@@ -1452,22 +3300,48 @@ impl Application for App {
             // Close find if open
             self.find = false;
             self.find_search_value.clear();
+            self.update_find_search();
         }
 
         // Focus correct widget
         self.update_focus()
     }
 
-    fn on_context_drawer(&mut self) -> Command<Message> {
-        if self.core.window.show_context {
-            Command::none()
+    fn find_pattern(&self) -> String {
+        if self.find_search_value.is_empty() {
+            String::new()
         } else {
-            self.update_focus()
+            terminal::search_pattern(
+                &self.find_search_value,
+                self.find_case_sensitive,
+                self.find_regex,
+            )
         }
     }
 
-    /// Handle application events here.
-    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+    // Highlight matches of the find bar query in all tabs
+    fn update_find_search(&mut self) {
+        let pattern = self.find_pattern();
+        for (_pane, tab_model) in self.pane_model.panes.iter() {
+            for entity in tab_model.iter() {
+                if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                    let mut terminal = terminal.lock().unwrap();
+                    terminal.set_search(&pattern);
+                }
+            }
+        }
+    }
+
+    fn on_context_drawer(&mut self) -> Command<Message> {
+        if self.core.window.show_context {
+            Command::none()
+        } else {
+            self.update_focus()
+        }
+    }
+
+    /// Handle application events here.
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         // Helper for updating config values efficiently
         macro_rules! config_set {
             ($name: ident, $value: expr) => {
@@ -1491,6 +3365,32 @@ impl Application for App {
         }
 
         match message {
+            Message::AlternateScroll(alternate_scroll) => {
+                if alternate_scroll != self.config.alternate_scroll {
+                    self.config.alternate_scroll = alternate_scroll;
+                    return self.save_config();
+                }
+            }
+            Message::BellAudible(bell_audible) => {
+                config_set!(bell_audible, bell_audible);
+            }
+            Message::BellFlashTick => {
+                if self
+                    .bell_flash_opt
+                    .is_some_and(|(_entity, instant)| instant.elapsed() >= BELL_FLASH_DURATION)
+                {
+                    self.bell_flash_opt = None;
+                }
+            }
+            Message::BellTabBadge(bell_tab_badge) => {
+                config_set!(bell_tab_badge, bell_tab_badge);
+            }
+            Message::BellUrgent(bell_urgent) => {
+                config_set!(bell_urgent, bell_urgent);
+            }
+            Message::BellVisual(bell_visual) => {
+                config_set!(bell_visual, bell_visual);
+            }
             Message::AppTheme(app_theme) => {
                 self.config.app_theme = app_theme;
                 return self.save_config();
@@ -1500,6 +3400,12 @@ impl Application for App {
             }
             Message::ColorSchemeDelete(color_scheme_kind, color_scheme_id) => {
                 self.color_scheme_expanded = None;
+                if matches!(
+                    &self.color_scheme_editing,
+                    Some((kind, id, _values)) if kind == &color_scheme_kind && id == &color_scheme_id
+                ) {
+                    self.color_scheme_editing = None;
+                }
                 self.config
                     .color_schemes_mut(color_scheme_kind)
                     .remove(&color_scheme_id);
@@ -1569,6 +3475,57 @@ impl Application for App {
                     }
                 }
             }
+            Message::ColorSchemeEdit(color_scheme_kind, color_scheme_id) => {
+                self.color_scheme_expanded = None;
+                if let Some(color_scheme) = self
+                    .config
+                    .color_schemes(color_scheme_kind)
+                    .get(&color_scheme_id)
+                {
+                    let values = ColorSchemeField::all()
+                        .map(|field| (field, color_input_value(color_scheme.color(field))))
+                        .collect();
+                    self.color_scheme_editing = Some((color_scheme_kind, color_scheme_id, values));
+                    self.preview_color_scheme();
+                }
+            }
+            Message::ColorSchemeEditColor(field, value) => {
+                let Some((color_scheme_kind, color_scheme_id, values)) =
+                    &mut self.color_scheme_editing
+                else {
+                    return Command::none();
+                };
+                let (color_scheme_kind, color_scheme_id) = (*color_scheme_kind, *color_scheme_id);
+
+                // Empty values fall back to automatic colors, partial values are kept until valid
+                let color_opt_opt = if value.trim().is_empty() {
+                    Some(None)
+                } else {
+                    HexColor::parse_rgb(value.trim()).ok().map(Some)
+                };
+                if let Some((_field, field_value)) = values.iter_mut().find(|(f, _)| *f == field) {
+                    *field_value = value;
+                }
+
+                if let Some(color_opt) = color_opt_opt {
+                    if let Some(color) = self
+                        .config
+                        .color_schemes_mut(color_scheme_kind)
+                        .get_mut(&color_scheme_id)
+                        .and_then(|color_scheme| color_scheme.color_mut(field))
+                    {
+                        *color = color_opt;
+                        let command = self.save_color_schemes(color_scheme_kind);
+                        self.preview_color_scheme();
+                        return command;
+                    }
+                }
+            }
+            Message::ColorSchemeEditDone => {
+                self.color_scheme_editing = None;
+                // Restore the configured colors of the previewing terminal
+                return self.update_config();
+            }
             Message::ColorSchemeExpand(color_scheme_kind, color_scheme_id) => {
                 self.color_scheme_expanded = Some((color_scheme_kind, color_scheme_id));
             }
@@ -1590,7 +3547,7 @@ impl Application for App {
                 if let DialogResult::Open(paths) = result {
                     self.color_scheme_errors.clear();
                     for path in &paths {
-                        let mut file = match fs::File::open(path) {
+                        let data = match fs::read_to_string(path) {
                             Ok(ok) => ok,
                             Err(err) => {
                                 self.color_scheme_errors
@@ -1598,7 +3555,7 @@ impl Application for App {
                                 continue;
                             }
                         };
-                        match ron::de::from_reader::<_, ColorScheme>(&mut file) {
+                        match terminal_theme::parse_color_scheme(path, &data) {
                             Ok(color_scheme) => {
                                 // Get next color_scheme ID
                                 let color_scheme_id = self
@@ -1629,6 +3586,35 @@ impl Application for App {
                     return widget::text_input::focus(self.color_scheme_rename_id.clone());
                 }
             }
+            Message::ColorSchemeNew(color_scheme_kind) => {
+                // Start from the color scheme currently in use
+                let theme_name = match color_scheme_kind {
+                    ColorSchemeKind::Dark => &self.config.syntax_theme_dark,
+                    ColorSchemeKind::Light => &self.config.syntax_theme_light,
+                };
+                let mut color_scheme = self
+                    .themes
+                    .get(&(theme_name.clone(), color_scheme_kind))
+                    .map(|colors| ColorScheme::from((theme_name.as_str(), colors)))
+                    .unwrap_or_default();
+                color_scheme.name = fl!("new-color-scheme");
+
+                // Get next color_scheme ID
+                let color_scheme_id = self
+                    .config
+                    .color_schemes(color_scheme_kind)
+                    .last_key_value()
+                    .map(|(id, _)| ColorSchemeId(id.0 + 1))
+                    .unwrap_or_default();
+                self.config
+                    .color_schemes_mut(color_scheme_kind)
+                    .insert(color_scheme_id, color_scheme);
+                let command = self.save_color_schemes(color_scheme_kind);
+                return Command::batch([
+                    command,
+                    self.update(Message::ColorSchemeEdit(color_scheme_kind, color_scheme_id)),
+                ]);
+            }
             Message::ColorSchemeRenameSubmit => {
                 if let Some((color_scheme_kind, color_scheme_id, color_scheme_name)) =
                     self.color_scheme_renaming.take()
@@ -1653,9 +3639,80 @@ impl Application for App {
                     }
                 }
             }
+            Message::ConfirmCloseRunning(confirm_close_running) => {
+                config_set!(confirm_close_running, confirm_close_running);
+            }
+            Message::CopyOnSelect(copy_on_select) => {
+                config_set!(copy_on_select, copy_on_select);
+            }
+            Message::CopyHtml(copy_html) => {
+                config_set!(copy_html, copy_html);
+            }
+            Message::ClipboardRead(clipboard_read) => {
+                config_set!(clipboard_read, clipboard_read);
+            }
+            Message::CursorBlink(cursor_blink) => {
+                if cursor_blink != self.config.cursor_blink {
+                    self.config.cursor_blink = cursor_blink;
+                    return self.save_config();
+                }
+            }
+            Message::BlinkTick => {
+                // Stop ticking once no visible terminal has a blinking cursor or text
+                let mut blinking = false;
+                for (_pane, tab_model) in self.pane_model.panes.iter() {
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(tab_model.active()) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.blink();
+                        blinking |= terminal.blinking();
+                    }
+                }
+                self.blinking = blinking;
+            }
+            Message::CursorShape(cursor_shape) => {
+                if cursor_shape != self.config.cursor_shape {
+                    self.config.cursor_shape = cursor_shape;
+                    return self.save_config();
+                }
+            }
+            Message::CursorColor(value) => {
+                // Empty values fall back to automatic colors, partial values are kept until valid
+                let color_opt_opt = if value.trim().is_empty() {
+                    Some(None)
+                } else {
+                    HexColor::parse_rgb(value.trim()).ok().map(Some)
+                };
+                self.cursor_color_input = value;
+                if let Some(color_opt) = color_opt_opt {
+                    if color_opt != self.config.cursor_color {
+                        self.config.cursor_color = color_opt;
+                        return self.save_config();
+                    }
+                }
+            }
+            Message::CursorTextColor(value) => {
+                let color_opt_opt = if value.trim().is_empty() {
+                    Some(None)
+                } else {
+                    HexColor::parse_rgb(value.trim()).ok().map(Some)
+                };
+                self.cursor_text_color_input = value;
+                if let Some(color_opt) = color_opt_opt {
+                    if color_opt != self.config.cursor_text_color {
+                        self.config.cursor_text_color = color_opt;
+                        return self.save_config();
+                    }
+                }
+            }
             Message::Config(config) => {
                 if config != self.config {
                     log::info!("update config");
+                    if config.cursor_color != self.config.cursor_color {
+                        self.cursor_color_input = color_input_value(config.cursor_color);
+                    }
+                    if config.cursor_text_color != self.config.cursor_text_color {
+                        self.cursor_text_color_input = color_input_value(config.cursor_text_color);
+                    }
                     //TODO: update syntax theme by clearing tabs, only if needed
                     self.config = config;
                     return self.update_config();
@@ -1666,23 +3723,65 @@ impl Application for App {
                     let entity = entity_opt.unwrap_or_else(|| tab_model.active());
                     if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                         let terminal = terminal.lock().unwrap();
-                        let term = terminal.term.lock();
-                        if let Some(text) = term.selection_to_string() {
-                            return Command::batch([clipboard::write(text), self.update_focus()]);
-                        }
+                        return Command::batch([
+                            self.copy_selection(&terminal),
+                            self.update_focus(),
+                        ]);
                     }
                 } else {
                     log::warn!("Failed to get focused pane");
                 }
                 return self.update_focus();
             }
+            Message::CopyLastOutput(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let terminal = terminal.lock().unwrap();
+                        if let Some(text) = terminal.last_command_output() {
+                            return Command::batch([clipboard::write(text), self.update_focus()]);
+                        }
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::CopyText(text_opt) => {
+                if let Some(text) = text_opt {
+                    return clipboard::write(text);
+                }
+            }
+            Message::CopyWorkingDirectory(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    let working_directory_opt = tab_model
+                        .data::<Mutex<Terminal>>(entity)
+                        .and_then(|terminal| terminal.lock().unwrap().current_working_directory());
+                    if let Some(working_directory) = working_directory_opt {
+                        return Command::batch([
+                            clipboard::write(working_directory.to_string_lossy().into_owned()),
+                            self.update_focus(),
+                        ]);
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::CopyModeYank(entity) => {
+                // Copy the selection and leave copy mode, like yanking in vi
+                if let Some(tab_model) = self.pane_model.active() {
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        let command = self.copy_selection(&terminal);
+                        terminal.toggle_copy_mode();
+                        return command;
+                    }
+                }
+            }
             Message::CopyPrimary(entity_opt) => {
                 if let Some(tab_model) = self.pane_model.active() {
                     let entity = entity_opt.unwrap_or_else(|| tab_model.active());
                     if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                         let terminal = terminal.lock().unwrap();
-                        let term = terminal.term.lock();
-                        if let Some(text) = term.selection_to_string() {
+                        if let Some(text) = terminal.selection_text() {
                             return Command::batch([
                                 clipboard::write_primary(text),
                                 self.update_focus(),
@@ -1773,6 +3872,15 @@ impl Application for App {
                     log::warn!("failed to find bold font weight with index {}", index);
                 }
             },
+            Message::DefaultLineHeight(index) => match self.line_heights.get(index) {
+                Some(line_height) => {
+                    self.config.line_height_mul_100 = *line_height;
+                    return self.save_config();
+                }
+                None => {
+                    log::warn!("failed to find line height with index {}", index);
+                }
+            },
             Message::DefaultZoomStep(index) => match self.zoom_steps.get(index) {
                 Some(zoom_step) => {
                     self.config.font_size_zoom_step_mul_100 = *zoom_step;
@@ -1783,6 +3891,146 @@ impl Application for App {
                     log::warn!("failed to find zoom step with index {}", index);
                 }
             },
+            Message::ExportScrollback(entity_opt, html) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if self.dialog_opt.is_none() {
+                        let (dialog, command) = Dialog::new(
+                            DialogKind::SaveFile {
+                                filename: if html {
+                                    "scrollback.html".to_string()
+                                } else {
+                                    "scrollback.txt".to_string()
+                                },
+                            },
+                            None,
+                            Message::DialogMessage,
+                            move |result| Message::ExportScrollbackResult(entity, html, result),
+                        );
+                        self.dialog_opt = Some(dialog);
+                        return command;
+                    }
+                }
+            }
+            Message::ExportScrollbackResult(entity, html, result) => {
+                //TODO: show errors in UI
+                self.dialog_opt = None;
+                if let DialogResult::Open(paths) = result {
+                    let path = &paths[0];
+                    // The tab may have been moved to another pane while the dialog was open
+                    let contents_opt =
+                        self.pane_model.panes.iter().find_map(|(_pane, tab_model)| {
+                            let terminal = tab_model.data::<Mutex<Terminal>>(entity)?;
+                            let terminal = terminal.lock().unwrap();
+                            Some(if html {
+                                terminal.history_html()
+                            } else {
+                                terminal.history_text()
+                            })
+                        });
+                    match contents_opt {
+                        Some(contents) => {
+                            if let Err(err) = fs::write(path, contents) {
+                                log::error!("failed to export scrollback to {:?}: {}", path, err);
+                            }
+                        }
+                        None => log::error!("failed to find tab {:?}", entity),
+                    }
+                }
+            }
+            Message::RecordSession(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let terminal = terminal.lock().unwrap();
+                        if terminal.recording_path().is_some() {
+                            terminal.stop_recording();
+                            return Command::none();
+                        }
+                    }
+                    if self.dialog_opt.is_none() {
+                        let (dialog, command) = Dialog::new(
+                            DialogKind::SaveFile {
+                                filename: "session.cast".to_string(),
+                            },
+                            None,
+                            Message::DialogMessage,
+                            move |result| Message::RecordSessionResult(entity, result),
+                        );
+                        self.dialog_opt = Some(dialog);
+                        return command;
+                    }
+                }
+            }
+            Message::RecordSessionResult(entity, result) => {
+                //TODO: show errors in UI
+                self.dialog_opt = None;
+                if let DialogResult::Open(paths) = result {
+                    let path = &paths[0];
+                    // The tab may have been moved to another pane while the dialog was open
+                    let result_opt = self.pane_model.panes.iter().find_map(|(_pane, tab_model)| {
+                        let terminal = tab_model.data::<Mutex<Terminal>>(entity)?;
+                        let terminal = terminal.lock().unwrap();
+                        Some(terminal.start_recording(path))
+                    });
+                    match result_opt {
+                        Some(Ok(())) => {}
+                        Some(Err(err)) => {
+                            log::error!("failed to record session to {:?}: {}", path, err);
+                        }
+                        None => log::error!("failed to find tab {:?}", entity),
+                    }
+                }
+            }
+            Message::LogOutput(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let terminal = terminal.lock().unwrap();
+                        if terminal.output_log_path().is_some() {
+                            terminal.stop_output_log();
+                            return Command::none();
+                        }
+                    }
+                    if self.dialog_opt.is_none() {
+                        let (dialog, command) = Dialog::new(
+                            DialogKind::SaveFile {
+                                filename: "output.log".to_string(),
+                            },
+                            None,
+                            Message::DialogMessage,
+                            move |result| Message::LogOutputResult(entity, result),
+                        );
+                        self.dialog_opt = Some(dialog);
+                        return command;
+                    }
+                }
+            }
+            Message::LogOutputResult(entity, result) => {
+                //TODO: show errors in UI
+                self.dialog_opt = None;
+                if let DialogResult::Open(paths) = result {
+                    let path = &paths[0];
+                    // The tab may have been moved to another pane while the dialog was open
+                    let result_opt = self.pane_model.panes.iter().find_map(|(_pane, tab_model)| {
+                        let terminal = tab_model.data::<Mutex<Terminal>>(entity)?;
+                        let terminal = terminal.lock().unwrap();
+                        // Logged the way the profile of the tab logs, if it has one
+                        let format = terminal
+                            .profile_id_opt
+                            .and_then(|profile_id| self.config.profiles.get(&profile_id))
+                            .map_or(OutputLogFormat::Raw, |profile| profile.output_log_format);
+                        Some(terminal.start_output_log(path, format))
+                    });
+                    match result_opt {
+                        Some(Ok(())) => {}
+                        Some(Err(err)) => {
+                            log::error!("failed to log output to {:?}: {}", path, err);
+                        }
+                        None => log::error!("failed to find tab {:?}", entity),
+                    }
+                }
+            }
             Message::DialogMessage(dialog_message) => {
                 if let Some(dialog) = &mut self.dialog_opt {
                     return dialog.update(dialog_message);
@@ -1795,8 +4043,7 @@ impl Application for App {
                         let entity = tab_model.active();
                         if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                             let terminal = terminal.lock().unwrap();
-                            let term = terminal.term.lock();
-                            if let Some(text) = term.selection_to_string() {
+                            if let Some(text) = terminal.selection_text() {
                                 self.find_search_value = text;
                             }
                         }
@@ -1806,6 +4053,7 @@ impl Application for App {
                 } else {
                     self.find_search_value.clear();
                 }
+                self.update_find_search();
 
                 // Focus correct input
                 return self.update_focus();
@@ -1816,7 +4064,7 @@ impl Application for App {
                         let entity = tab_model.active();
                         if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                             let mut terminal = terminal.lock().unwrap();
-                            terminal.search(&self.find_search_value, true);
+                            terminal.search(&self.find_pattern(), true);
                         }
                     }
                 }
@@ -1830,7 +4078,7 @@ impl Application for App {
                         let entity = tab_model.active();
                         if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                             let mut terminal = terminal.lock().unwrap();
-                            terminal.search(&self.find_search_value, false);
+                            terminal.search(&self.find_pattern(), false);
                         }
                     }
                 }
@@ -1838,8 +4086,17 @@ impl Application for App {
                 // Focus correct input
                 return self.update_focus();
             }
+            Message::FindCaseSensitive(find_case_sensitive) => {
+                self.find_case_sensitive = find_case_sensitive;
+                self.update_find_search();
+            }
+            Message::FindRegex(find_regex) => {
+                self.find_regex = find_regex;
+                self.update_find_search();
+            }
             Message::FindSearchValueChanged(value) => {
                 self.find_search_value = value;
+                self.update_find_search();
             }
             Message::MiddleClick(pane, entity_opt) => {
                 self.pane_model.focus = pane;
@@ -1851,9 +4108,67 @@ impl Application for App {
                     }),
                 ]);
             }
+            Message::FileDropped(path) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(tab_model.active()) {
+                        let terminal = terminal.lock().unwrap();
+                        // Quoting a lossy conversion would insert a different path
+                        let Some(path_str) = path.to_str() else {
+                            log::warn!("ignoring dropped path that is not UTF-8: {:?}", path);
+                            return self.update_focus();
+                        };
+                        match shlex::try_quote(path_str) {
+                            // Ctrl+drop types a command to change to a dropped directory instead,
+                            // which runs once the user presses Enter
+                            Ok(quoted) if self.modifiers.control() && path.is_dir() => {
+                                terminal.input_scroll(format!("cd {}", quoted).into_bytes());
+                            }
+                            // Separated by spaces, as each dropped file arrives on its own
+                            Ok(quoted) => terminal.paste(format!("{} ", quoted)),
+                            Err(err) => log::warn!("failed to quote {:?}: {}", path, err),
+                        }
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::FileRefCommand(file_ref_command) => {
+                config_set!(file_ref_command, file_ref_command);
+            }
             Message::FocusFollowMouse(focus_follow_mouse) => {
                 config_set!(focus_follow_mouse, focus_follow_mouse);
             }
+            Message::HintActivate(entity, action, text) => match action {
+                HintAction::Copy => return clipboard::write(text),
+                HintAction::Open => {
+                    // Paths are relative to the directory of the shell
+                    let mut url = text;
+                    if !url.contains(':') {
+                        let path_opt = match url.strip_prefix("~/") {
+                            Some(rest) => {
+                                env::var_os("HOME").map(|home| PathBuf::from(home).join(rest))
+                            }
+                            None => self.active_working_directory().map(|cwd| cwd.join(&url)),
+                        };
+                        if let Some(path) = path_opt {
+                            url = path.to_string_lossy().into_owned();
+                        }
+                    }
+                    return self.update(Message::LaunchUrl(url));
+                }
+                HintAction::Paste => {
+                    return self.update(Message::PasteValue(Some(entity), text));
+                }
+            },
+            Message::Hints(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.start_hints(&self.config.hints);
+                    }
+                }
+                return self.update_focus();
+            }
             Message::Key(modifiers, key) => {
                 for (key_bind, action) in &self.key_binds {
                     if key_bind.matches(modifiers, &key) {
@@ -1861,26 +4176,140 @@ impl Application for App {
                     }
                 }
             }
-            Message::LaunchUrl(url) => {
-                if let Err(err) = open::that_detached(&url) {
-                    log::warn!("failed to open {:?}: {}", url, err);
+            Message::LaunchFileRef(file_ref) => {
+                let file = file_ref.path.to_string_lossy().into_owned();
+                match self
+                    .config
+                    .file_ref_command(&file, file_ref.line, file_ref.column)
+                {
+                    Some(mut argv) => {
+                        // Run in a new tab, so editors that need a terminal work
+                        let command = argv.remove(0);
+                        self.startup_options = Some(tty::Options {
+                            shell: Some(tty::Shell::new(command, argv)),
+                            working_directory: file_ref.path.parent().map(|dir| dir.to_path_buf()),
+                            ..tty::Options::default()
+                        });
+                        return self.create_and_focus_new_terminal(
+                            self.pane_model.focus,
+                            None,
+                            None,
+                        );
+                    }
+                    None => return self.update(Message::LaunchUrl(file)),
                 }
             }
+            Message::LaunchUrl(url) => match self.config.opener_command(&url) {
+                // Spawned with an argv array so the url is never interpreted by a shell
+                Some(argv) => match process::Command::new(&argv[0]).args(&argv[1..]).spawn() {
+                    Ok(mut child) => {
+                        // Reap the child so it does not linger as a zombie
+                        thread::spawn(move || child.wait());
+                    }
+                    Err(err) => {
+                        log::warn!("failed to open {:?} with {:?}: {}", url, argv, err);
+                    }
+                },
+                None => {
+                    if let Err(err) = open::that_detached(&url) {
+                        log::warn!("failed to open {:?}: {}", url, err);
+                    }
+                }
+            },
             Message::Modifiers(modifiers) => {
                 self.modifiers = modifiers;
+                // Releasing Ctrl ends cycling through recent tabs on the one that was reached
+                if !modifiers.control() && self.tab_history_pos_opt.take().is_some() {
+                    return self.update_title(None);
+                }
             }
             Message::MouseEnter(pane) => {
                 self.pane_model.focus = pane;
                 return self.update_focus();
             }
+            Message::MouseReleased => {
+                // A left click release may finish a selection
+                if self.config.copy_on_select {
+                    return self.update(Message::CopyPrimary(None));
+                }
+            }
+            Message::NewTabInheritCwd(new_tab_inherit_cwd) => {
+                config_set!(new_tab_inherit_cwd, new_tab_inherit_cwd);
+            }
+            Message::OnLastTabClose(on_last_tab_close) => {
+                config_set!(on_last_tab_close, on_last_tab_close);
+            }
             Message::Opacity(opacity) => {
                 config_set!(opacity, cmp::min(100, opacity));
             }
+            Message::Padding(padding) => {
+                config_set!(padding, cmp::min(32, padding));
+            }
+            Message::OpenWorkingDirectory(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    let working_directory_opt = tab_model
+                        .data::<Mutex<Terminal>>(entity)
+                        .and_then(|terminal| terminal.lock().unwrap().current_working_directory());
+                    if let Some(working_directory) = working_directory_opt {
+                        if let Err(err) = open::that_detached(&working_directory) {
+                            log::warn!("failed to open {:?}: {}", working_directory, err);
+                        }
+                    }
+                }
+            }
             Message::PaneClicked(pane) => {
                 self.pane_model.focus = pane;
                 return self.update_title(Some(pane));
             }
+            Message::PaneClose => {
+                // Close tab by tab so running programs are confirmed and the rest still close
+                if self.config.confirm_close_running {
+                    if let Some(tab_model) = self.pane_model.active() {
+                        let running = tab_model.iter().any(|entity| {
+                            tab_model
+                                .data::<Mutex<Terminal>>(entity)
+                                .map_or(false, |terminal| {
+                                    terminal.lock().unwrap().foreground_process_name().is_some()
+                                })
+                        });
+                        if running {
+                            let active = tab_model.active();
+                            let entities = tab_model.iter().collect();
+                            return self.close_tabs(entities, active);
+                        }
+                    }
+                }
+                let pane = self.pane_model.focus;
+                if let Some((_state, sibling)) = self.pane_model.panes.close(pane) {
+                    // Dropping the tab model shuts down all terminals in the pane
+                    self.terminal_ids.remove(&pane);
+                    self.pane_model.focus = sibling;
+                    return self.update_title(Some(sibling));
+                }
+                match self.config.on_last_tab_close {
+                    LastTabClose::CloseWindow => {
+                        //Last pane, closing window
+                        return self.close_window();
+                    }
+                    LastTabClose::NewTab => {
+                        //Last pane, replace all of its tabs with a fresh tab
+                        if let Some(tab_model) = self.pane_model.active_mut() {
+                            let entities: Vec<_> = tab_model.iter().collect();
+                            for entity in entities {
+                                tab_model.remove(entity);
+                            }
+                        }
+                        return self.update(Message::TabNew { inherit_cwd: false });
+                    }
+                }
+            }
             Message::PaneSplit(axis) => {
+                let working_directory_opt = if self.config.new_tab_inherit_cwd {
+                    self.active_working_directory()
+                } else {
+                    None
+                };
                 let result = self.pane_model.panes.split(
                     axis,
                     self.pane_model.focus,
@@ -1888,8 +4317,11 @@ impl Application for App {
                 );
                 if let Some((pane, _)) = result {
                     self.terminal_ids.insert(pane, widget::Id::unique());
-                    let command =
-                        self.create_and_focus_new_terminal(pane, self.get_default_profile());
+                    let command = self.create_and_focus_new_terminal(
+                        pane,
+                        self.get_default_profile(),
+                        working_directory_opt,
+                    );
                     self.pane_model.panes_created += 1;
                     return command;
                 }
@@ -1925,6 +4357,59 @@ impl Application for App {
                     None => message::none(),
                 });
             }
+            Message::PasteConfirm(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    if let Some(PasteConfirm(value)) = tab_model.data_remove::<PasteConfirm>(entity)
+                    {
+                        // The value is already filtered, and filtering it again leaves it as shown
+                        if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                            terminal.lock().unwrap().paste(value);
+                        }
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::PasteConfirmCancel(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    tab_model.data_remove::<PasteConfirm>(entity);
+                }
+                return self.update_focus();
+            }
+            Message::PasteConfirmPrompt(paste_confirm) => {
+                config_set!(paste_confirm, paste_confirm);
+            }
+            Message::PasteJoinLines(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    if let Some(PasteConfirm(value)) = tab_model.data_mut::<PasteConfirm>(entity) {
+                        *value = value
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                    }
+                }
+            }
+            Message::PasteStripNewline(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    if let Some(PasteConfirm(value)) = tab_model.data_mut::<PasteConfirm>(entity) {
+                        let len = value.trim_end_matches(['\r', '\n']).len();
+                        value.truncate(len);
+                    }
+                }
+            }
+            Message::PasteFilter(paste_filter) => {
+                if paste_filter != self.config.paste_filter {
+                    self.config.paste_filter = paste_filter;
+                    return self.save_config();
+                }
+            }
+            Message::PasteLoneCrToLf(paste_lone_cr_to_lf) => {
+                if paste_lone_cr_to_lf != self.config.paste_lone_cr_to_lf {
+                    self.config.paste_lone_cr_to_lf = paste_lone_cr_to_lf;
+                    return self.save_config();
+                }
+            }
             Message::PastePrimary(entity_opt) => {
                 return clipboard::read_primary(move |value_opt| match value_opt {
                     Some(value) => message::app(Message::PasteValue(entity_opt, value)),
@@ -1932,22 +4417,224 @@ impl Application for App {
                 });
             }
             Message::PasteValue(entity_opt, value) => {
-                if let Some(tab_model) = self.pane_model.active() {
+                let paste_confirm = self.config.paste_confirm;
+                if let Some(tab_model) = self.pane_model.active_mut() {
                     let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    let mut confirm_value_opt = None;
                     if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                         let terminal = terminal.lock().unwrap();
-                        terminal.paste(value);
+                        if paste_confirm && terminal.paste_needs_confirm(&value) {
+                            // The preview shows the text as it will be sent
+                            confirm_value_opt = Some(terminal.filter_paste(&value));
+                        } else {
+                            terminal.paste(value);
+                        }
+                    }
+                    // Show the pasted text first instead of possibly running it right away
+                    if let Some(confirm_value) = confirm_value_opt {
+                        tab_model.data_set(entity, PasteConfirm(confirm_value));
+                        return Command::none();
                     }
                 }
                 return self.update_focus();
             }
+            Message::PerfTick => {
+                // Statistics of every visible terminal are reset, only the focused one is shown
+                for (pane, tab_model) in self.pane_model.panes.iter() {
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(tab_model.active()) {
+                        let perf_stats = terminal.lock().unwrap().take_perf_stats();
+                        if *pane == self.pane_model.focus {
+                            self.perf_stats = perf_stats;
+                        }
+                    }
+                }
+                self.perf_stats.queue_depth = self
+                    .term_event_tx_opt
+                    .as_ref()
+                    .map_or(0, |tx| tx.max_capacity() - tx.capacity());
+            }
+            #[cfg(unix)]
+            Message::PlaybackPause(entity) => {
+                if let Some(control) = self.playback_control(entity) {
+                    let mut control = control.lock().unwrap();
+                    if control.paused && control.finished() {
+                        // Playing again once finished starts over
+                        control.seek(0.0);
+                        control.paused = false;
+                    } else {
+                        control.paused = !control.paused;
+                    }
+                }
+            }
+            #[cfg(unix)]
+            Message::PlaybackSeek(entity, position) => {
+                if let Some(control) = self.playback_control(entity) {
+                    control.lock().unwrap().seek(position.into());
+                }
+            }
+            #[cfg(unix)]
+            Message::PlaybackSpeed(entity, index) => {
+                if let (Some(control), Some(speed)) =
+                    (self.playback_control(entity), PLAYBACK_SPEEDS.get(index))
+                {
+                    control.lock().unwrap().speed = *speed;
+                }
+            }
+            #[cfg(unix)]
+            Message::PlaybackTick => {
+                // Redrawn to show the position, and pause once finished so play starts over
+                for (_pane, tab_model) in self.pane_model.panes.iter() {
+                    for entity in tab_model.iter() {
+                        if let Some(Playback(control)) = tab_model.data::<Playback>(entity) {
+                            let mut control = control.lock().unwrap();
+                            if control.finished() {
+                                control.paused = true;
+                            }
+                        }
+                    }
+                }
+            }
+            Message::RestartTick => {
+                let now = Instant::now();
+                let (due, pending) = self
+                    .restarts_pending
+                    .drain(..)
+                    .partition(|(_, _, instant)| *instant <= now);
+                self.restarts_pending = pending;
+                let mut commands = Vec::new();
+                for (pane, entity, _) in due {
+                    // The tab may have been closed while waiting
+                    let Some(terminal) = self
+                        .pane_model
+                        .panes
+                        .get(pane)
+                        .and_then(|tab_model| tab_model.data::<Mutex<Terminal>>(entity))
+                    else {
+                        self.restart_delays.remove(&entity);
+                        continue;
+                    };
+                    let (options, profile_id_opt, tab_title_override) = {
+                        let terminal = terminal.lock().unwrap();
+                        (
+                            terminal.options.clone(),
+                            terminal.profile_id_opt,
+                            terminal.tab_title_override.clone(),
+                        )
+                    };
+                    self.spawn_terminal(pane, entity, options, profile_id_opt, tab_title_override);
+                    commands.push(self.update_title(Some(pane)));
+                }
+                return Command::batch(commands);
+            }
+            Message::ProcessTitleTick => {
+                let mut focus_changed = false;
+                for (pane, tab_model) in self.pane_model.panes.iter_mut() {
+                    let entities: Vec<_> = tab_model.iter().collect();
+                    for entity in entities {
+                        let title_opt = match tab_model.data::<Mutex<Terminal>>(entity) {
+                            Some(terminal) => {
+                                let terminal = terminal.lock().unwrap();
+                                if terminal.tab_title_override.is_some()
+                                    || terminal.title_from_program
+                                    || terminal.remote_opt.is_some()
+                                {
+                                    continue;
+                                }
+                                terminal.process_title(&self.config.tab_title_format)
+                            }
+                            None => continue,
+                        };
+                        if let Some(title) = title_opt {
+                            if tab_model.text(entity) != Some(title.as_str()) {
+                                focus_changed |=
+                                    *pane == self.pane_model.focus && tab_model.active() == entity;
+                                tab_model.text_set(entity, title);
+                            }
+                        }
+                    }
+                }
+                if focus_changed {
+                    return self.update_title(None);
+                }
+            }
             Message::ProfileCollapse(_profile_id) => {
                 self.profile_expanded = None;
             }
             Message::ProfileCommand(profile_id, text) => {
                 if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
-                    profile.command = text;
-                    return self.save_profiles();
+                    profile.command = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSerialBaudRate(profile_id, index) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    if let Some(baud_rate) = SERIAL_BAUD_RATES.get(index) {
+                        profile.serial_baud_rate = *baud_rate;
+                        return self.save_profiles();
+                    }
+                }
+            }
+            Message::ProfileSerialDevice(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.serial_device = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSerialParity(profile_id, index) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.serial_parity = match index {
+                        1 => SerialParity::Even,
+                        2 => SerialParity::Odd,
+                        _ => SerialParity::None,
+                    };
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSshHost(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.ssh_host = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSshIdentityFile(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.ssh_identity_file = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSshPort(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.ssh_port = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileSshUser(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.ssh_user = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileBackgroundImage(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.background_image = text;
+                    return Command::batch([self.save_profiles(), self.update_config()]);
+                }
+            }
+            Message::ProfileBackgroundImageDim(profile_id, dim) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.background_image_dim = dim;
+                    return Command::batch([self.save_profiles(), self.update_config()]);
+                }
+            }
+            Message::ProfileBackgroundImageScale(profile_id, index) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.background_image_scale = match index {
+                        1 => BackgroundImageScale::Fit,
+                        2 => BackgroundImageScale::Stretch,
+                        3 => BackgroundImageScale::Center,
+                        _ => BackgroundImageScale::Fill,
+                    };
+                    return Command::batch([self.save_profiles(), self.update_config()]);
                 }
             }
             Message::ProfileDirectory(profile_id, text) => {
@@ -1956,12 +4643,28 @@ impl Application for App {
                     return self.save_profiles();
                 }
             }
+            Message::ProfileEnvironment(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.environment = text;
+                    return self.save_profiles();
+                }
+            }
             Message::ProfileExpand(profile_id) => {
                 self.profile_expanded = Some(profile_id);
             }
-            Message::ProfileHold(profile_id, hold) => {
+            Message::ProfileExitAction(profile_id, index) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.exit_action = match index {
+                        1 => ExitAction::Hold,
+                        2 => ExitAction::Restart,
+                        _ => ExitAction::Close,
+                    };
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileLoginShell(profile_id, login_shell) => {
                 if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
-                    profile.hold = hold;
+                    profile.login_shell = login_shell;
                     return self.save_profiles();
                 }
             }
@@ -1971,6 +4674,21 @@ impl Application for App {
                     return self.save_profiles();
                 }
             }
+            Message::ProfileOutputLogFile(profile_id, text) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.output_log_file = text;
+                    return self.save_profiles();
+                }
+            }
+            Message::ProfileOutputLogFormat(profile_id, index) => {
+                if let Some(profile) = self.config.profiles.get_mut(&profile_id) {
+                    profile.output_log_format = match index {
+                        1 => OutputLogFormat::Plain,
+                        _ => OutputLogFormat::Raw,
+                    };
+                    return self.save_profiles();
+                }
+            }
             Message::ProfileNew => {
                 // Get next profile ID
                 let profile_id = self
@@ -1984,7 +4702,11 @@ impl Application for App {
                 return self.save_profiles();
             }
             Message::ProfileOpen(profile_id) => {
-                return self.create_and_focus_new_terminal(self.pane_model.focus, Some(profile_id));
+                return self.create_and_focus_new_terminal(
+                    self.pane_model.focus,
+                    Some(profile_id),
+                    None,
+                );
             }
             Message::ProfileRemove(profile_id) => {
                 // Reset matching terminals to default profile
@@ -2034,6 +4756,123 @@ impl Application for App {
                     return self.save_profiles();
                 }
             }
+            Message::RestoreSession(restore_session) => {
+                config_set!(restore_session, restore_session);
+            }
+            Message::SessionDismiss(pane, entity) => {
+                self.session_opt = None;
+                if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
+                    tab_model.data_remove::<SessionRestoreOffer>(entity);
+                }
+            }
+            Message::SessionRestore(pane, entity) => {
+                let Some(session) = self.session_opt.take() else {
+                    return Command::none();
+                };
+
+                let mut commands = Vec::with_capacity(session.tabs.len());
+                let mut entities = Vec::with_capacity(session.tabs.len());
+                for tab in session.tabs {
+                    // Profiles may have been removed since the session was saved
+                    let profile_id_opt = tab
+                        .profile_id
+                        .filter(|profile_id| self.config.profiles.contains_key(profile_id));
+                    commands.push(self.create_and_focus_new_terminal(
+                        pane,
+                        profile_id_opt,
+                        tab.working_directory,
+                    ));
+                    if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
+                        let new_entity = tab_model.active();
+                        if !tab.title.is_empty() {
+                            tab_model.text_set(new_entity, tab.title);
+                        }
+                        entities.push(new_entity);
+                    }
+                }
+
+                // The restored tabs replace the one that offered them
+                if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
+                    tab_model.remove(entity);
+                    if let Some(active) = entities.get(session.active).or(entities.last()) {
+                        tab_model.activate(*active);
+                    }
+                }
+                commands.push(self.update_title(Some(pane)));
+                return Command::batch(commands);
+            }
+            Message::ScrollOnKeypress(scroll_on_keypress) => {
+                if scroll_on_keypress != self.config.scroll_on_keypress {
+                    self.config.scroll_on_keypress = scroll_on_keypress;
+                    return self.save_config();
+                }
+            }
+            Message::ScrollOnOutput(scroll_on_output) => {
+                if scroll_on_output != self.config.scroll_on_output {
+                    self.config.scroll_on_output = scroll_on_output;
+                    return self.save_config();
+                }
+            }
+            Message::ScrollbackArchive(scrollback_archive) => {
+                if scrollback_archive != self.config.scrollback_archive {
+                    self.config.scrollback_archive = scrollback_archive;
+                    return self.save_config();
+                }
+            }
+            Message::ScrollbackArchiveShow(entity) => {
+                let archive_opt = self.pane_model.panes.iter().find_map(|(_pane, tab_model)| {
+                    let terminal = tab_model.data::<Mutex<Terminal>>(entity)?;
+                    let terminal = terminal.lock().unwrap();
+                    Some((terminal.archived_text(), terminal.archived_lines()))
+                });
+                match archive_opt {
+                    #[cfg(unix)]
+                    Some((Ok(text), lines)) => {
+                        let mut term_config = self.term_config.clone();
+                        // Twice the lines, so none of them are archived again
+                        term_config.scrolling_history = lines.saturating_mul(2);
+                        let events = vec![PlaybackEvent {
+                            time: 0.0,
+                            data: text.replace('\n', "\r\n"),
+                        }];
+                        return self.open_playback_tab(
+                            fl!("archived-history"),
+                            events,
+                            term_config,
+                            false,
+                        );
+                    }
+                    // Shown in a playback tab, which needs a Unix socket
+                    #[cfg(not(unix))]
+                    Some((Ok(_), _)) => {
+                        log::warn!("showing archived history is only supported on Unix");
+                    }
+                    Some((Err(err), _)) => {
+                        log::error!("failed to read scrollback archive: {}", err);
+                    }
+                    None => log::error!("failed to find tab {:?}", entity),
+                }
+            }
+            Message::Scrollback(index) => match self.scrollbacks.get(index) {
+                Some(scrollback) => {
+                    self.config.scrollback_lines = *scrollback;
+                    return self.save_config();
+                }
+                None => {
+                    log::warn!("failed to find scrollback with index {}", index);
+                }
+            },
+            Message::CommandNotify(index) => match self.command_notify_seconds.get(index) {
+                Some(seconds) => {
+                    config_set!(command_notify_seconds, *seconds);
+                }
+                None => {
+                    log::warn!(
+                        "failed to find command notification delay with index {}",
+                        index
+                    );
+                }
+            },
             Message::SelectAll(entity_opt) => {
                 if let Some(tab_model) = self.pane_model.active() {
                     let entity = entity_opt.unwrap_or_else(|| tab_model.active());
@@ -2050,12 +4889,57 @@ impl Application for App {
                     return self.save_config();
                 }
             }
+            Message::FontLigatures(font_ligatures) => {
+                if font_ligatures != self.config.font_ligatures {
+                    self.config.font_ligatures = font_ligatures;
+                    return self.save_config();
+                }
+            }
             Message::UseBrightBold(use_bright_bold) => {
                 if use_bright_bold != self.config.use_bright_bold {
                     self.config.use_bright_bold = use_bright_bold;
                     return self.save_config();
                 }
             }
+            Message::BroadcastInput(source_pane, source_entity, key_input) => {
+                let Some(broadcast) = self.broadcast_opt else {
+                    return Command::none();
+                };
+                for (pane, tab_model) in self.pane_model.panes.iter() {
+                    let entities: Vec<_> = match broadcast {
+                        Broadcast::Panes => vec![tab_model.active()],
+                        Broadcast::Tabs => tab_model.iter().collect(),
+                    };
+                    for entity in entities {
+                        if (*pane, entity) == (source_pane, source_entity) {
+                            continue;
+                        }
+                        // Encoded for each terminal, since their modes can differ
+                        if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                            terminal.lock().unwrap().input_key(&key_input);
+                        }
+                    }
+                }
+            }
+            Message::BroadcastToggle(broadcast) => {
+                self.broadcast_opt = if self.broadcast_opt == Some(broadcast) {
+                    None
+                } else {
+                    Some(broadcast)
+                };
+                return self.update_focus();
+            }
+            Message::MinimumContrast(index) => match self.minimum_contrasts.get(index) {
+                Some(minimum_contrast) => {
+                    if *minimum_contrast != self.config.minimum_contrast_mul_10 {
+                        self.config.minimum_contrast_mul_10 = *minimum_contrast;
+                        return self.save_config();
+                    }
+                }
+                None => {
+                    log::warn!("failed to find minimum contrast with index {}", index);
+                }
+            },
             Message::ShowAdvancedFontSettings(show) => {
                 self.show_advanced_font_settings = show;
             }
@@ -2080,12 +4964,36 @@ impl Application for App {
                     }
                 }
             }
+            Message::TabActivityIndicator(tab_activity_indicator) => {
+                config_set!(tab_activity_indicator, tab_activity_indicator);
+            }
+            Message::TabBarPosition(tab_bar_position) => {
+                config_set!(tab_bar_position, tab_bar_position);
+            }
+            Message::TabBarVisibility(tab_bar_visibility) => {
+                config_set!(tab_bar_visibility, tab_bar_visibility);
+            }
+            Message::TabTitleFormat(tab_title_format) => {
+                config_set!(tab_title_format, tab_title_format);
+            }
             Message::TabActivate(entity) => {
                 if let Some(tab_model) = self.pane_model.active_mut() {
                     tab_model.activate(entity);
+                    tab_model.icon_remove(entity);
                 }
                 return self.update_title(None);
             }
+            Message::TabClicked(entity) => {
+                // Double clicking a tab renames it
+                let now = Instant::now();
+                if let Some((last_entity, instant)) = self.tab_click_opt.take() {
+                    if last_entity == entity && now - instant <= TAB_DOUBLE_CLICK_INTERVAL {
+                        return self.update(Message::TabRename(Some(entity)));
+                    }
+                }
+                self.tab_click_opt = Some((entity, now));
+                return self.update(Message::TabActivate(entity));
+            }
             Message::TabActivateJump(pos) => {
                 if let Some(tab_model) = self.pane_model.active() {
                     // Length is always at least one so there shouldn't be a division by zero
@@ -2104,36 +5012,146 @@ impl Application for App {
                 }
             }
             Message::TabClose(entity_opt) => {
+                // Ask before closing a tab with a program running in the foreground
+                if self.config.confirm_close_running {
+                    if let Some(tab_model) = self.pane_model.active_mut() {
+                        let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                        let process_name_opt =
+                            tab_model
+                                .data::<Mutex<Terminal>>(entity)
+                                .and_then(|terminal| {
+                                    terminal.lock().unwrap().foreground_process_name()
+                                });
+                        if let Some(process_name) = process_name_opt {
+                            tab_model.data_set(entity, TabCloseConfirm(process_name));
+                            tab_model.activate(entity);
+                            return self.update_title(None);
+                        }
+                    }
+                }
+                return self.close_tab(entity_opt);
+            }
+            Message::TabCloseCancel(entity) => {
                 if let Some(tab_model) = self.pane_model.active_mut() {
+                    tab_model.data_remove::<TabCloseConfirm>(entity);
+                }
+            }
+            Message::TabCloseForce(entity) => {
+                return self.close_tab(Some(entity));
+            }
+            Message::TabCloseOthers(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
                     let entity = entity_opt.unwrap_or_else(|| tab_model.active());
-
-                    // Activate closest item
-                    if let Some(position) = tab_model.position(entity) {
-                        if position > 0 {
-                            tab_model.activate_position(position - 1);
-                        } else {
-                            tab_model.activate_position(position + 1);
+                    let others: Vec<_> = tab_model.iter().filter(|e| *e != entity).collect();
+                    return self.close_tabs(others, entity);
+                }
+            }
+            Message::TabCloseRight(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    let right: Vec<_> = tab_model
+                        .iter()
+                        .skip_while(|e| *e != entity)
+                        .skip(1)
+                        .collect();
+                    return self.close_tabs(right, entity);
+                }
+            }
+            Message::TabContext(entity) => {
+                // The tab context menu acts on the active tab
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    tab_model.activate(entity);
+                    tab_model.icon_remove(entity);
+                }
+                return self.update_title(None);
+            }
+            Message::TabDuplicate(entity_opt) => {
+                let Some(tab_model) = self.pane_model.active() else {
+                    return Command::none();
+                };
+                let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                let position_opt = tab_model.position(entity);
+                let (profile_id_opt, working_directory_opt, tab_title_override, read_only) =
+                    match tab_model.data::<Mutex<Terminal>>(entity) {
+                        Some(terminal) => {
+                            let terminal = terminal.lock().unwrap();
+                            (
+                                terminal.profile_id_opt,
+                                terminal.current_working_directory(),
+                                terminal.tab_title_override.clone(),
+                                terminal.read_only(),
+                            )
+                        }
+                        None => (self.get_default_profile(), None, None, false),
+                    };
+                // Zoom applies to every tab, so the copy is shown at the same size
+                let command = self.create_and_focus_new_terminal(
+                    self.pane_model.focus,
+                    profile_id_opt,
+                    working_directory_opt,
+                );
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    let duplicate = tab_model.active();
+                    if duplicate != entity {
+                        if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(duplicate) {
+                            let mut terminal = terminal.lock().unwrap();
+                            terminal.set_read_only(read_only);
+                            if tab_title_override.is_some() {
+                                terminal.tab_title_override = tab_title_override.clone();
+                                terminal.title_from_program = false;
+                            }
+                        }
+                        if let Some(title) = tab_title_override {
+                            tab_model.text_set(duplicate, title);
+                        }
+                        // Place the copy right after the original
+                        if let Some(position) = position_opt {
+                            tab_model.position_set(duplicate, position + 1);
                         }
                     }
-
-                    // Remove item
-                    tab_model.remove(entity);
-
-                    // If that was the last tab, close current pane
-                    if tab_model.iter().next().is_none() {
-                        if let Some((_state, sibling)) =
-                            self.pane_model.panes.close(self.pane_model.focus)
-                        {
-                            self.terminal_ids.remove(&self.pane_model.focus);
-                            self.pane_model.focus = sibling;
-                        } else {
-                            //Last pane, closing window
-                            return window::close(window::Id::MAIN);
+                }
+                return Command::batch([command, self.update_title(None)]);
+            }
+            Message::TabRename(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    let title = tab_model.text(entity).unwrap_or_default().to_string();
+                    tab_model.data_set(entity, TabRename(title));
+                    tab_model.activate(entity);
+                }
+                return self.update_focus();
+            }
+            Message::TabRenameCancel(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    tab_model.data_remove::<TabRename>(entity);
+                }
+                return self.update_focus();
+            }
+            Message::TabRenameInput(entity, title) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    if let Some(tab_rename) = tab_model.data_mut::<TabRename>(entity) {
+                        tab_rename.0 = title;
+                    }
+                }
+            }
+            Message::TabRenameSubmit(entity) => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    if let Some(TabRename(title)) = tab_model.data_remove::<TabRename>(entity) {
+                        // An empty title goes back to the title set by the program
+                        let title = title.trim().to_string();
+                        let tab_title_override = (!title.is_empty()).then_some(title);
+                        if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                            let mut terminal = terminal.lock().unwrap();
+                            terminal.tab_title_override = tab_title_override.clone();
+                            terminal.title_from_program = false;
                         }
+                        tab_model.text_set(
+                            entity,
+                            tab_title_override.unwrap_or_else(|| fl!("new-terminal")),
+                        );
                     }
                 }
-
-                return self.update_title(None);
+                return Command::batch([self.update_title(None), self.update_focus()]);
             }
             Message::TabContextAction(entity, action) => {
                 if let Some(tab_model) = self.pane_model.active() {
@@ -2174,11 +5192,64 @@ impl Application for App {
                 self.pane_model.focus = pane;
                 return self.update_title(Some(pane));
             }
-            Message::TabNew => {
+            Message::TabContextUrl(entity, url) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        // Close context menu
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.context_menu = None;
+                    }
+                }
+                return self.update(Message::LaunchUrl(url));
+            }
+            Message::TabNew { inherit_cwd } => {
+                let working_directory_opt = if inherit_cwd {
+                    self.active_working_directory()
+                } else {
+                    None
+                };
                 return self.create_and_focus_new_terminal(
                     self.pane_model.focus,
                     self.get_default_profile(),
-                )
+                    working_directory_opt,
+                );
+            }
+            Message::TabMoveLeft => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    let entity = tab_model.active();
+                    if let Some(pos) = tab_model.position(entity).and_then(|i| i.checked_sub(1)) {
+                        tab_model.position_set(entity, pos);
+                    }
+                }
+            }
+            Message::TabMoveRight => {
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    let entity = tab_model.active();
+                    let len = tab_model.iter().count();
+                    if let Some(pos) = tab_model.position(entity).map(|i| i as usize + 1) {
+                        if pos < len {
+                            tab_model.position_set(entity, pos as u16);
+                        }
+                    }
+                }
+            }
+            Message::TabRecent => {
+                let pane = self.pane_model.focus;
+                if let Some(tab_model) = self.pane_model.active_mut() {
+                    let history = self.tab_history.entry(pane).or_default();
+                    history.retain(|entity| tab_model.position(*entity).is_some());
+                    // Tabs that were never active are visited after the used ones
+                    for entity in tab_model.iter() {
+                        if !history.contains(&entity) {
+                            history.push(entity);
+                        }
+                    }
+                    let pos = self.tab_history_pos_opt.map_or(1, |pos| pos + 1) % history.len();
+                    tab_model.activate(history[pos]);
+                    tab_model.icon_remove(history[pos]);
+                    self.tab_history_pos_opt = Some(pos);
+                }
+                return self.update_title(None);
             }
             Message::TabNext => {
                 if let Some(tab_model) = self.pane_model.active() {
@@ -2195,6 +5266,47 @@ impl Application for App {
                     }
                 }
             }
+            Message::TabOpenInWindow(entity_opt) => {
+                let Some(tab_model) = self.pane_model.active() else {
+                    return Command::none();
+                };
+                let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                let (working_directory_opt, tab_title_override) =
+                    match tab_model.data::<Mutex<Terminal>>(entity) {
+                        Some(terminal) => {
+                            let terminal = terminal.lock().unwrap();
+                            (
+                                terminal.current_working_directory(),
+                                terminal.tab_title_override.clone(),
+                            )
+                        }
+                        None => (None, None),
+                    };
+                // The shell cannot move between processes, so a new one is started in the same
+                // directory and this tab is kept
+                //TODO: move the tab itself once windows share one process
+                match env::current_exe() {
+                    Ok(exe) => {
+                        let mut command = process::Command::new(&exe);
+                        command.arg("--separate");
+                        if let Some(working_directory) = working_directory_opt {
+                            command.arg("--working-directory").arg(working_directory);
+                        }
+                        if let Some(title) = tab_title_override {
+                            command.arg("--title").arg(title);
+                        }
+                        match command.spawn() {
+                            Ok(_child) => {}
+                            Err(err) => {
+                                log::error!("failed to execute {:?}: {}", exe, err);
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("failed to get current executable path: {}", err);
+                    }
+                }
+            }
             Message::TabPrev => {
                 if let Some(tab_model) = self.pane_model.active() {
                     let pos = tab_model
@@ -2210,24 +5322,88 @@ impl Application for App {
                     }
                 }
             }
+            Message::TabRespawn(pane, entity) => {
+                let spawn_error_opt = self
+                    .pane_model
+                    .panes
+                    .get_mut(pane)
+                    .and_then(|tab_model| tab_model.data_remove::<TerminalSpawnError>(entity));
+                if let Some(spawn_error) = spawn_error_opt {
+                    self.spawn_terminal(
+                        pane,
+                        entity,
+                        spawn_error.options,
+                        spawn_error.profile_id_opt,
+                        spawn_error.tab_title_override,
+                    );
+                }
+                return self.update_title(Some(pane));
+            }
             Message::TermEvent(pane, entity, event) => {
+                let is_output = matches!(event, TermEvent::Wakeup);
                 match event {
                     TermEvent::Bell => {
-                        //TODO: audible or visible bell options?
+                        let mut commands = Vec::new();
+                        if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
+                            if tab_model.active() == entity {
+                                if self.config.bell_visual {
+                                    self.bell_flash_opt = Some((entity, Instant::now()));
+                                }
+                            } else if self.config.bell_tab_badge {
+                                // Cleared when the tab is activated
+                                tab_model.icon_set(
+                                    entity,
+                                    icon_cache_get("preferences-system-notifications-symbolic", 16),
+                                );
+                            }
+                        }
+                        if self.config.bell_urgent && !self.window_focused {
+                            commands.push(window::request_user_attention(
+                                window::Id::MAIN,
+                                Some(window::UserAttention::Informational),
+                            ));
+                        }
+                        if self.config.bell_audible {
+                            // Plays the bell from the sound theme, if libcanberra is installed
+                            match process::Command::new("canberra-gtk-play")
+                                .arg("--id=bell")
+                                .spawn()
+                            {
+                                Ok(mut child) => {
+                                    // Reap the child so it does not linger as a zombie
+                                    thread::spawn(move || child.wait());
+                                }
+                                Err(err) => {
+                                    log::warn!("failed to play bell sound: {}", err);
+                                }
+                            }
+                        }
+                        return Command::batch(commands);
                     }
                     TermEvent::ClipboardLoad(kind, callback) => {
+                        // Programs may only read the clipboard when allowed in the settings
+                        if !self.config.clipboard_read {
+                            log::info!("refused clipboard load");
+                            return Command::none();
+                        }
+                        // The callback writes the OSC 52 response to the PTY
+                        let pty_write = move |data_opt: Option<String>| {
+                            //TODO: what to do when data_opt is None?
+                            let text = callback(&data_opt.unwrap_or_default());
+                            message::app(Message::TermEvent(
+                                pane,
+                                entity,
+                                TermEvent::PtyWrite(text),
+                            ))
+                        };
                         match kind {
                             term::ClipboardType::Clipboard => {
                                 log::info!("clipboard load");
-                                return clipboard::read(move |data_opt| {
-                                    //TODO: what to do when data_opt is None?
-                                    callback(&data_opt.unwrap_or_default());
-                                    // We don't need to do anything else
-                                    message::none()
-                                });
+                                return clipboard::read(pty_write);
                             }
                             term::ClipboardType::Selection => {
-                                log::info!("TODO: load selection");
+                                log::info!("selection load");
+                                return clipboard::read_primary(pty_write);
                             }
                         }
                     }
@@ -2237,21 +5413,23 @@ impl Application for App {
                             return clipboard::write(data);
                         }
                         term::ClipboardType::Selection => {
-                            log::info!("TODO: store selection");
+                            log::info!("selection store");
+                            return clipboard::write_primary(data);
                         }
                     },
                     TermEvent::ColorRequest(index, f) => {
                         if let Some(tab_model) = self.pane_model.panes.get(pane) {
                             if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
                                 let terminal = terminal.lock().unwrap();
-                                let rgb = terminal.colors()[index].unwrap_or_default();
+                                let rgb = terminal.effective_color(index).unwrap_or_default();
                                 let text = f(rgb);
                                 terminal.input_no_scroll(text.into_bytes());
                             }
                         }
                     }
                     TermEvent::CursorBlinkingChange => {
-                        //TODO: should we blink the cursor?
+                        // The next tick finds out if any cursor still blinks
+                        self.blinking = true;
                     }
                     TermEvent::Exit => {
                         return self.update(Message::TabClose(Some(entity)));
@@ -2268,8 +5446,12 @@ impl Application for App {
                         if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
                             let tab_title_override =
                                 if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
-                                    let terminal = terminal.lock().unwrap();
-                                    terminal.tab_title_override.clone()
+                                    let mut terminal = terminal.lock().unwrap();
+                                    terminal.title_from_program = false;
+                                    terminal
+                                        .tab_title_override
+                                        .clone()
+                                        .or_else(|| terminal.remote_opt.as_ref().map(remote_title))
                                 } else {
                                     None
                                 };
@@ -2293,7 +5475,8 @@ impl Application for App {
                         if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
                             let has_override =
                                 if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
-                                    let terminal = terminal.lock().unwrap();
+                                    let mut terminal = terminal.lock().unwrap();
+                                    terminal.title_from_program = true;
                                     terminal.tab_title_override.is_some()
                                 } else {
                                     false
@@ -2302,19 +5485,160 @@ impl Application for App {
                                 tab_model.text_set(entity, title);
                             }
                         }
-                        return self.update_title(Some(pane));
+                        return self.update_title(Some(pane));
+                    }
+                    TermEvent::MouseCursorDirty | TermEvent::Wakeup => {
+                        let mut trigger_bell = false;
+                        if let Some(tab_model) = self.pane_model.panes.get_mut(pane) {
+                            if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                                let mut terminal = terminal.lock().unwrap();
+                                terminal.output_ready();
+
+                                // Notify about long running commands that finish out of view
+                                if let Some((command, finished)) = terminal.take_finished_command()
+                                {
+                                    let focused = self.window_focused
+                                        && pane == self.pane_model.focus
+                                        && tab_model.active() == entity;
+                                    let seconds = self.config.command_notify_seconds;
+                                    if !focused
+                                        && seconds > 0
+                                        && finished.duration >= Duration::from_secs(seconds.into())
+                                    {
+                                        self.notifier.notify(
+                                            (pane, entity),
+                                            &command_notification_summary(finished.exit_code_opt),
+                                            &command,
+                                        );
+                                    }
+                                }
+
+                                for response in terminal.take_responses() {
+                                    terminal.input_no_scroll(response);
+                                }
+
+                                // The next tick finds out if the blinking text is still visible
+                                if terminal.take_blink_started() {
+                                    self.blinking = true;
+                                }
+
+                                // Notifications from OSC 9 without a title use the tab title
+                                for notification in terminal.take_notifications() {
+                                    let summary = notification.title_opt.unwrap_or_else(|| {
+                                        tab_model.text(entity).unwrap_or_default().to_string()
+                                    });
+                                    self.notifier.notify(
+                                        (pane, entity),
+                                        &summary,
+                                        &notification.body,
+                                    );
+                                }
+
+                                for trigger_match in terminal.take_trigger_matches() {
+                                    match trigger_match.action {
+                                        TriggerAction::Notify => {
+                                            let summary = tab_model
+                                                .text(entity)
+                                                .unwrap_or_default()
+                                                .to_string();
+                                            self.notifier.notify(
+                                                (pane, entity),
+                                                &summary,
+                                                &trigger_match.line,
+                                            );
+                                        }
+                                        TriggerAction::Bell => trigger_bell = true,
+                                        TriggerAction::Command(command) => {
+                                            trigger_command(&command, &trigger_match.line);
+                                        }
+                                        // Drawn with the highlights of the profile
+                                        TriggerAction::Highlight(_) => {}
+                                    }
+                                }
+                            }
+
+                            // Mark background tabs with new output, unless they already have
+                            // an indicator. Cleared when the tab is activated
+                            if is_output
+                                && self.config.tab_activity_indicator
+                                && tab_model.active() != entity
+                                && tab_model.icon(entity).is_none()
+                            {
+                                tab_model
+                                    .icon_set(entity, icon_cache_get("media-record-symbolic", 16));
+                            }
+                        }
+
+                        let bell_command = if trigger_bell {
+                            self.update(Message::TermEvent(pane, entity, TermEvent::Bell))
+                        } else {
+                            Command::none()
+                        };
+
+                        // Output from ssh means the host was reached
+                        if is_output {
+                            return Command::batch([
+                                bell_command,
+                                self.remote_state_set(pane, entity, RemoteState::Connected),
+                            ]);
+                        }
+                        return bell_command;
                     }
-                    TermEvent::MouseCursorDirty | TermEvent::Wakeup => {
-                        if let Some(tab_model) = self.pane_model.panes.get(pane) {
-                            if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
-                                let mut terminal = terminal.lock().unwrap();
-                                terminal.needs_update = true;
+                    TermEvent::ChildExit(exit_code) => {
+                        // Sent on every child exit; with the close action there is nothing to do
+                        // here, as the tab is closed by the exit event
+                        let Some(tab_model) = self.pane_model.panes.get(pane) else {
+                            return Command::none();
+                        };
+                        let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) else {
+                            return Command::none();
+                        };
+                        let mut terminal = terminal.lock().unwrap();
+                        let is_remote = terminal.remote_opt.is_some();
+                        let exit_action = terminal
+                            .profile_id_opt
+                            .and_then(|profile_id| self.config.profiles.get(&profile_id))
+                            .map_or(ExitAction::Close, |profile| profile.exit_action);
+                        match exit_action {
+                            ExitAction::Close => {}
+                            ExitAction::Hold => {
+                                terminal.write_output(&format!(
+                                    "\r\n{}\r\n",
+                                    fl!("process-exited", code = exit_code)
+                                ));
+                                drop(terminal);
+                                if is_remote {
+                                    return self.remote_state_set(
+                                        pane,
+                                        entity,
+                                        RemoteState::Disconnected,
+                                    );
+                                }
+                            }
+                            ExitAction::Restart => {
+                                // Commands that keep failing right away are restarted slower
+                                let delay = match self.restart_delays.get(&entity) {
+                                    Some(delay)
+                                        if terminal.spawn_instant.elapsed() < RESTART_DELAY_MAX =>
+                                    {
+                                        (*delay * 2).min(RESTART_DELAY_MAX)
+                                    }
+                                    _ => RESTART_DELAY_MIN,
+                                };
+                                terminal.write_output(&format!(
+                                    "\r\n{}\r\n",
+                                    fl!(
+                                        "process-restarting",
+                                        code = exit_code,
+                                        seconds = delay.as_secs()
+                                    )
+                                ));
+                                self.restart_delays.insert(entity, delay);
+                                self.restarts_pending
+                                    .push((pane, entity, Instant::now() + delay));
                             }
                         }
                     }
-                    TermEvent::ChildExit(_error_code) => {
-                        //Ignore this for now
-                    }
                 }
             }
             Message::TermEventTx(term_event_tx) => {
@@ -2343,8 +5667,24 @@ impl Application for App {
                 // Set new terminal event channel
                 self.term_event_tx_opt = Some(term_event_tx);
 
+                // Play a recording given on the command line instead of running a shell
+                #[cfg(unix)]
+                if let Some(path) = self.startup_playback_opt.take() {
+                    return self.open_playback(path);
+                }
+
                 // Spawn first tab
-                return self.update(Message::TabNew);
+                let command = self.update(Message::TabNew { inherit_cwd: false });
+
+                // Offer to bring back the tabs from the last session in it
+                if self.session_opt.is_some() {
+                    if let Some(tab_model) = self.pane_model.active_mut() {
+                        let entity = tab_model.active();
+                        tab_model.data_set(entity, SessionRestoreOffer);
+                    }
+                }
+
+                return command;
             }
             Message::ToggleContextPage(context_page) => {
                 if self.context_page == context_page {
@@ -2381,33 +5721,181 @@ impl Application for App {
 
                 self.set_context_title(context_page.title());
             }
+            Message::PromptJump(entity_opt, previous) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.scroll_to_prompt(previous);
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::MarkJump(entity_opt, previous) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.scroll_to_mark(previous);
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::MarkToggle(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.toggle_mark();
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::ToggleCopyMode(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        terminal.toggle_copy_mode();
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::TogglePause(entity_opt) => {
+                // Output is only held back by the Unix PTY reader, so tabs without one ignore this
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        let paused = !terminal.paused();
+                        terminal.set_paused(paused);
+                    }
+                }
+                return self.update_focus();
+            }
+            Message::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                // The header is hidden while fullscreen, like the headerbar setting does
+                self.core.window.show_headerbar = self.config.show_headerbar && !self.fullscreen;
+                return window::change_mode(
+                    window::Id::MAIN,
+                    if self.fullscreen {
+                        window::Mode::Fullscreen
+                    } else {
+                        window::Mode::Windowed
+                    },
+                );
+            }
+            Message::TogglePerfOverlay => {
+                self.perf_overlay = !self.perf_overlay;
+                self.perf_stats = PerfStats::default();
+            }
+            Message::ToggleReadOnly(entity_opt) => {
+                if let Some(tab_model) = self.pane_model.active() {
+                    let entity = entity_opt.unwrap_or_else(|| tab_model.active());
+                    if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
+                        let mut terminal = terminal.lock().unwrap();
+                        let read_only = !terminal.read_only();
+                        terminal.set_read_only(read_only);
+                    }
+                }
+                return self.update_focus();
+            }
             Message::UpdateDefaultProfile((default, profile_id)) => {
                 config_set!(default_profile, default.then_some(profile_id));
             }
             Message::WindowClose => {
-                return window::close(window::Id::MAIN);
+                return self.close_window();
+            }
+            Message::WindowFocused(focused) => {
+                if focused != self.window_focused {
+                    self.window_focused = focused;
+                    // The blink timer stops while unfocused, so the cursor and text are shown
+                    // instead of freezing hidden, and blink in phase once focused again
+                    for (_pane, tab_model) in self.pane_model.panes.iter() {
+                        if let Some(terminal) =
+                            tab_model.data::<Mutex<Terminal>>(tab_model.active())
+                        {
+                            let mut terminal = terminal.lock().unwrap();
+                            terminal.reset_blink();
+                            if focused {
+                                // Catch up on anything skipped while idle
+                                terminal.needs_update = true;
+                            }
+                        }
+                    }
+                    if focused {
+                        // Process titles are not polled while unfocused
+                        return self.update(Message::ProcessTitleTick);
+                    }
+                }
+            }
+            Message::WindowMaximize => {
+                return window::toggle_maximize(window::Id::MAIN);
+            }
+            Message::WindowMinimize => {
+                return window::minimize(window::Id::MAIN, true);
             }
             Message::WindowNew => match env::current_exe() {
-                Ok(exe) => match process::Command::new(&exe).spawn() {
-                    Ok(_child) => {}
-                    Err(err) => {
-                        log::error!("failed to execute {:?}: {}", exe, err);
+                Ok(exe) => {
+                    // Each window runs in its own process with its own tabs
+                    //TODO: open windows in this process, each with its own tabs and events
+                    let mut command = process::Command::new(&exe);
+                    command.arg("--separate");
+                    if self.config.new_tab_inherit_cwd {
+                        if let Some(working_directory) = self.active_working_directory() {
+                            command.arg("--working-directory").arg(working_directory);
+                        }
                     }
-                },
+                    match command.spawn() {
+                        Ok(_child) => {}
+                        Err(err) => {
+                            log::error!("failed to execute {:?}: {}", exe, err);
+                        }
+                    }
+                }
                 Err(err) => {
                     log::error!("failed to get current executable path: {}", err);
                 }
             },
+            Message::WindowMaximized(size, maximized) => {
+                self.window_maximized = maximized;
+                if !maximized && !self.fullscreen {
+                    self.window_size = size;
+                }
+            }
+            Message::WindowResized(size) => {
+                return window::fetch_maximized(window::Id::MAIN, move |maximized| {
+                    Message::WindowMaximized(size, maximized)
+                });
+            }
+            Message::WordSeparators(word_separators) => {
+                if word_separators != self.config.word_separators {
+                    self.config.word_separators = word_separators;
+                    return self.save_config();
+                }
+            }
             Message::ZoomIn => {
                 self.zoom_adj = self.zoom_adj.saturating_add(1);
+                self.zoom_indicator_opt = Some(Instant::now());
                 return self.save_config();
             }
+            Message::ZoomIndicatorTick => {
+                if self
+                    .zoom_indicator_opt
+                    .is_some_and(|instant| instant.elapsed() >= ZOOM_INDICATOR_DURATION)
+                {
+                    self.zoom_indicator_opt = None;
+                }
+            }
             Message::ZoomOut => {
                 self.zoom_adj = self.zoom_adj.saturating_sub(1);
+                self.zoom_indicator_opt = Some(Instant::now());
                 return self.save_config();
             }
             Message::ZoomReset => {
                 self.zoom_adj = 0;
+                self.zoom_indicator_opt = Some(Instant::now());
                 return self.save_config();
             }
         }
@@ -2429,16 +5917,37 @@ impl Application for App {
     }
 
     fn header_start(&self) -> Vec<Element<Self::Message>> {
-        vec![menu_bar(&self.config, &self.key_binds)]
+        let pausable = self.pane_model.active().is_some_and(|tab_model| {
+            tab_model
+                .data::<Mutex<Terminal>>(tab_model.active())
+                .is_some_and(|terminal| terminal.lock().unwrap().pausable())
+        });
+        vec![menu_bar(&self.config, &self.key_binds, pausable)]
     }
 
     fn header_end(&self) -> Vec<Element<Self::Message>> {
         let cosmic_theme::Spacing { space_xxs, .. } = self.core().system_theme().cosmic().spacing;
-        vec![widget::button(icon_cache_get("list-add-symbolic", 16))
-            .on_press(Message::TabNew)
-            .padding(space_xxs)
-            .style(style::Button::Icon)
-            .into()]
+        let mut elements = Vec::with_capacity(2);
+        elements.push(
+            widget::button(icon_cache_get("list-add-symbolic", 16))
+                .on_press(Message::TabNew {
+                    inherit_cwd: self.config.new_tab_inherit_cwd,
+                })
+                .padding(space_xxs)
+                .style(style::Button::Icon)
+                .into(),
+        );
+        // Open a tab with a specific profile
+        if !self.profile_names.is_empty() {
+            let profile_ids = self.profile_ids.clone();
+            elements.push(
+                widget::dropdown(&self.profile_names, None, move |index| {
+                    Message::ProfileOpen(profile_ids[index])
+                })
+                .into(),
+            );
+        }
+        elements
     }
 
     fn view_window(&self, window_id: window::Id) -> Element<Message> {
@@ -2455,18 +5964,29 @@ impl Application for App {
         let pane_grid = PaneGrid::new(&self.pane_model.panes, |pane, tab_model, _is_maximized| {
             let mut tab_column = widget::column::with_capacity(1);
 
-            if tab_model.iter().count() > 1 {
-                tab_column = tab_column.push(
-                    widget::container(
-                        widget::tab_bar::horizontal(tab_model)
-                            .button_height(32)
-                            .button_spacing(space_xxs)
-                            .on_activate(Message::TabActivate)
-                            .on_close(|entity| Message::TabClose(Some(entity))),
-                    )
-                    .style(style::Container::Background)
-                    .width(Length::Fill),
-                );
+            let show_tab_bar = match self.config.tab_bar_visibility {
+                TabBarVisibility::Always => true,
+                TabBarVisibility::Multiple => tab_model.iter().count() > 1,
+                TabBarVisibility::Never => false,
+            };
+            let mut tab_bar_opt = show_tab_bar.then(|| {
+                widget::container(
+                    widget::tab_bar::horizontal(tab_model)
+                        .button_height(32)
+                        .button_spacing(space_xxs)
+                        .context_menu(Some(menu::tab_context_menu(&self.key_binds)))
+                        .on_context(Message::TabContext)
+                        .on_activate(Message::TabClicked)
+                        .on_close(|entity| Message::TabClose(Some(entity))),
+                )
+                .style(style::Container::Background)
+                .width(Length::Fill)
+            });
+
+            if self.config.tab_bar_position == TabBarPosition::Top {
+                if let Some(tab_bar) = tab_bar_opt.take() {
+                    tab_column = tab_column.push(tab_bar);
+                }
             }
 
             let entity = tab_model.active();
@@ -2477,36 +5997,443 @@ impl Application for App {
                 .cloned()
                 .unwrap_or_else(widget::Id::unique);
             if let Some(terminal) = tab_model.data::<Mutex<Terminal>>(entity) {
-                let mut terminal_box = terminal_box(terminal)
+                let mut terminal_box = terminal_box(terminal, &self.key_binds)
                     .id(terminal_id)
                     .on_context_menu(move |position_opt| {
                         Message::TabContextMenu(pane, position_opt)
                     })
+                    .on_copy_mode_yank(move || Message::CopyModeYank(entity))
+                    .on_find(|| Message::Find(true))
+                    .on_hint(move |action, text| Message::HintActivate(entity, action, text))
                     .on_middle_click(move || Message::MiddleClick(pane, Some(entity_middle_click)))
+                    .on_open_file_ref(Message::LaunchFileRef)
+                    .on_open_url(Message::LaunchUrl)
+                    .on_zoom_in(|| Message::ZoomIn)
+                    .on_zoom_out(|| Message::ZoomOut)
+                    .bell_flash(
+                        self.bell_flash_opt
+                            .is_some_and(|(flash_entity, _instant)| flash_entity == entity),
+                    )
                     .opacity(self.config.opacity_ratio())
-                    .padding(space_xxs);
+                    .padding(self.config.padding);
 
                 if self.config.focus_follow_mouse {
                     terminal_box = terminal_box.on_mouse_enter(move || Message::MouseEnter(pane));
                 }
 
-                let context_menu = {
+                if self.broadcast_opt.is_some() {
+                    terminal_box = terminal_box.on_input(move |key_input| {
+                        Message::BroadcastInput(pane, entity, key_input)
+                    });
+                }
+
+                let (
+                    context_menu,
+                    context_menu_url,
+                    paused,
+                    paused_overflow,
+                    read_only,
+                    recording_path_opt,
+                    output_log_path_opt,
+                    archived_lines,
+                ) = {
                     let terminal = terminal.lock().unwrap();
-                    terminal.context_menu
+                    (
+                        terminal.context_menu,
+                        terminal.context_menu_url.clone(),
+                        terminal.paused(),
+                        terminal.paused_overflow(),
+                        terminal.read_only(),
+                        terminal.recording_path(),
+                        terminal.output_log_path(),
+                        // Shown at the top of the scrollback, or while finding text
+                        if self.find || terminal.scrolled_to_top() {
+                            terminal.archived_lines()
+                        } else {
+                            0
+                        },
+                    )
                 };
 
                 let tab_element: Element<'_, Message> = match context_menu {
                     Some(point) => widget::popover(terminal_box.context_menu(point))
-                        .popup(menu::context_menu(&self.config, &self.key_binds, entity))
+                        .popup(menu::context_menu(
+                            &self.config,
+                            &self.key_binds,
+                            entity,
+                            context_menu_url,
+                            self.fullscreen,
+                        ))
                         .position(widget::popover::Position::Point(point))
                         .into(),
+                    None if self.zoom_indicator_opt.is_some() && pane == self.pane_model.focus => {
+                        let zoom_percent = (self.config.metrics(self.zoom_adj).font_size
+                            / f32::from(self.config.font_size.max(1))
+                            * 100.0)
+                            .round() as u32;
+                        widget::popover(terminal_box)
+                            .popup(
+                                widget::layer_container(
+                                    widget::text(fl!("zoom-level", percent = zoom_percent))
+                                        .size(16),
+                                )
+                                .layer(cosmic_theme::Layer::Primary)
+                                .padding(space_xxs),
+                            )
+                            .position(widget::popover::Position::Center)
+                            .into()
+                    }
                     None => terminal_box.into(),
                 };
                 tab_column = tab_column.push(tab_element);
+
+                if self.perf_overlay && pane == self.pane_model.focus {
+                    let perf_stats = &self.perf_stats;
+                    let seconds = PERF_INTERVAL.as_secs_f32();
+                    tab_column = tab_column.push(
+                        widget::layer_container(widget::text(fl!(
+                            "performance-overlay",
+                            fps = format!("{:.0}", perf_stats.frames as f32 / seconds),
+                            kib = format!("{:.1}", perf_stats.bytes_read as f32 / seconds / 1024.0),
+                            events = perf_stats.queue_depth,
+                            latency =
+                                format!("{:.1}", perf_stats.redraw_latency.as_secs_f32() * 1000.0)
+                        )))
+                        .layer(cosmic_theme::Layer::Primary)
+                        .padding(space_xxs)
+                        .width(Length::Fill),
+                    );
+                }
+
+                if paused {
+                    let paused_widget = widget::row::with_children(vec![
+                        icon_cache_get("media-playback-pause-symbolic", 16).into(),
+                        widget::text(if paused_overflow {
+                            fl!("output-paused-overflow")
+                        } else {
+                            fl!("output-paused")
+                        })
+                        .into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("resume-output"))
+                            .on_press(Message::TogglePause(Some(entity)))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(paused_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let Some(broadcast) = self.broadcast_opt {
+                    let broadcast_widget = widget::row::with_children(vec![
+                        icon_cache_get("network-transmit-symbolic", 16).into(),
+                        widget::text(match broadcast {
+                            Broadcast::Panes => fl!("broadcast-panes-active"),
+                            Broadcast::Tabs => fl!("broadcast-tabs-active"),
+                        })
+                        .into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("broadcast-stop"))
+                            .on_press(Message::BroadcastToggle(broadcast))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    // Stands out from the other bars, since typing goes to every terminal
+                    tab_column = tab_column.push(widget::container(broadcast_widget).style(
+                        style::Container::custom(|theme| {
+                            let cosmic = theme.cosmic();
+                            widget::container::Appearance {
+                                text_color: Some(cosmic.warning.on.into()),
+                                icon_color: Some(cosmic.warning.on.into()),
+                                background: Some(Background::Color(cosmic.warning.base.into())),
+                                ..Default::default()
+                            }
+                        }),
+                    ));
+                }
+
+                if let Some(recording_path) = recording_path_opt {
+                    let file_name = recording_path
+                        .file_name()
+                        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                    let recording_widget = widget::row::with_children(vec![
+                        icon_cache_get("media-record-symbolic", 16).into(),
+                        widget::text(fl!("recording-session", file = file_name)).into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("stop-recording"))
+                            .on_press(Message::RecordSession(Some(entity)))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(recording_widget)
+                            .layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if archived_lines > 0 {
+                    let archive_widget = widget::row::with_children(vec![
+                        icon_cache_get("document-open-recent-symbolic", 16).into(),
+                        widget::text(fl!("archived-lines", lines = archived_lines)).into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("show-archived-history"))
+                            .on_press(Message::ScrollbackArchiveShow(entity))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(archive_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let Some(output_log_path) = output_log_path_opt {
+                    let file_name = output_log_path
+                        .file_name()
+                        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                    let output_log_widget = widget::row::with_children(vec![
+                        icon_cache_get("document-save-symbolic", 16).into(),
+                        widget::text(fl!("logging-output", file = file_name)).into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("stop-logging"))
+                            .on_press(Message::LogOutput(Some(entity)))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(output_log_widget)
+                            .layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                // Recordings show their controls in place of the read-only bar
+                #[cfg(unix)]
+                let playback_widget_opt: Option<Element<Message>> =
+                    tab_model.data::<Playback>(entity).map(|Playback(control)| {
+                        let control = control.lock().unwrap();
+                        let playback_widget = widget::row::with_children(vec![
+                            widget::button(icon_cache_get(
+                                if control.paused {
+                                    "media-playback-start-symbolic"
+                                } else {
+                                    "media-playback-pause-symbolic"
+                                },
+                                16,
+                            ))
+                            .on_press(Message::PlaybackPause(entity))
+                            .style(style::Button::Icon)
+                            .into(),
+                            widget::slider(
+                                0.0..=control.duration as f32,
+                                control.position as f32,
+                                move |position| Message::PlaybackSeek(entity, position),
+                            )
+                            .step(0.1)
+                            .width(Length::Fill)
+                            .into(),
+                            widget::text(format!(
+                                "{} / {}",
+                                format_playback_time(control.position),
+                                format_playback_time(control.duration)
+                            ))
+                            .into(),
+                            widget::dropdown(
+                                &self.playback_speed_names,
+                                PLAYBACK_SPEEDS
+                                    .iter()
+                                    .position(|speed| *speed == control.speed),
+                                move |index| Message::PlaybackSpeed(entity, index),
+                            )
+                            .into(),
+                        ])
+                        .align_items(Alignment::Center)
+                        .padding(space_xxs)
+                        .spacing(space_xxs);
+                        widget::layer_container(playback_widget)
+                            .layer(cosmic_theme::Layer::Primary)
+                            .into()
+                    });
+                #[cfg(not(unix))]
+                let playback_widget_opt: Option<Element<Message>> = None;
+
+                if let Some(playback_widget) = playback_widget_opt {
+                    tab_column = tab_column.push(playback_widget);
+                } else if read_only {
+                    let read_only_widget = widget::row::with_children(vec![
+                        icon_cache_get("changes-prevent-symbolic", 16).into(),
+                        widget::text(fl!("read-only-tab")).into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("allow-input"))
+                            .on_press(Message::ToggleReadOnly(Some(entity)))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(read_only_widget)
+                            .layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let (Some(session), Some(SessionRestoreOffer)) = (
+                    &self.session_opt,
+                    tab_model.data::<SessionRestoreOffer>(entity),
+                ) {
+                    let session_widget = widget::row::with_children(vec![
+                        icon_cache_get("document-open-recent-symbolic", 16).into(),
+                        widget::text(fl!("session-restore-offer", count = session.tabs.len()))
+                            .into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("dismiss"))
+                            .on_press(Message::SessionDismiss(pane, entity))
+                            .into(),
+                        widget::button::suggested(fl!("restore"))
+                            .on_press(Message::SessionRestore(pane, entity))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(session_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let Some(TabRename(title)) = tab_model.data::<TabRename>(entity) {
+                    let rename_widget = widget::row::with_children(vec![
+                        widget::text_input(fl!("tab-title"), title)
+                            .id(self.tab_rename_id.clone())
+                            .on_input(move |title| Message::TabRenameInput(entity, title))
+                            .on_submit(Message::TabRenameSubmit(entity))
+                            .width(Length::Fill)
+                            .into(),
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::TabRenameCancel(entity))
+                            .into(),
+                        widget::button::suggested(fl!("rename"))
+                            .on_press(Message::TabRenameSubmit(entity))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(rename_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let Some(PasteConfirm(value)) = tab_model.data::<PasteConfirm>(entity) {
+                    let line_count = value.lines().count().max(1);
+                    let mut preview = value
+                        .lines()
+                        .take(PASTE_PREVIEW_LINES)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if line_count > PASTE_PREVIEW_LINES {
+                        preview.push_str("\n…");
+                    }
+                    let confirm_widget = widget::column::with_children(vec![
+                        widget::row::with_children(vec![
+                            icon_cache_get("dialog-warning-symbolic", 16).into(),
+                            widget::text(fl!("paste-confirm", lines = line_count)).into(),
+                            widget::horizontal_space(Length::Fill).into(),
+                            widget::button::standard(fl!("cancel"))
+                                .on_press(Message::PasteConfirmCancel(entity))
+                                .into(),
+                            widget::button::standard(fl!("paste-strip-newline"))
+                                .on_press(Message::PasteStripNewline(entity))
+                                .into(),
+                            widget::button::standard(fl!("paste-join-lines"))
+                                .on_press(Message::PasteJoinLines(entity))
+                                .into(),
+                            widget::button::suggested(fl!("paste"))
+                                .on_press(Message::PasteConfirm(entity))
+                                .into(),
+                        ])
+                        .align_items(Alignment::Center)
+                        .spacing(space_xxs)
+                        .into(),
+                        widget::text::monotext(preview).into(),
+                    ])
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(confirm_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+
+                if let Some(TabCloseConfirm(process_name)) =
+                    tab_model.data::<TabCloseConfirm>(entity)
+                {
+                    let confirm_widget = widget::row::with_children(vec![
+                        icon_cache_get("dialog-warning-symbolic", 16).into(),
+                        widget::text(if process_name.is_empty() {
+                            fl!("close-running-unknown")
+                        } else {
+                            fl!("close-running", process = process_name.as_str())
+                        })
+                        .into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::TabCloseCancel(entity))
+                            .into(),
+                        widget::button::destructive(fl!("close-anyway"))
+                            .on_press(Message::TabCloseForce(entity))
+                            .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
+
+                    tab_column = tab_column.push(
+                        widget::layer_container(confirm_widget).layer(cosmic_theme::Layer::Primary),
+                    );
+                }
+            } else if let Some(spawn_error) = tab_model.data::<TerminalSpawnError>(entity) {
+                let error_widget = widget::column::with_children(vec![
+                    icon_cache_get("dialog-error-symbolic", 32).into(),
+                    widget::text(&spawn_error.message).into(),
+                    widget::button::standard(fl!("retry"))
+                        .on_press(Message::TabRespawn(pane, entity))
+                        .into(),
+                ])
+                .align_items(Alignment::Center)
+                .spacing(space_xxs);
+
+                tab_column = tab_column.push(
+                    widget::container(error_widget)
+                        .center_x()
+                        .center_y()
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                );
             }
 
             //Only draw find in the currently focused pane
             if self.find && pane == self.pane_model.focus {
+                let is_alt_screen = tab_model
+                    .data::<Mutex<Terminal>>(entity)
+                    .is_some_and(|terminal| terminal.lock().unwrap().is_alt_screen());
                 let find_input = widget::text_input::text_input(
                     fl!("find-placeholder"),
                     &self.find_search_value,
@@ -2527,8 +6454,26 @@ impl Application for App {
                         .style(style::Button::Icon)
                         .into(),
                 );
-                let find_widget = widget::row::with_children(vec![
-                    find_input.into(),
+                let mut find_children: Vec<Element<'_, Message>> = vec![find_input.into()];
+                if is_alt_screen {
+                    // Full screen programs have no scrollback, only the visible screen is searched
+                    find_children.push(
+                        widget::tooltip(
+                            icon_cache_get("dialog-information-symbolic", 16),
+                            fl!("find-alt-screen"),
+                            widget::tooltip::Position::Top,
+                        )
+                        .into(),
+                    );
+                }
+                find_children.extend([
+                    widget::checkbox(
+                        fl!("find-match-case"),
+                        self.find_case_sensitive,
+                        Message::FindCaseSensitive,
+                    )
+                    .into(),
+                    widget::checkbox(fl!("find-regex"), self.find_regex, Message::FindRegex).into(),
                     widget::tooltip(
                         button(icon_cache_get("go-up-symbolic", 16))
                             .on_press(Message::FindPrevious)
@@ -2553,10 +6498,11 @@ impl Application for App {
                         .padding(space_xxs)
                         .style(style::Button::Icon)
                         .into(),
-                ])
-                .align_items(Alignment::Center)
-                .padding(space_xxs)
-                .spacing(space_xxs);
+                ]);
+                let find_widget = widget::row::with_children(find_children)
+                    .align_items(Alignment::Center)
+                    .padding(space_xxs)
+                    .spacing(space_xxs);
 
                 tab_column = tab_column
                     .push(widget::layer_container(find_widget).layer(cosmic_theme::Layer::Primary));
@@ -2564,6 +6510,11 @@ impl Application for App {
                 // TODO
             }
 
+            // Below the bars of the terminal when at the bottom
+            if let Some(tab_bar) = tab_bar_opt {
+                tab_column = tab_column.push(tab_bar);
+            }
+
             pane_grid::Content::new(tab_column)
         })
         .width(Length::Fill)
@@ -2582,6 +6533,15 @@ impl Application for App {
         struct ThemeSubscription;
         struct ThemeModeSubscription;
 
+        #[cfg(unix)]
+        let playback_subscription = if self.playback_playing() {
+            iced::time::every(Duration::from_millis(250)).map(|_| Message::PlaybackTick)
+        } else {
+            subscription::Subscription::none()
+        };
+        #[cfg(not(unix))]
+        let playback_subscription = subscription::Subscription::none();
+
         Subscription::batch([
             event::listen_with(|event, _status| match event {
                 Event::Keyboard(KeyEvent::KeyPressed { key, modifiers, .. }) => {
@@ -2591,8 +6551,16 @@ impl Application for App {
                     Some(Message::Modifiers(modifiers))
                 }
                 Event::Mouse(MouseEvent::ButtonReleased(MouseButton::Left)) => {
-                    Some(Message::CopyPrimary(None))
+                    Some(Message::MouseReleased)
+                }
+                Event::Window(_id, window::Event::Focused) => Some(Message::WindowFocused(true)),
+                Event::Window(_id, window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+                Event::Window(_id, window::Event::FileDropped(path)) => {
+                    Some(Message::FileDropped(path))
                 }
+                Event::Window(_id, window::Event::Resized { width, height }) => Some(
+                    Message::WindowResized(Size::new(width as f32, height as f32)),
+                ),
                 _ => None,
             }),
             subscription::channel(
@@ -2602,11 +6570,31 @@ impl Application for App {
                     let (event_tx, mut event_rx) = mpsc::channel(100);
                     output.send(Message::TermEventTx(event_tx)).await.unwrap();
 
-                    while let Some((pane, entity, event)) = event_rx.recv().await {
-                        output
-                            .send(Message::TermEvent(pane, entity, event))
-                            .await
-                            .unwrap();
+                    while let Some(first) = event_rx.recv().await {
+                        // A busy terminal sends far more wakeups than can be drawn. Take the
+                        // events queued while the last batch was sent, and only pass on one
+                        // wakeup per terminal, as the next frame draws all of its output anyway.
+                        let mut events = vec![first];
+                        while events.len() < 100 {
+                            match event_rx.try_recv() {
+                                Ok(event) => events.push(event),
+                                Err(_) => break,
+                            }
+                        }
+                        let mut woken = Vec::new();
+                        for (pane, entity, event) in events {
+                            if let TermEvent::MouseCursorDirty | TermEvent::Wakeup = event {
+                                let key = (pane, entity, matches!(event, TermEvent::Wakeup));
+                                if woken.contains(&key) {
+                                    continue;
+                                }
+                                woken.push(key);
+                            }
+                            output
+                                .send(Message::TermEvent(pane, entity, event))
+                                .await
+                                .unwrap();
+                        }
                     }
 
                     panic!("terminal event channel closed");
@@ -2625,7 +6613,9 @@ impl Application for App {
                         update.errors
                     );
                 }
-                Message::Config(update.config)
+                let mut config = update.config;
+                config.migrate();
+                Message::Config(config)
             }),
             cosmic_config::config_subscription::<_, cosmic_theme::Theme>(
                 TypeId::of::<ThemeSubscription>(),
@@ -2648,6 +6638,43 @@ impl Application for App {
                 Some(dialog) => dialog.subscription(),
                 None => subscription::Subscription::none(),
             },
+            match self.bell_flash_opt {
+                Some(_) => {
+                    iced::time::every(Duration::from_millis(50)).map(|_| Message::BellFlashTick)
+                }
+                None => subscription::Subscription::none(),
+            },
+            // Periodic timers only run while the window is focused, so an unfocused window
+            // only wakes for PTY output, focus, and short animations such as the bell flash
+            if self.blinking && self.window_focused {
+                iced::time::every(BLINK_INTERVAL).map(|_| Message::BlinkTick)
+            } else {
+                subscription::Subscription::none()
+            },
+            match self.zoom_indicator_opt {
+                Some(_) => iced::time::every(Duration::from_millis(250))
+                    .map(|_| Message::ZoomIndicatorTick),
+                None => subscription::Subscription::none(),
+            },
+            if cfg!(target_os = "linux")
+                && self.window_focused
+                && !self.config.tab_title_format.is_empty()
+            {
+                iced::time::every(PROCESS_TITLE_INTERVAL).map(|_| Message::ProcessTitleTick)
+            } else {
+                subscription::Subscription::none()
+            },
+            if !self.restarts_pending.is_empty() {
+                iced::time::every(Duration::from_millis(250)).map(|_| Message::RestartTick)
+            } else {
+                subscription::Subscription::none()
+            },
+            playback_subscription,
+            if self.perf_overlay {
+                iced::time::every(PERF_INTERVAL).map(|_| Message::PerfTick)
+            } else {
+                subscription::Subscription::none()
+            },
         ])
     }
 }
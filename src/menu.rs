@@ -24,6 +24,8 @@ pub fn context_menu<'a>(
     config: &Config,
     key_binds: &HashMap<KeyBind, Action>,
     entity: segmented_button::Entity,
+    url_opt: Option<String>,
+    fullscreen: bool,
 ) -> Element<'a, Message> {
     let find_key = |action: &Action| -> String {
         for (key_bind, key_action) in key_binds {
@@ -56,7 +58,34 @@ pub fn context_menu<'a>(
         .on_press(Message::TabContextAction(entity, action))
     };
 
+    let open_link: Element<'a, Message> = match url_opt {
+        Some(url) => column!(
+            menu_button!(
+                widget::text(fl!("open-link")),
+                horizontal_space(Length::Fill)
+            )
+            .on_press(Message::TabContextUrl(entity, url)),
+            horizontal_rule(1),
+        )
+        .into(),
+        None => column!().into(),
+    };
+
+    // Window controls for when there is no header to hold them
+    let window_controls: Element<'a, Message> = if config.show_headerbar && !fullscreen {
+        column!().into()
+    } else {
+        column!(
+            horizontal_rule(1),
+            menu_item(fl!("window-minimize"), Action::WindowMinimize),
+            menu_item(fl!("window-maximize"), Action::WindowMaximize),
+            menu_item(fl!("window-close"), Action::WindowClose),
+        )
+        .into()
+    };
+
     widget::container(column!(
+        open_link,
         menu_item(fl!("copy"), Action::Copy),
         menu_item(fl!("paste"), Action::Paste),
         menu_item(fl!("select-all"), Action::SelectAll),
@@ -64,14 +93,21 @@ pub fn context_menu<'a>(
         menu_item(fl!("split-horizontal"), Action::PaneSplitHorizontal),
         menu_item(fl!("split-vertical"), Action::PaneSplitVertical),
         menu_item(fl!("pane-toggle-maximize"), Action::PaneToggleMaximized),
+        menu_item(fl!("pane-close"), Action::PaneClose),
         horizontal_rule(1),
         menu_item(fl!("new-tab"), Action::TabNew),
+        menu_item(fl!("new-tab-here"), Action::TabNewHere),
+        menu_item(fl!("rename-tab"), Action::TabRename),
+        menu_item(fl!("open-working-directory"), Action::OpenWorkingDirectory),
+        menu_item(fl!("copy-working-directory"), Action::CopyWorkingDirectory),
         menu_item(fl!("menu-settings"), Action::Settings),
         menu_checkbox(
             fl!("show-headerbar"),
             config.show_headerbar,
             Action::ShowHeaderBar(!config.show_headerbar)
         ),
+        menu_checkbox(fl!("fullscreen"), fullscreen, Action::ToggleFullscreen),
+        window_controls,
     ))
     .padding(1)
     //TODO: move style to libcosmic
@@ -106,6 +142,7 @@ pub fn color_scheme_menu<'a>(
             fl!("rename"),
             Message::ColorSchemeRename(kind, id, name.to_string())
         ),
+        menu_item(fl!("edit"), Message::ColorSchemeEdit(kind, id)),
         menu_item(fl!("export"), Message::ColorSchemeExport(kind, id)),
         menu_item(fl!("delete"), Message::ColorSchemeDelete(kind, id)),
     ))
@@ -130,45 +167,102 @@ pub fn color_scheme_menu<'a>(
     .into()
 }
 
-pub fn menu_bar<'a>(config: &Config, key_binds: &HashMap<KeyBind, Action>) -> Element<'a, Message> {
+/// Menu shown when right clicking a tab, its actions apply to the active tab
+pub fn tab_context_menu(key_binds: &HashMap<KeyBind, Action>) -> Vec<MenuTree<Message>> {
+    menu_items(
+        key_binds,
+        vec![
+            MenuItem::Button(fl!("close-tab"), Action::TabClose),
+            MenuItem::Button(fl!("close-other-tabs"), Action::TabCloseOthers),
+            MenuItem::Button(fl!("close-tabs-right"), Action::TabCloseRight),
+            MenuItem::Divider,
+            MenuItem::Button(fl!("duplicate-tab"), Action::TabDuplicate),
+            MenuItem::Button(fl!("open-tab-in-window"), Action::TabOpenInWindow),
+            MenuItem::Button(fl!("rename-tab"), Action::TabRename),
+        ],
+    )
+}
+
+pub fn menu_bar<'a>(
+    config: &Config,
+    key_binds: &HashMap<KeyBind, Action>,
+    pausable: bool,
+) -> Element<'a, Message> {
     let mut profile_items = Vec::with_capacity(config.profiles.len());
+    let mut remote_profile_items = Vec::new();
     for (name, id) in config.profile_names() {
-        profile_items.push(MenuItem::Button(name, Action::ProfileOpen(id)));
+        let item = MenuItem::Button(name, Action::ProfileOpen(id));
+        if config
+            .profiles
+            .get(&id)
+            .is_some_and(|profile| profile.is_remote())
+        {
+            remote_profile_items.push(item);
+        } else {
+            profile_items.push(item);
+        }
     }
 
     //TODO: what to do if there are no profiles?
 
-    MenuBar::new(vec![
-        MenuTree::with_children(
-            menu_root(fl!("file")),
-            menu_items(
-                key_binds,
-                vec![
-                    MenuItem::Button(fl!("new-tab"), Action::TabNew),
-                    MenuItem::Button(fl!("new-window"), Action::WindowNew),
-                    MenuItem::Divider,
-                    MenuItem::Folder(fl!("profile"), profile_items),
-                    MenuItem::Button(fl!("menu-profiles"), Action::Profiles),
-                    MenuItem::Divider,
-                    MenuItem::Button(fl!("close-tab"), Action::TabClose),
-                    MenuItem::Divider,
-                    MenuItem::Button(fl!("quit"), Action::WindowClose),
-                ],
-            ),
+    let mut file_items = vec![
+        MenuItem::Button(fl!("new-tab"), Action::TabNew),
+        MenuItem::Button(fl!("new-tab-here"), Action::TabNewHere),
+        MenuItem::Button(fl!("duplicate-tab"), Action::TabDuplicate),
+        MenuItem::Button(fl!("new-window"), Action::WindowNew),
+        MenuItem::Divider,
+        MenuItem::Folder(fl!("profile"), profile_items),
+    ];
+    if !remote_profile_items.is_empty() {
+        file_items.push(MenuItem::Folder(
+            fl!("remote-profile"),
+            remote_profile_items,
+        ));
+    }
+    file_items.extend([
+        MenuItem::Button(fl!("menu-profiles"), Action::Profiles),
+        MenuItem::Divider,
+        MenuItem::Button(fl!("rename-tab"), Action::TabRename),
+        MenuItem::Button(fl!("close-tab"), Action::TabClose),
+        MenuItem::Divider,
+        MenuItem::Button(fl!("quit"), Action::WindowClose),
+    ]);
+
+    let mut edit_items = vec![
+        MenuItem::Button(fl!("copy"), Action::Copy),
+        MenuItem::Button(fl!("copy-last-output"), Action::CopyLastOutput),
+        MenuItem::Button(fl!("copy-working-directory"), Action::CopyWorkingDirectory),
+        MenuItem::Button(fl!("paste"), Action::Paste),
+        MenuItem::Button(fl!("select-all"), Action::SelectAll),
+        MenuItem::Button(
+            fl!("export-scrollback-text"),
+            Action::ExportScrollback { html: false },
         ),
-        MenuTree::with_children(
-            menu_root(fl!("edit")),
-            menu_items(
-                key_binds,
-                vec![
-                    MenuItem::Button(fl!("copy"), Action::Copy),
-                    MenuItem::Button(fl!("paste"), Action::Paste),
-                    MenuItem::Button(fl!("select-all"), Action::SelectAll),
-                    MenuItem::Divider,
-                    MenuItem::Button(fl!("find"), Action::Find),
-                ],
-            ),
+        MenuItem::Button(
+            fl!("export-scrollback-html"),
+            Action::ExportScrollback { html: true },
         ),
+        MenuItem::Button(fl!("record-session"), Action::RecordSession),
+        MenuItem::Button(fl!("log-output"), Action::LogOutput),
+        MenuItem::Divider,
+        MenuItem::Button(fl!("find"), Action::Find),
+        MenuItem::Button(fl!("copy-mode"), Action::ToggleCopyMode),
+        MenuItem::Button(fl!("hints"), Action::Hints),
+    ];
+    // Pausing relies on the Unix PTY reader, output would keep flowing in other tabs
+    if pausable {
+        edit_items.push(MenuItem::Button(fl!("pause-output"), Action::TogglePause));
+    }
+    edit_items.extend([
+        MenuItem::Button(fl!("read-only"), Action::ToggleReadOnly),
+        MenuItem::Divider,
+        MenuItem::Button(fl!("broadcast-tabs"), Action::BroadcastTabs),
+        MenuItem::Button(fl!("broadcast-panes"), Action::BroadcastPanes),
+    ]);
+
+    MenuBar::new(vec![
+        MenuTree::with_children(menu_root(fl!("file")), menu_items(key_binds, file_items)),
+        MenuTree::with_children(menu_root(fl!("edit")), menu_items(key_binds, edit_items)),
         MenuTree::with_children(
             menu_root(fl!("view")),
             menu_items(
@@ -177,13 +271,23 @@ pub fn menu_bar<'a>(config: &Config, key_binds: &HashMap<KeyBind, Action>) -> El
                     MenuItem::Button(fl!("zoom-in"), Action::ZoomIn),
                     MenuItem::Button(fl!("zoom-reset"), Action::ZoomReset),
                     MenuItem::Button(fl!("zoom-out"), Action::ZoomOut),
+                    MenuItem::Button(fl!("fullscreen"), Action::ToggleFullscreen),
                     MenuItem::Divider,
                     MenuItem::Button(fl!("next-tab"), Action::TabNext),
                     MenuItem::Button(fl!("previous-tab"), Action::TabPrev),
+                    MenuItem::Button(fl!("move-tab-left"), Action::TabMoveLeft),
+                    MenuItem::Button(fl!("move-tab-right"), Action::TabMoveRight),
+                    MenuItem::Divider,
+                    MenuItem::Button(fl!("previous-prompt"), Action::PromptPrevious),
+                    MenuItem::Button(fl!("next-prompt"), Action::PromptNext),
+                    MenuItem::Button(fl!("toggle-mark"), Action::MarkToggle),
+                    MenuItem::Button(fl!("previous-mark"), Action::MarkPrevious),
+                    MenuItem::Button(fl!("next-mark"), Action::MarkNext),
                     MenuItem::Divider,
                     MenuItem::Button(fl!("split-horizontal"), Action::PaneSplitHorizontal),
                     MenuItem::Button(fl!("split-vertical"), Action::PaneSplitVertical),
                     MenuItem::Button(fl!("pane-toggle-maximize"), Action::PaneToggleMaximized),
+                    MenuItem::Button(fl!("pane-close"), Action::PaneClose),
                     MenuItem::Divider,
                     MenuItem::Button(
                         fl!("menu-color-schemes"),
@@ -5,8 +5,6 @@ use cosmic::{
 
 use crate::terminal::Terminal;
 
-const SCROLL_SPEED: u32 = 3;
-
 #[derive(Default)]
 pub struct MouseReporter {
     last_movment_x: Option<u32>,
@@ -31,6 +29,7 @@ impl MouseReporter {
         event: Event,
         modifiers: &Modifiers,
         is_utf8: bool,
+        all_motion: bool,
         x: u32,
         y: u32,
     ) -> Option<Vec<u8>> {
@@ -59,7 +58,12 @@ impl MouseReporter {
                 //character, Cb).
                 //For example, motion into cell x,y with button 1 down is reported as
                 //CSI M @ CxCy ( @  = 32 + 0 (button 1) + 32 (motion indicator) ).
-                self.button.and_then(Self::button_number).map(|b| b + 32)
+                //With any event tracking (1003), motion without a button is reported as button 3
+                match self.button {
+                    Some(b) => Self::button_number(b).map(|b| b + 32),
+                    None if all_motion => Some(3 + 32),
+                    None => None,
+                }
             }
             _ => None,
         })?;
@@ -74,6 +78,10 @@ impl MouseReporter {
             button += 16;
         }
 
+        Self::normal_mouse_encode(button, is_utf8, x, y)
+    }
+
+    fn normal_mouse_encode(button: u8, is_utf8: bool, x: u32, y: u32) -> Option<Vec<u8>> {
         //Normal mode have a max of 223 (255 - 32), while utf8 extend this to 2015
         let max_point: usize = if is_utf8 { 2015 } else { 223 };
         if x as usize >= max_point || y as usize >= max_point {
@@ -120,6 +128,7 @@ impl MouseReporter {
         &mut self,
         event: Event,
         modifiers: &Modifiers,
+        all_motion: bool,
         x: u32,
         y: u32,
     ) -> Option<Vec<u8>> {
@@ -143,8 +152,11 @@ impl MouseReporter {
                     self.last_movment_x = Some(x);
                     self.last_movment_y = Some(y);
                 }
-                self.button
-                    .map(|button| (Self::button_number(button).map(|b| b + 32), "M"))
+                match self.button {
+                    Some(button) => Some((Self::button_number(button).map(|b| b + 32), "M")),
+                    None if all_motion => Some((Some(3 + 32), "M")),
+                    None => None,
+                }
             }
             _ => None,
         })?;
@@ -203,28 +215,49 @@ impl MouseReporter {
         }
         let term_code = format!("\x1b[<{};{};{}M", button_no, x + 1, y + 1);
         for _ in 0..amount as u32 {
-            terminal.input_no_scroll(term_code.as_bytes().to_vec());
+            terminal.input_event(term_code.as_bytes().to_vec());
         }
     }
 
-    //Emulate mouse wheel scroll with up/down arrows. Using mouse spec uses
-    //scroll-back and scroll-forw actions, which moves whole windows like page up/page down.
-    pub fn report_mouse_wheel_as_arrows(
+    #[allow(clippy::too_many_arguments)]
+    pub fn report_normal_mouse_wheel_scroll(
         terminal: &Terminal,
         term_cell_width: f32,
         term_cell_height: f32,
         delta: ScrollDelta,
+        modifiers: &Modifiers,
+        is_utf8: bool,
+        x: u32,
+        y: u32,
     ) {
-        let (_delta_x, delta_y) = match delta {
+        let (delta_x, delta_y) = match delta {
             ScrollDelta::Lines { x, y } => (x, y),
             ScrollDelta::Pixels { x, y } => (x / term_cell_width, y / term_cell_height),
         };
-        //Send delta_y * SCROLL_SPEED number of Up/Down arrows
-        for _ in 0..(delta_y.abs() as u32 * SCROLL_SPEED) {
-            if delta_y > 0.0 {
-                terminal.input_no_scroll(b"\x1B[A".as_slice())
-            } else if delta_y < 0.0 {
-                terminal.input_no_scroll(b"\x1B[B".as_slice())
+        let (mut button_no, amount) = if delta_y > 0.0 {
+            (64, delta_y.abs()) //Wheel UP
+        } else if delta_y < 0.0 {
+            (65, delta_y.abs()) //Wheel Down
+        } else if delta_x < 0.0 {
+            (66, delta_x.abs()) //Wheel Left
+        } else if delta_x > 0.0 {
+            (67, delta_x.abs()) //Wheel Right
+        } else {
+            return;
+        };
+
+        if modifiers.shift() {
+            button_no += 4;
+        }
+        if modifiers.alt() {
+            button_no += 8;
+        }
+        if modifiers.control() {
+            button_no += 16;
+        }
+        if let Some(code) = Self::normal_mouse_encode(button_no, is_utf8, x, y) {
+            for _ in 0..amount as u32 {
+                terminal.input_event(code.clone());
             }
         }
     }
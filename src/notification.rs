@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Desktop notifications, sent by one worker thread over a single D-Bus session connection so
+// the UI never blocks on the notification daemon. Each tab shows at most one notification per
+// interval, further ones in that time are dropped, and a tab's notification replaces its
+// previous one instead of stacking up.
+
+use cosmic::{
+    widget::{pane_grid, segmented_button},
+    Application,
+};
+use std::{
+    collections::HashMap,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{fl, App};
+
+// Shortest time between two notifications from the same tab
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tab a notification comes from
+pub type NotificationSource = (pane_grid::Pane, segmented_button::Entity);
+
+struct Request {
+    source: NotificationSource,
+    summary: String,
+    body: String,
+}
+
+#[derive(Default)]
+pub struct Notifier {
+    sender_opt: Option<mpsc::Sender<Request>>,
+    last_sent: HashMap<NotificationSource, Instant>,
+}
+
+impl Notifier {
+    /// Queue a notification, unless the tab already showed one within the interval
+    pub fn notify(&mut self, source: NotificationSource, summary: &str, body: &str) {
+        let now = Instant::now();
+        if self
+            .last_sent
+            .get(&source)
+            .is_some_and(|last_sent| now.duration_since(*last_sent) < INTERVAL)
+        {
+            return;
+        }
+        self.last_sent.insert(source, now);
+
+        let sender = self.sender_opt.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || worker(receiver));
+            sender
+        });
+        let request = Request {
+            source,
+            summary: summary.to_string(),
+            body: body.to_string(),
+        };
+        if sender.send(request).is_err() {
+            // The worker exited, start a new one for the next notification
+            self.sender_opt = None;
+        }
+    }
+
+    /// Forget a closed tab
+    pub fn remove(&mut self, source: NotificationSource) {
+        self.last_sent.remove(&source);
+    }
+}
+
+fn worker(receiver: mpsc::Receiver<Request>) {
+    let app_name = fl!("cosmic-terminal");
+    let mut connection_opt: Option<zbus::blocking::Connection> = None;
+    // Notification IDs returned by the daemon, used to replace the previous one of a tab
+    let mut ids = HashMap::<NotificationSource, u32>::new();
+    for request in receiver {
+        let connection = match &connection_opt {
+            Some(connection) => connection,
+            None => match zbus::blocking::Connection::session() {
+                Ok(connection) => connection_opt.insert(connection),
+                Err(err) => {
+                    log::warn!("failed to connect to the session bus: {}", err);
+                    continue;
+                }
+            },
+        };
+        let replaces_id = ids.get(&request.source).copied().unwrap_or(0);
+        // Notify(app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout)
+        let result = connection
+            .call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.Notifications"),
+                "Notify",
+                &(
+                    app_name.as_str(),
+                    replaces_id,
+                    App::APP_ID,
+                    request.summary.as_str(),
+                    request.body.as_str(),
+                    Vec::<&str>::new(),
+                    HashMap::<&str, zbus::zvariant::Value>::new(),
+                    -1i32,
+                ),
+            )
+            .and_then(|reply| reply.body::<u32>());
+        match result {
+            Ok(id) => {
+                ids.insert(request.source, id);
+            }
+            Err(err) => {
+                log::warn!("failed to show notification: {}", err);
+                // Connect again for the next notification, in case the bus went away
+                connection_opt = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Output logging, where everything a tab reads from the PTY is appended to a file as it arrives,
+// so the output of long-running jobs is kept after it scrolls out of the scrollback. Plain logs
+// have escape sequences and control characters other than whitespace removed.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::config::OutputLogFormat;
+
+/// Where the plain text filter is within an escape sequence
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StripState {
+    #[default]
+    Ground,
+    Escape,
+    // CSI sequences end with a byte from @ to ~
+    Csi,
+    // OSC, DCS, SOS, PM, and APC strings end with BEL or ST
+    String,
+    StringEscape,
+}
+
+/// Output of a terminal being appended to a log file
+#[derive(Debug)]
+pub struct OutputLog {
+    path: PathBuf,
+    file: File,
+    format: OutputLogFormat,
+    state: StripState,
+    buffer: Vec<u8>,
+}
+
+impl OutputLog {
+    pub fn open(path: &Path, format: OutputLogFormat) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            format,
+            state: StripState::default(),
+            buffer: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append output read from the PTY
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.format {
+            OutputLogFormat::Raw => self.file.write_all(bytes),
+            OutputLogFormat::Plain => {
+                self.buffer.clear();
+                for &byte in bytes {
+                    self.state = strip_byte(self.state, byte, &mut self.buffer);
+                }
+                self.file.write_all(&self.buffer)
+            }
+        }
+    }
+}
+
+/// Add a byte of output to plain text, returning the state for the next byte
+pub fn strip_byte(state: StripState, byte: u8, output: &mut Vec<u8>) -> StripState {
+    match state {
+        StripState::Ground => match byte {
+            0x1B => StripState::Escape,
+            b'\t' | b'\n' => {
+                output.push(byte);
+                StripState::Ground
+            }
+            // Carriage returns are dropped, lines end with the newline that follows them
+            0x00..=0x1F | 0x7F => StripState::Ground,
+            _ => {
+                output.push(byte);
+                StripState::Ground
+            }
+        },
+        StripState::Escape => match byte {
+            b'[' => StripState::Csi,
+            b']' | b'P' | b'X' | b'^' | b'_' => StripState::String,
+            // Intermediate bytes, as in character set selection
+            0x20..=0x2F => StripState::Escape,
+            _ => StripState::Ground,
+        },
+        StripState::Csi => match byte {
+            0x40..=0x7E => StripState::Ground,
+            _ => StripState::Csi,
+        },
+        StripState::String => match byte {
+            0x07 => StripState::Ground,
+            0x1B => StripState::StringEscape,
+            _ => StripState::String,
+        },
+        StripState::StringEscape => match byte {
+            b'\\' => StripState::Ground,
+            0x1B => StripState::StringEscape,
+            _ => StripState::String,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut state = StripState::default();
+        for &byte in input {
+            state = strip_byte(state, byte, &mut output);
+        }
+        output
+    }
+
+    #[test]
+    fn strip_csi_and_controls() {
+        assert_eq!(strip(b"a\x1B[31mb\x1B[0m\r\n\tc\x07"), b"ab\n\tc");
+    }
+
+    #[test]
+    fn strip_strings() {
+        assert_eq!(
+            strip(b"\x1B]0;title\x07x\x1B]0;title\x1B\\y\x1BPq#0~\x1B\\z"),
+            b"xyz"
+        );
+    }
+
+    #[test]
+    fn strip_charset_selection() {
+        assert_eq!(strip(b"\x1B(Ba\x1B)0b"), b"ab");
+    }
+}
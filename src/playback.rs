@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Playback of asciicast recordings, made by recording a session or by asciinema. A thread writes
+// the recorded output into a socket at the recorded times, and the terminal event loop reads the
+// socket in place of a PTY. Input is discarded.
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+};
+use polling::{Event, PollMode, Poller};
+use std::{
+    fs,
+    io::{self, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    recording::{AsciicastEvent, AsciicastHeader},
+    shell_integration::PTY_READ_WRITE_TOKEN,
+};
+
+// Longest time the playback thread waits before checking the controls again
+const PLAYBACK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Output recorded at a number of seconds from the start
+pub struct PlaybackEvent {
+    pub time: f64,
+    pub data: String,
+}
+
+/// Playback state shared between the playback thread and the controls of the tab
+#[derive(Debug)]
+pub struct PlaybackControl {
+    pub paused: bool,
+    pub speed: f64,
+    /// Seconds into the recording that have been played
+    pub position: f64,
+    pub duration: f64,
+    seek_opt: Option<f64>,
+}
+
+impl PlaybackControl {
+    pub fn seek(&mut self, position: f64) {
+        self.seek_opt = Some(position.clamp(0.0, self.duration));
+    }
+
+    pub fn finished(&self) -> bool {
+        self.position >= self.duration
+    }
+}
+
+/// Read the output events of an asciicast v2 file
+pub fn load(path: &Path) -> io::Result<Vec<PlaybackEvent>> {
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines();
+    let header = lines
+        .next()
+        .and_then(|header| serde_json::from_str::<AsciicastHeader>(header).ok());
+    if !header.is_some_and(|header| header.version == 2) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an asciicast v2 file",
+        ));
+    }
+
+    let mut events = Vec::new();
+    for line in lines.map(str::trim).filter(|line| !line.is_empty()) {
+        match serde_json::from_str::<AsciicastEvent>(line) {
+            Ok((time, code, data)) => {
+                // Input, resize, and marker events are not played
+                if code == "o" {
+                    events.push(PlaybackEvent { time, data });
+                }
+            }
+            Err(err) => log::warn!("ignoring invalid asciicast event {:?}: {}", line, err),
+        }
+    }
+    Ok(events)
+}
+
+// Write the events at their times, following the controls, until the tab is closed
+fn play(events: Vec<PlaybackEvent>, control: Arc<Mutex<PlaybackControl>>, mut writer: UnixStream) {
+    let mut index = 0;
+    let mut position = 0.0;
+    let mut instant = Instant::now();
+    loop {
+        // Nobody else holds the controls once the tab is closed
+        if Arc::strong_count(&control) == 1 {
+            return;
+        }
+
+        let mut data = String::new();
+        let wait = {
+            let mut control = control.lock().unwrap();
+            if let Some(seek) = control.seek_opt.take() {
+                // Going back starts over from a reset terminal
+                if seek < position {
+                    data.push_str("\x1Bc");
+                    index = 0;
+                }
+                position = seek;
+            } else if !control.paused {
+                position = (position + instant.elapsed().as_secs_f64() * control.speed)
+                    .min(control.duration);
+            }
+            instant = Instant::now();
+            control.position = position;
+
+            while let Some(event) = events.get(index).filter(|event| event.time <= position) {
+                data.push_str(&event.data);
+                index += 1;
+            }
+
+            match events.get(index) {
+                Some(event) if !control.paused => {
+                    Duration::from_secs_f64(((event.time - position) / control.speed).max(0.0))
+                        .min(PLAYBACK_INTERVAL)
+                }
+                _ => PLAYBACK_INTERVAL,
+            }
+        };
+
+        if !data.is_empty() && writer.write_all(data.as_bytes()).is_err() {
+            // The terminal is gone
+            return;
+        }
+        thread::sleep(wait);
+    }
+}
+
+/// Stands in for the PTY of a tab playing a recording
+pub struct PlaybackPty {
+    reader: UnixStream,
+    writer: io::Sink,
+}
+
+impl PlaybackPty {
+    pub fn new(events: Vec<PlaybackEvent>) -> io::Result<(Self, Arc<Mutex<PlaybackControl>>)> {
+        let (reader, writer) = UnixStream::pair()?;
+        // The event loop reads until there is nothing left
+        reader.set_nonblocking(true)?;
+        let control = Arc::new(Mutex::new(PlaybackControl {
+            paused: false,
+            speed: 1.0,
+            position: 0.0,
+            duration: events.last().map_or(0.0, |event| event.time),
+            seek_opt: None,
+        }));
+        let thread_control = control.clone();
+        thread::spawn(move || play(events, thread_control, writer));
+        Ok((
+            Self {
+                reader,
+                writer: io::sink(),
+            },
+            control,
+        ))
+    }
+}
+
+impl EventedReadWrite for PlaybackPty {
+    type Reader = UnixStream;
+    type Writer = io::Sink;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.add_with_mode(&self.reader, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.modify_with_mode(&self.reader, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        poll.delete(&self.reader)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        &mut self.writer
+    }
+}
+
+impl EventedPty for PlaybackPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        None
+    }
+}
+
+impl OnResize for PlaybackPty {
+    fn on_resize(&mut self, _window_size: WindowSize) {}
+}
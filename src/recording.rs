@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Session recording in the asciicast v2 format, a header line followed by one JSON array per
+// event, see https://docs.asciinema.org/manual/asciicast/v2/
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str,
+    time::{Instant, SystemTime},
+};
+
+/// Header line of an asciicast v2 file, other keys are ignored when reading
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AsciicastHeader {
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// An event line of an asciicast v2 file, with its time in seconds, code and data
+pub type AsciicastEvent = (f64, String, String);
+
+/// Output of a terminal being written to an asciicast file
+#[derive(Debug)]
+pub struct Recording {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    start: Instant,
+    // Bytes of a character split between reads
+    pending: Vec<u8>,
+}
+
+impl Recording {
+    pub fn new(path: &Path, columns: usize, rows: usize) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = AsciicastHeader {
+            version: 2,
+            width: columns,
+            height: rows,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()
+                .map(|duration| duration.as_secs()),
+            env: BTreeMap::from([("TERM".to_string(), "xterm-256color".to_string())]),
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer,
+            start: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record output read from the PTY
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(bytes);
+        let valid = match str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Keep an incomplete character at the end for the next read
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if valid == 0 {
+            return Ok(());
+        }
+        let data = String::from_utf8_lossy(&self.pending[..valid]).into_owned();
+        self.pending.drain(..valid);
+        self.event("o", &data)
+    }
+
+    /// Record a change of the terminal size
+    pub fn resize(&mut self, columns: usize, rows: usize) -> io::Result<()> {
+        self.event("r", &format!("{}x{}", columns, rows))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        // Times are kept to microseconds, like asciinema writes them
+        let elapsed = (self.start.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000_000.0;
+        let event: AsciicastEvent = (elapsed, code.to_string(), data.to_string());
+        serde_json::to_writer(&mut self.writer, &event)?;
+        writeln!(self.writer)?;
+        // Flushed as it goes, so a recording is usable even if the terminal is killed
+        self.writer.flush()
+    }
+}
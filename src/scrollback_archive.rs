@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Scrollback archive, where the oldest lines of a terminal are moved out of the grid into
+// compressed files before the scrollback limit would drop them. The files form a ring, the oldest
+// is removed once they take more than ARCHIVE_MAX_BYTES, and all of them are removed with the tab.
+//
+// Lines are compressed and written on a thread of their own, so output is not held up by it.
+// Archived lines are no longer part of the grid: scrolling and the find bar only reach the
+// scrollback, and the archive is shown in a tab of its own and included when exporting history.
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    collections::VecDeque,
+    env,
+    fs::{self, DirBuilder, File},
+    io::{self, Read, Write},
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+// Compressed size of the archive of one terminal before the oldest lines are dropped
+const ARCHIVE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+static NEXT_ARCHIVE_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+struct ArchiveChunk {
+    path: PathBuf,
+    lines: usize,
+    bytes: u64,
+}
+
+// Sent to the archive thread, which handles them in order
+enum ArchiveRequest {
+    Push(String, usize),
+    Text(mpsc::Sender<io::Result<String>>),
+}
+
+// Files of the archive, owned by the archive thread
+struct ArchiveFiles {
+    dir: PathBuf,
+    chunks: VecDeque<ArchiveChunk>,
+    next_chunk: u64,
+    bytes: u64,
+    lines: Arc<AtomicUsize>,
+}
+
+impl ArchiveFiles {
+    fn push(&mut self, text: &str, lines: usize) -> io::Result<()> {
+        let path = self.dir.join(format!("{}.gz", self.next_chunk));
+        self.next_chunk += 1;
+        let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+        encoder.write_all(text.as_bytes())?;
+        encoder.finish()?;
+        let bytes = fs::metadata(&path)?.len();
+        self.chunks.push_back(ArchiveChunk { path, lines, bytes });
+        self.bytes += bytes;
+        self.lines.fetch_add(lines, Ordering::Relaxed);
+
+        while self.bytes > ARCHIVE_MAX_BYTES && self.chunks.len() > 1 {
+            let Some(chunk) = self.chunks.pop_front() else {
+                break;
+            };
+            self.bytes -= chunk.bytes;
+            self.lines.fetch_sub(chunk.lines, Ordering::Relaxed);
+            if let Err(err) = fs::remove_file(&chunk.path) {
+                log::warn!(
+                    "failed to remove scrollback archive {:?}: {}",
+                    chunk.path,
+                    err
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&self) -> io::Result<String> {
+        let mut text = String::new();
+        for chunk in self.chunks.iter() {
+            GzDecoder::new(File::open(&chunk.path)?).read_to_string(&mut text)?;
+        }
+        Ok(text)
+    }
+}
+
+impl Drop for ArchiveFiles {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            log::warn!(
+                "failed to remove scrollback archive {:?}: {}",
+                self.dir,
+                err
+            );
+        }
+    }
+}
+
+/// Lines of scrollback kept compressed on disk, oldest first
+#[derive(Debug)]
+pub struct ScrollbackArchive {
+    lines: Arc<AtomicUsize>,
+    request_tx: mpsc::Sender<ArchiveRequest>,
+}
+
+impl ScrollbackArchive {
+    pub fn new() -> io::Result<Self> {
+        let dir = env::temp_dir().join(format!(
+            "cosmic-term-scrollback-{}-{}",
+            process::id(),
+            NEXT_ARCHIVE_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut builder = DirBuilder::new();
+        // Scrollback may hold secrets, so only the user can read it
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&dir)?;
+
+        let lines = Arc::new(AtomicUsize::new(0));
+        let mut files = ArchiveFiles {
+            dir,
+            chunks: VecDeque::new(),
+            next_chunk: 0,
+            bytes: 0,
+            lines: lines.clone(),
+        };
+        let (request_tx, request_rx) = mpsc::channel();
+        // Runs until the archive is dropped, then removes the files
+        thread::Builder::new()
+            .name("scrollback-archive".to_string())
+            .spawn(move || {
+                for request in request_rx {
+                    match request {
+                        ArchiveRequest::Push(text, lines) => {
+                            if let Err(err) = files.push(&text, lines) {
+                                log::error!("failed to archive scrollback: {}", err);
+                            }
+                        }
+                        ArchiveRequest::Text(text_tx) => {
+                            let _ = text_tx.send(files.text());
+                        }
+                    }
+                }
+            })?;
+        Ok(Self { lines, request_tx })
+    }
+
+    /// Number of lines written to the archive
+    pub fn lines(&self) -> usize {
+        self.lines.load(Ordering::Relaxed)
+    }
+
+    /// Add lines newer than those already archived, each ending with a newline. They are
+    /// written in the background.
+    pub fn push(&self, text: String, lines: usize) -> io::Result<()> {
+        self.request_tx
+            .send(ArchiveRequest::Push(text, lines))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "archive thread stopped"))
+    }
+
+    /// Text of all archived lines, oldest first, once the lines pushed before are written
+    pub fn text(&self) -> io::Result<String> {
+        let (text_tx, text_rx) = mpsc::channel();
+        self.request_tx
+            .send(ArchiveRequest::Text(text_tx))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "archive thread stopped"))?;
+        text_rx
+            .recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "archive thread stopped"))?
+    }
+}
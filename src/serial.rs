@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Serial console connections, where a serial device is read and written by the terminal event
+// loop in place of a PTY. There is no child process, so nothing is reported as exiting and
+// resizing has no effect.
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+};
+use polling::{Event, PollMode, Poller};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    mem,
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    sync::Arc,
+};
+
+use crate::{
+    config::SerialParity,
+    shell_integration::{ShellIntegrationBackend, PTY_READ_WRITE_TOKEN},
+};
+
+fn baud_rate_speed(baud_rate: u32) -> io::Result<libc::speed_t> {
+    Ok(match baud_rate {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        921600 => libc::B921600,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate {}", baud_rate),
+            ))
+        }
+    })
+}
+
+/// Reads from the serial device, ending the event loop when the device goes away
+pub struct SerialReader(File);
+
+impl Read for SerialReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            // The event loop waits for the child to exit after EIO, which never happens here
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "serial device disconnected",
+            )),
+            result => result,
+        }
+    }
+}
+
+pub struct SerialPty {
+    reader: SerialReader,
+    writer: File,
+}
+
+impl SerialPty {
+    /// Open a serial device as raw 8N1 without flow control, at the given speed and parity
+    pub fn open(device: &str, baud_rate: u32, parity: SerialParity) -> io::Result<Self> {
+        let speed = baud_rate_speed(baud_rate)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(device)?;
+
+        let fd = file.as_raw_fd();
+        // Safety: termios is plain data filled in by tcgetattr, and fd is open for the calls
+        unsafe {
+            let mut termios: libc::termios = mem::zeroed();
+            if libc::tcgetattr(fd, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::cfmakeraw(&mut termios);
+            termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+            termios.c_cflag &= !(libc::CSTOPB | libc::CRTSCTS | libc::PARENB | libc::PARODD);
+            match parity {
+                SerialParity::None => {}
+                SerialParity::Even => termios.c_cflag |= libc::PARENB,
+                SerialParity::Odd => termios.c_cflag |= libc::PARENB | libc::PARODD,
+            }
+            if parity != SerialParity::None {
+                termios.c_iflag |= libc::INPCK;
+            }
+            if libc::cfsetispeed(&mut termios, speed) != 0
+                || libc::cfsetospeed(&mut termios, speed) != 0
+                || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Self {
+            reader: SerialReader(file.try_clone()?),
+            writer: file,
+        })
+    }
+}
+
+impl EventedReadWrite for SerialPty {
+    type Reader = SerialReader;
+    type Writer = File;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.add_with_mode(&self.writer, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.modify_with_mode(&self.writer, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        poll.delete(&self.writer)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        &mut self.writer
+    }
+}
+
+impl EventedPty for SerialPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        None
+    }
+}
+
+impl OnResize for SerialPty {
+    fn on_resize(&mut self, _window_size: WindowSize) {}
+}
+
+impl ShellIntegrationBackend for SerialPty {
+    type Output = SerialReader;
+
+    fn try_clone_reader(&self) -> io::Result<SerialReader> {
+        Ok(SerialReader(self.writer.try_clone()?))
+    }
+}
@@ -0,0 +1,1149 @@
+// Shell integration through OSC 133 semantic prompt sequences, OSC 7 working directories, OSC 9
+// and OSC 777 notifications, sixel and kitty graphics, and blinking text
+//
+// alacritty_terminal drops OSC, DCS, and APC sequences and SGR attributes it does not know about,
+// so the PTY output is filtered before it reaches the parser. Prompt marks, blinking text, and the
+// cells covered by images are rewritten into OSC 8 hyperlinks with a private URI, which keeps them
+// attached to the grid cells as the content scrolls.
+
+#[cfg(unix)]
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite, Pty},
+};
+use cosmic::iced::advanced::image;
+#[cfg(unix)]
+use polling::{Event, PollMode, Poller};
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, Read, Write},
+    os::unix::{ffi::OsStringExt, net::UnixStream},
+    sync::Arc,
+};
+
+use crate::{
+    config::Trigger,
+    output_log::OutputLog,
+    recording::Recording,
+    trigger::{TriggerMatch, TriggerMatcher},
+};
+#[cfg(unix)]
+use crate::{kitty_graphics::KittyGraphics, sixel};
+
+/// Hyperlink URI used to mark the cells of a shell prompt
+pub const PROMPT_URI: &str = "cosmic-term:prompt";
+/// Hyperlink URI used to mark the cells of blinking text
+pub const BLINK_URI: &str = "cosmic-term:blink";
+/// Hyperlink URI prefix used to mark the cells covered by an image, followed by the image ID and
+/// the row of the image, like `cosmic-term:image/1/0`
+pub const IMAGE_URI_PREFIX: &str = "cosmic-term:image/";
+
+/// Parse the image ID and row from the hyperlink URI of a cell covered by an image
+pub fn parse_image_uri(uri: &str) -> Option<(u32, usize)> {
+    let (id, row) = uri.strip_prefix(IMAGE_URI_PREFIX)?.split_once('/')?;
+    Some((id.parse().ok()?, row.parse().ok()?))
+}
+
+/// A command that finished while shell integration was active
+#[derive(Clone, Debug)]
+pub struct FinishedCommand {
+    pub duration: Duration,
+    pub exit_code_opt: Option<i32>,
+    /// Number of prompts started after the command finished
+    pub prompts_after: usize,
+}
+
+/// A desktop notification requested by a program with OSC 9 or OSC 777
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub title_opt: Option<String>,
+    pub body: String,
+}
+
+/// An image sent by a program, drawn over the cells linked to its ID
+#[derive(Clone, Debug)]
+pub struct TerminalImage {
+    /// Image ID chosen by the program, for the kitty graphics protocol
+    pub kitty_id_opt: Option<u32>,
+    pub handle: image::Handle,
+    /// Size the image is drawn at, in pixels
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug)]
+pub enum ImageEvent {
+    Add(u32, TerminalImage),
+    /// Delete the kitty graphics images with an ID, or all of them
+    DeleteKitty(Option<u32>),
+}
+
+/// Where the cursor is left after placing an image
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageCursor {
+    /// On the line below the image, in the column it started at
+    Below,
+    /// After the last column of the image, on its last line
+    After,
+    /// Where the image started
+    Unchanged,
+}
+
+/// Command progress, notifications, and images sent by programs, shared between the PTY reader
+/// and the terminal
+#[derive(Debug, Default)]
+pub struct ShellState {
+    started_opt: Option<Instant>,
+    finished_opt: Option<FinishedCommand>,
+    notifications: Vec<Notification>,
+    image_events: Vec<ImageEvent>,
+    next_image_id: u32,
+    responses: Vec<Vec<u8>>,
+    blink_started: bool,
+    bytes_read: u64,
+    working_directory_opt: Option<PathBuf>,
+    recording_opt: Option<Recording>,
+    output_log_opt: Option<OutputLog>,
+    trigger_matcher_opt: Option<TriggerMatcher>,
+    trigger_matches: Vec<TriggerMatch>,
+    cell_width: f32,
+    cell_height: f32,
+    columns: usize,
+    screen_lines: usize,
+    // Output read while paused, passed on by the PTY reader when output is resumed
+    paused: bool,
+    paused_output: VecDeque<u8>,
+    paused_dropped: bool,
+    // Makes the PTY reader run when output is resumed, even if the program writes nothing
+    #[cfg(unix)]
+    resume_wake_opt: Option<UnixStream>,
+    #[cfg(unix)]
+    resume_wake_sent: bool,
+}
+
+impl ShellState {
+    /// Set the cell size in pixels and the number of columns and lines, used to place images
+    pub fn set_cell_size(
+        &mut self,
+        cell_width: f32,
+        cell_height: f32,
+        columns: usize,
+        screen_lines: usize,
+    ) {
+        self.cell_width = cell_width;
+        self.cell_height = cell_height;
+        self.columns = columns;
+        self.screen_lines = screen_lines;
+    }
+
+    #[cfg(unix)]
+    pub fn cell_size(&self) -> (f32, f32) {
+        (self.cell_width, self.cell_height)
+    }
+
+    /// Add an image and cover it with blank cells linked to it
+    #[cfg(unix)]
+    pub fn place_image(&mut self, image: TerminalImage, cursor: ImageCursor, output: &mut Vec<u8>) {
+        if self.cell_width <= 0.0 || self.cell_height <= 0.0 {
+            return;
+        }
+        let columns =
+            ((image.width as f32 / self.cell_width).ceil() as usize).clamp(1, self.columns.max(1));
+        let rows = ((image.height as f32 / self.cell_height).ceil() as usize)
+            .clamp(1, self.screen_lines.max(1));
+        let id = self.next_image_id;
+        self.next_image_id = id.wrapping_add(1);
+        self.image_events.push(ImageEvent::Add(id, image));
+
+        for row in 0..rows {
+            output.extend_from_slice(
+                format!("\x1B]8;;{}{}/{}\x1B\\", IMAGE_URI_PREFIX, id, row).as_bytes(),
+            );
+            output.extend(std::iter::repeat(b' ').take(columns));
+            output.extend_from_slice(b"\x1B]8;;\x1B\\");
+            if row + 1 < rows {
+                output.extend_from_slice(format!("\x1B[{}D\n", columns).as_bytes());
+            }
+        }
+        match cursor {
+            ImageCursor::Below => {
+                output.extend_from_slice(format!("\x1B[{}D\n", columns).as_bytes());
+            }
+            ImageCursor::After => {}
+            ImageCursor::Unchanged => {
+                output.extend_from_slice(format!("\x1B[{}D", columns).as_bytes());
+                if rows > 1 {
+                    output.extend_from_slice(format!("\x1B[{}A", rows - 1).as_bytes());
+                }
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn push_image_event(&mut self, event: ImageEvent) {
+        self.image_events.push(event);
+    }
+
+    pub fn take_image_events(&mut self) -> Vec<ImageEvent> {
+        std::mem::take(&mut self.image_events)
+    }
+
+    /// Queue a reply to be written back to the program
+    #[cfg(unix)]
+    pub fn push_response(&mut self, response: Vec<u8>) {
+        self.responses.push(response);
+    }
+
+    /// Pause or resume applying output to the terminal. Once resumed, the PTY reader passes on
+    /// the output read while paused before anything newer, through the same parser.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.paused_dropped = false;
+        #[cfg(unix)]
+        if !paused && !self.paused_output.is_empty() {
+            if let Some(resume_wake) = &mut self.resume_wake_opt {
+                // The socket only needs to be readable, a full buffer already is
+                let _ = resume_wake.write(&[0]);
+                self.resume_wake_sent = true;
+            }
+        }
+    }
+
+    /// Whether output read while paused was dropped to stay within PAUSED_OUTPUT_MAX_LEN
+    pub fn paused_dropped(&self) -> bool {
+        self.paused_dropped
+    }
+
+    // Keep output read while paused, dropping the oldest bytes past the limit
+    #[cfg(unix)]
+    fn pause_output(&mut self, bytes: &[u8]) {
+        self.paused_output.extend(bytes);
+        let excess = self
+            .paused_output
+            .len()
+            .saturating_sub(PAUSED_OUTPUT_MAX_LEN);
+        if excess > 0 {
+            // Start again after a newline, so the parser is not handed the rest of a sequence
+            let start = self
+                .paused_output
+                .iter()
+                .skip(excess)
+                .position(|&byte| byte == b'\n')
+                .map_or(excess, |pos| excess + pos + 1);
+            self.paused_output.drain(..start);
+            self.paused_dropped = true;
+        }
+    }
+
+    pub fn take_responses(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.responses)
+    }
+
+    pub fn take_finished(&mut self) -> Option<FinishedCommand> {
+        self.finished_opt.take()
+    }
+
+    pub fn take_notifications(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.notifications)
+    }
+
+    /// Whether a program started blinking text since the last call
+    pub fn take_blink_started(&mut self) -> bool {
+        std::mem::take(&mut self.blink_started)
+    }
+
+    /// Number of bytes read from the PTY since the last call
+    pub fn take_bytes_read(&mut self) -> u64 {
+        std::mem::take(&mut self.bytes_read)
+    }
+
+    pub fn start_recording(&mut self, recording: Recording) {
+        self.recording_opt = Some(recording);
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording_opt = None;
+    }
+
+    /// File the output is being recorded to
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.recording_opt
+            .as_ref()
+            .map(|recording| recording.path().to_path_buf())
+    }
+
+    /// Record output read from the PTY, stopping the recording if it fails
+    pub fn record_output(&mut self, bytes: &[u8]) {
+        if let Some(recording) = &mut self.recording_opt {
+            if let Err(err) = recording.output(bytes) {
+                log::error!("failed to record to {:?}: {}", recording.path(), err);
+                self.recording_opt = None;
+            }
+        }
+    }
+
+    /// Record a change of the terminal size, stopping the recording if it fails
+    pub fn record_resize(&mut self, columns: usize, rows: usize) {
+        if let Some(recording) = &mut self.recording_opt {
+            if let Err(err) = recording.resize(columns, rows) {
+                log::error!("failed to record to {:?}: {}", recording.path(), err);
+                self.recording_opt = None;
+            }
+        }
+    }
+
+    pub fn start_output_log(&mut self, output_log: OutputLog) {
+        self.output_log_opt = Some(output_log);
+    }
+
+    pub fn stop_output_log(&mut self) {
+        self.output_log_opt = None;
+    }
+
+    /// File the output is being logged to
+    pub fn output_log_path(&self) -> Option<PathBuf> {
+        self.output_log_opt
+            .as_ref()
+            .map(|output_log| output_log.path().to_path_buf())
+    }
+
+    /// Log output read from the PTY, stopping the log if it fails
+    pub fn log_output(&mut self, bytes: &[u8]) {
+        if let Some(output_log) = &mut self.output_log_opt {
+            if let Err(err) = output_log.output(bytes) {
+                log::error!("failed to log output to {:?}: {}", output_log.path(), err);
+                self.output_log_opt = None;
+            }
+        }
+    }
+
+    /// Match output against the triggers from now on
+    pub fn set_triggers(&mut self, triggers: &[Trigger]) {
+        let trigger_matcher = TriggerMatcher::new(triggers);
+        self.trigger_matcher_opt = (!trigger_matcher.is_empty()).then_some(trigger_matcher);
+    }
+
+    /// Match output read from the PTY against the triggers
+    pub fn match_triggers(&mut self, bytes: &[u8]) {
+        if let Some(trigger_matcher) = &mut self.trigger_matcher_opt {
+            trigger_matcher.output(bytes, &mut self.trigger_matches);
+        }
+    }
+
+    pub fn take_trigger_matches(&mut self) -> Vec<TriggerMatch> {
+        std::mem::take(&mut self.trigger_matches)
+    }
+
+    /// Working directory last reported by the shell with OSC 7, if it is on this host
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.working_directory_opt.clone()
+    }
+}
+
+#[cfg(unix)]
+const OSC_7_PREFIX: &[u8] = b"7;";
+#[cfg(unix)]
+const OSC_8_PREFIX: &[u8] = b"8;";
+#[cfg(unix)]
+const OSC_133_PREFIX: &[u8] = b"133;";
+#[cfg(unix)]
+const OSC_9_PREFIX: &[u8] = b"9;";
+#[cfg(unix)]
+const OSC_777_PREFIX: &[u8] = b"777;notify;";
+// OSC sequences handled here instead of by the terminal
+#[cfg(unix)]
+const OSC_PREFIXES: [&[u8]; 5] = [
+    OSC_7_PREFIX,
+    OSC_8_PREFIX,
+    OSC_133_PREFIX,
+    OSC_9_PREFIX,
+    OSC_777_PREFIX,
+];
+// Longest OSC sequence that will be buffered before giving up on it
+#[cfg(unix)]
+const OSC_MAX_LEN: usize = 4096;
+// Image data past this length is dropped
+#[cfg(unix)]
+const IMAGE_MAX_LEN: usize = 32 * 1024 * 1024;
+// Output read while paused past this length drops the oldest bytes
+#[cfg(unix)]
+const PAUSED_OUTPUT_MAX_LEN: usize = 16 * 1024 * 1024;
+
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FilterState {
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+    Dcs,
+    Sixel,
+    SixelEscape,
+    Apc,
+    ApcEscape,
+}
+
+/// Filters handled OSC, DCS, and APC sequences out of a byte stream, passing everything else
+/// through unchanged
+#[cfg(unix)]
+struct SequenceFilter {
+    state: FilterState,
+    csi: Vec<u8>,
+    osc: Vec<u8>,
+    dcs: Vec<u8>,
+    sixel: Vec<u8>,
+    apc: Vec<u8>,
+    kitty_graphics: KittyGraphics,
+    // Whether the cells being written are part of a prompt, or blink
+    prompt: bool,
+    blink: bool,
+    // Hyperlink opened by the program, as its OSC 8 parameters and URI, restored once a prompt
+    // or blinking text ends
+    hyperlink_opt: Option<Vec<u8>>,
+    shell_state: Arc<Mutex<ShellState>>,
+}
+
+#[cfg(unix)]
+impl SequenceFilter {
+    fn new(shell_state: Arc<Mutex<ShellState>>) -> Self {
+        Self {
+            state: FilterState::Ground,
+            csi: Vec::new(),
+            osc: Vec::new(),
+            dcs: Vec::new(),
+            sixel: Vec::new(),
+            apc: Vec::new(),
+            kitty_graphics: KittyGraphics::default(),
+            prompt: false,
+            blink: false,
+            hyperlink_opt: None,
+            shell_state,
+        }
+    }
+
+    // Whether no sequence is partly filtered
+    fn is_ground(&self) -> bool {
+        self.state == FilterState::Ground
+    }
+
+    fn push(&mut self, byte: u8, output: &mut Vec<u8>) {
+        match self.state {
+            FilterState::Ground => {
+                if byte == 0x1B {
+                    self.state = FilterState::Escape;
+                } else {
+                    output.push(byte);
+                }
+            }
+            FilterState::Escape => {
+                if byte == b'[' {
+                    self.csi.clear();
+                    self.state = FilterState::Csi;
+                } else if byte == b']' {
+                    self.osc.clear();
+                    self.state = FilterState::Osc;
+                } else if byte == b'P' {
+                    self.dcs.clear();
+                    self.state = FilterState::Dcs;
+                } else if byte == b'_' {
+                    self.apc.clear();
+                    self.state = FilterState::Apc;
+                } else {
+                    // A full reset also ends prompts and blinking
+                    if byte == b'c' {
+                        self.prompt = false;
+                        self.blink = false;
+                        self.hyperlink_opt = None;
+                    }
+                    output.push(0x1B);
+                    self.state = FilterState::Ground;
+                    self.push(byte, output);
+                }
+            }
+            FilterState::Csi => {
+                if (0x40..=0x7E).contains(&byte) {
+                    self.finish_csi(byte, output);
+                } else if byte == 0x1B || self.csi.len() > OSC_MAX_LEN {
+                    self.flush(output);
+                    self.push(byte, output);
+                } else {
+                    self.csi.push(byte);
+                }
+            }
+            FilterState::Osc => {
+                if byte == 0x07 {
+                    self.finish(output);
+                } else if byte == 0x1B && self.handled() {
+                    self.state = FilterState::OscEscape;
+                } else {
+                    self.osc.push(byte);
+                    let maybe_handled = OSC_PREFIXES.iter().any(|prefix| {
+                        let len = self.osc.len().min(prefix.len());
+                        self.osc[..len] == prefix[..len]
+                    });
+                    if !maybe_handled || self.osc.len() > OSC_MAX_LEN {
+                        // Not a handled sequence, let the terminal parse it
+                        self.flush(output);
+                    }
+                }
+            }
+            FilterState::OscEscape => {
+                if byte == b'\\' {
+                    self.finish(output);
+                } else {
+                    self.flush(output);
+                    self.push(0x1B, output);
+                    self.push(byte, output);
+                }
+            }
+            FilterState::Dcs => {
+                if byte.is_ascii_digit() || byte == b';' {
+                    self.dcs.push(byte);
+                    if self.dcs.len() > OSC_MAX_LEN {
+                        self.flush(output);
+                    }
+                } else if byte == b'q' {
+                    self.sixel.clear();
+                    self.state = FilterState::Sixel;
+                } else {
+                    // Not a sixel sequence, let the terminal parse it
+                    self.flush(output);
+                    self.push(byte, output);
+                }
+            }
+            FilterState::Sixel => {
+                if byte == 0x1B {
+                    self.state = FilterState::SixelEscape;
+                } else if self.sixel.len() < IMAGE_MAX_LEN {
+                    self.sixel.push(byte);
+                }
+            }
+            FilterState::SixelEscape => {
+                if byte == b'\\' {
+                    self.finish_sixel(output);
+                } else {
+                    // Any other escape sequence cancels the image
+                    self.sixel.clear();
+                    self.state = FilterState::Escape;
+                    self.push(byte, output);
+                }
+            }
+            FilterState::Apc => {
+                if byte == 0x1B {
+                    self.state = FilterState::ApcEscape;
+                } else if self.apc.len() < IMAGE_MAX_LEN {
+                    self.apc.push(byte);
+                }
+            }
+            FilterState::ApcEscape => {
+                if byte == b'\\' {
+                    self.state = FilterState::Ground;
+                    // Only kitty graphics commands are handled, other APC sequences are dropped
+                    if let Some(command) = self.apc.strip_prefix(b"G") {
+                        let mut shell_state = self.shell_state.lock().unwrap();
+                        self.kitty_graphics
+                            .command(command, &mut shell_state, output);
+                    }
+                    self.apc.clear();
+                } else {
+                    self.apc.clear();
+                    self.state = FilterState::Escape;
+                    self.push(byte, output);
+                }
+            }
+        }
+    }
+
+    fn finish_csi(&mut self, final_byte: u8, output: &mut Vec<u8>) {
+        self.state = FilterState::Ground;
+        output.extend_from_slice(b"\x1B[");
+        output.extend_from_slice(&self.csi);
+        output.push(final_byte);
+
+        // Track blinking from SGR sequences, skipping private ones
+        if final_byte != b'm' || self.csi.first().is_some_and(|b| (b'<'..=b'?').contains(b)) {
+            return;
+        }
+        let params: Vec<&[u8]> = self.csi.split(|&b| b == b';').collect();
+        let mut blink = self.blink;
+        let mut i = 0;
+        while let Some(param) = params.get(i) {
+            let code = param.split(|&b| b == b':').next().unwrap_or_default();
+            match std::str::from_utf8(code)
+                .ok()
+                .and_then(|code| code.parse().ok())
+            {
+                None | Some(0) | Some(25) => blink = false,
+                // Rapid blinking is shown at the same rate, like xterm does
+                Some(5) | Some(6) => blink = true,
+                // Skip the parameters of colors that are not given as subparameters
+                Some(38) | Some(48) | Some(58) if !param.contains(&b':') => {
+                    match params.get(i + 1).copied() {
+                        Some(b"5") => i += 2,
+                        Some(b"2") => i += 4,
+                        _ => {}
+                    }
+                }
+                Some(_) => {}
+            }
+            i += 1;
+        }
+        if blink != self.blink {
+            self.blink = blink;
+            if blink {
+                self.shell_state.lock().unwrap().blink_started = true;
+            }
+            self.write_link(output);
+        }
+    }
+
+    /// Write the hyperlink marking the cells that follow, prompts take precedence over blinking,
+    /// which takes precedence over a link opened by the program
+    fn write_link(&self, output: &mut Vec<u8>) {
+        output.extend_from_slice(b"\x1B]");
+        if self.prompt {
+            output.extend_from_slice(b"8;;");
+            output.extend_from_slice(PROMPT_URI.as_bytes());
+        } else if self.blink {
+            output.extend_from_slice(b"8;;");
+            output.extend_from_slice(BLINK_URI.as_bytes());
+        } else if let Some(hyperlink) = &self.hyperlink_opt {
+            output.extend_from_slice(hyperlink);
+        } else {
+            output.extend_from_slice(b"8;;");
+        }
+        output.extend_from_slice(b"\x1B\\");
+    }
+
+    fn finish_sixel(&mut self, output: &mut Vec<u8>) {
+        self.state = FilterState::Ground;
+        let image_opt = sixel::decode(&self.sixel);
+        self.sixel.clear();
+        let Some(image) = image_opt else {
+            return;
+        };
+
+        let image = TerminalImage {
+            kitty_id_opt: None,
+            handle: image::Handle::from_pixels(image.width, image.height, image.pixels),
+            width: image.width,
+            height: image.height,
+        };
+        self.shell_state
+            .lock()
+            .unwrap()
+            .place_image(image, ImageCursor::Below, output);
+    }
+
+    /// Whether the current OSC sequence is one handled here
+    fn handled(&self) -> bool {
+        OSC_PREFIXES
+            .iter()
+            .any(|prefix| self.osc.starts_with(prefix))
+    }
+
+    /// Write out any partially matched sequence as-is
+    fn flush(&mut self, output: &mut Vec<u8>) {
+        match self.state {
+            FilterState::Ground => {}
+            FilterState::Escape => output.push(0x1B),
+            FilterState::Csi => {
+                output.extend_from_slice(b"\x1B[");
+                output.append(&mut self.csi);
+            }
+            FilterState::Osc | FilterState::OscEscape => {
+                output.extend_from_slice(b"\x1B]");
+                output.append(&mut self.osc);
+            }
+            FilterState::Dcs => {
+                output.extend_from_slice(b"\x1BP");
+                output.append(&mut self.dcs);
+            }
+            // Incomplete images are dropped
+            FilterState::Sixel
+            | FilterState::SixelEscape
+            | FilterState::Apc
+            | FilterState::ApcEscape => {}
+        }
+        self.csi.clear();
+        self.osc.clear();
+        self.dcs.clear();
+        self.sixel.clear();
+        self.apc.clear();
+        self.state = FilterState::Ground;
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) {
+        if !self.handled() {
+            // Another OSC sequence terminated by BEL
+            output.extend_from_slice(b"\x1B]");
+            output.append(&mut self.osc);
+            output.push(0x07);
+        } else if let Some(params) = self.osc.strip_prefix(OSC_8_PREFIX) {
+            // An empty URI closes the link
+            let uri = params.splitn(2, |&b| b == b';').nth(1).unwrap_or_default();
+            self.hyperlink_opt = (!uri.is_empty()).then(|| self.osc.clone());
+            if !self.prompt && !self.blink {
+                self.write_link(output);
+            }
+        } else if let Some(url) = self.osc.strip_prefix(OSC_7_PREFIX) {
+            // Directories on other hosts, like over SSH, fall back to the local one
+            self.shell_state.lock().unwrap().working_directory_opt = parse_file_url(url);
+        } else if let Some(params) = self.osc.strip_prefix(OSC_9_PREFIX) {
+            // ConEmu uses OSC 9 with a numeric command for other purposes, like progress
+            let command = params.split(|&b| b == b';').next().unwrap_or_default();
+            if command.is_empty() || !command.iter().all(u8::is_ascii_digit) {
+                self.shell_state
+                    .lock()
+                    .unwrap()
+                    .notifications
+                    .push(Notification {
+                        title_opt: None,
+                        body: String::from_utf8_lossy(params).into_owned(),
+                    });
+            }
+        } else if let Some(params) = self.osc.strip_prefix(OSC_777_PREFIX) {
+            let mut parts = params.splitn(2, |&b| b == b';');
+            let title = String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+            let body = String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+            self.shell_state
+                .lock()
+                .unwrap()
+                .notifications
+                .push(Notification {
+                    title_opt: Some(title).filter(|title| !title.is_empty()),
+                    body,
+                });
+        } else {
+            let mut shell_state = self.shell_state.lock().unwrap();
+            match self.osc.get(OSC_133_PREFIX.len()) {
+                // Prompt start
+                Some(b'A') => {
+                    if let Some(finished) = shell_state.finished_opt.as_mut() {
+                        finished.prompts_after += 1;
+                    }
+                    self.prompt = true;
+                    self.write_link(output);
+                }
+                // Command start
+                Some(b'B') => {
+                    self.prompt = false;
+                    self.write_link(output);
+                }
+                // Command executed
+                Some(b'C') => {
+                    shell_state.started_opt = Some(Instant::now());
+                    self.prompt = false;
+                    self.write_link(output);
+                }
+                // Command finished, with an optional exit code
+                Some(b'D') => {
+                    if let Some(started) = shell_state.started_opt.take() {
+                        let exit_code_opt = self.osc[OSC_133_PREFIX.len() + 1..]
+                            .strip_prefix(b";")
+                            .and_then(|params| {
+                                let code = params.split(|&b| b == b';').next()?;
+                                std::str::from_utf8(code).ok()?.parse().ok()
+                            });
+                        shell_state.finished_opt = Some(FinishedCommand {
+                            duration: started.elapsed(),
+                            exit_code_opt,
+                            prompts_after: 0,
+                        });
+                    }
+                    self.prompt = false;
+                    self.write_link(output);
+                }
+                _ => {}
+            }
+        }
+        self.osc.clear();
+        self.state = FilterState::Ground;
+    }
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<Vec<u8>> {
+    let mut buf = [0u8; 256];
+    // Safety: the buffer is valid for the given length, which leaves a NUL at the end even if
+    // the name is truncated
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len() - 1) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&byte| byte == 0)?;
+    Some(buf[..len].to_vec())
+}
+
+/// Parse the path of a `file://host/path` URL sent with OSC 7, if the host is this one
+#[cfg(unix)]
+fn parse_file_url(url: &[u8]) -> Option<PathBuf> {
+    let rest = url.strip_prefix(b"file://")?;
+    let (host, path) = rest.split_at(rest.iter().position(|&b| b == b'/')?);
+    if !host.is_empty() && host != b"localhost" && Some(host) != hostname().as_deref() {
+        return None;
+    }
+
+    // Percent decode, keeping the bytes as they are as paths need not be UTF-8
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let escaped = (path[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(path[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(OsString::from_vec(decoded)))
+}
+
+/// Poller key of PTY reads and writes. alacritty_terminal keeps its own constant private, this
+/// matches the value in alacritty_terminal 0.23 and must be checked when upgrading it, as the
+/// event loop ignores readiness reported with any other key.
+#[cfg(unix)]
+pub const PTY_READ_WRITE_TOKEN: usize = 0;
+
+/// Backend of a terminal event loop whose output can be read a second time, to filter it
+#[cfg(unix)]
+pub trait ShellIntegrationBackend: EventedReadWrite + EventedPty + OnResize {
+    type Output: Read;
+
+    fn try_clone_reader(&self) -> io::Result<Self::Output>;
+}
+
+#[cfg(unix)]
+impl ShellIntegrationBackend for Pty {
+    type Output = File;
+
+    fn try_clone_reader(&self) -> io::Result<File> {
+        self.file().try_clone()
+    }
+}
+
+/// Reads from the PTY and filters shell integration sequences out of the output
+#[cfg(unix)]
+pub struct ShellIntegrationReader<R> {
+    inner: R,
+    // Registered with the PTY token, and written to when output is resumed
+    resume_wake: UnixStream,
+    filter: SequenceFilter,
+    input: Box<[u8]>,
+    output: Vec<u8>,
+    output_pos: usize,
+    // Whether the output so far ends between sequences and characters, where buffering the
+    // output of a paused terminal can start without splitting either
+    boundary: bool,
+    buffering: bool,
+}
+
+#[cfg(unix)]
+impl<R: Read> Read for ShellIntegrationReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.output_pos < self.output.len() {
+                let pending = &self.output[self.output_pos..];
+                let count = pending.len().min(buf.len());
+                buf[..count].copy_from_slice(&pending[..count]);
+                self.output_pos += count;
+                if self.output_pos == self.output.len() {
+                    self.output.clear();
+                    self.output_pos = 0;
+                }
+                return Ok(count);
+            }
+
+            // Output read while paused goes first once resumed. It is handed to the event loop
+            // like any other read, so its parser carries on where it stopped.
+            {
+                let mut shell_state = self.filter.shell_state.lock().unwrap();
+                if !shell_state.paused && !shell_state.paused_output.is_empty() {
+                    let count = shell_state.paused_output.len().min(buf.len());
+                    for (dst, byte) in buf.iter_mut().zip(shell_state.paused_output.drain(..count))
+                    {
+                        *dst = byte;
+                    }
+                    return Ok(count);
+                }
+                if shell_state.resume_wake_sent {
+                    shell_state.resume_wake_sent = false;
+                    let mut wake = [0; 64];
+                    while matches!(self.resume_wake.read(&mut wake), Ok(count) if count > 0) {}
+                }
+            }
+
+            let count = self.inner.read(&mut self.input)?;
+            {
+                let mut shell_state = self.filter.shell_state.lock().unwrap();
+                shell_state.bytes_read += count as u64;
+                // Recorded before filtering, so players see what the program wrote
+                shell_state.record_output(&self.input[..count]);
+                shell_state.log_output(&self.input[..count]);
+                shell_state.match_triggers(&self.input[..count]);
+            }
+            if count == 0 {
+                self.filter.flush(&mut self.output);
+                if self.output.is_empty() {
+                    return Ok(0);
+                }
+                continue;
+            }
+            let boundary = self.boundary;
+            for &byte in self.input[..count].iter() {
+                self.filter.push(byte, &mut self.output);
+            }
+            self.boundary = self.filter.is_ground()
+                && self.output.last().map_or(boundary, |byte| byte.is_ascii());
+
+            let mut shell_state = self.filter.shell_state.lock().unwrap();
+            if shell_state.paused && (self.buffering || boundary) {
+                shell_state.pause_output(&self.output);
+                self.output.clear();
+                self.buffering = true;
+            } else {
+                self.buffering = false;
+            }
+        }
+    }
+}
+
+/// PTY wrapper that adds shell integration to the output read by the event loop
+#[cfg(unix)]
+pub struct ShellIntegrationPty<P: ShellIntegrationBackend = Pty> {
+    pty: P,
+    reader: ShellIntegrationReader<P::Output>,
+}
+
+#[cfg(unix)]
+impl<P: ShellIntegrationBackend> ShellIntegrationPty<P> {
+    pub fn new(pty: P, shell_state: Arc<Mutex<ShellState>>) -> io::Result<Self> {
+        let inner = pty.try_clone_reader()?;
+        let (resume_wake_tx, resume_wake) = UnixStream::pair()?;
+        resume_wake_tx.set_nonblocking(true)?;
+        resume_wake.set_nonblocking(true)?;
+        shell_state.lock().unwrap().resume_wake_opt = Some(resume_wake_tx);
+        Ok(Self {
+            pty,
+            reader: ShellIntegrationReader {
+                inner,
+                resume_wake,
+                filter: SequenceFilter::new(shell_state),
+                input: vec![0; 0x10000].into_boxed_slice(),
+                output: Vec::new(),
+                output_pos: 0,
+                boundary: true,
+                buffering: false,
+            },
+        })
+    }
+}
+
+#[cfg(unix)]
+impl ShellIntegrationPty {
+    pub fn child(&self) -> &std::process::Child {
+        self.pty.child()
+    }
+}
+
+#[cfg(unix)]
+impl<P: ShellIntegrationBackend> EventedReadWrite for ShellIntegrationPty<P> {
+    type Reader = ShellIntegrationReader<P::Output>;
+    type Writer = P::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)?;
+        poll.add_with_mode(
+            &self.reader.resume_wake,
+            Event::readable(PTY_READ_WRITE_TOKEN),
+            mode,
+        )
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)?;
+        poll.modify_with_mode(
+            &self.reader.resume_wake,
+            Event::readable(PTY_READ_WRITE_TOKEN),
+            mode,
+        )
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)?;
+        poll.delete(&self.reader.resume_wake)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+#[cfg(unix)]
+impl<P: ShellIntegrationBackend> EventedPty for ShellIntegrationPty<P> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+#[cfg(unix)]
+impl<P: ShellIntegrationBackend> OnResize for ShellIntegrationPty<P> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    // Run the chunks through one filter as if they were separate reads
+    fn filter(chunks: &[&[u8]]) -> (Vec<u8>, Arc<Mutex<ShellState>>) {
+        let shell_state = Arc::new(Mutex::new(ShellState::default()));
+        let mut filter = SequenceFilter::new(shell_state.clone());
+        let mut output = Vec::new();
+        for chunk in chunks {
+            for &byte in chunk.iter() {
+                filter.push(byte, &mut output);
+            }
+        }
+        (output, shell_state)
+    }
+
+    fn link(uri: &str) -> Vec<u8> {
+        format!("\x1B]8;;{}\x1B\\", uri).into_bytes()
+    }
+
+    #[test]
+    fn sequence_split_across_reads() {
+        let (output, shell_state) = filter(&[b"a\x1B", b"]7;file://localhost", b"/tmp\x07b"]);
+        assert_eq!(output, b"ab");
+        assert_eq!(
+            shell_state.lock().unwrap().working_directory(),
+            Some(PathBuf::from("/tmp"))
+        );
+    }
+
+    #[test]
+    fn osc_terminated_by_bel_or_st() {
+        let (bel, _) = filter(&[b"\x1B]133;A\x07$ "]);
+        let (st, _) = filter(&[b"\x1B]133;A\x1B\\$ "]);
+        let mut expected = link(PROMPT_URI);
+        expected.extend_from_slice(b"$ ");
+        assert_eq!(bel, expected);
+        assert_eq!(st, expected);
+    }
+
+    #[test]
+    fn unhandled_osc_passes_through() {
+        let input: &[u8] = b"\x1B]0;title\x07\x1B]2;title\x1B\\";
+        assert_eq!(filter(&[input]).0, input);
+    }
+
+    #[test]
+    fn sgr_skips_color_parameters() {
+        // The 5 components of a direct color are not the blink attribute
+        let input: &[u8] = b"\x1B[38;2;5;5;5m\x1B[48;5;5m\x1B[38:2::5:5:5m";
+        let (output, shell_state) = filter(&[input]);
+        assert_eq!(output, input);
+        assert!(!shell_state.lock().unwrap().take_blink_started());
+
+        let (output, shell_state) = filter(&[b"\x1B[1;5m"]);
+        let mut expected = b"\x1B[1;5m".to_vec();
+        expected.extend(link(BLINK_URI));
+        assert_eq!(output, expected);
+        assert!(shell_state.lock().unwrap().take_blink_started());
+    }
+
+    #[test]
+    fn program_hyperlink_restored() {
+        let (output, _) = filter(&[b"\x1B]8;id=1;https://a\x07x\x1B[5my\x1B[25mz\x1B]8;;\x07"]);
+        let mut expected = b"\x1B]8;id=1;https://a\x1B\\x\x1B[5m".to_vec();
+        expected.extend(link(BLINK_URI));
+        expected.extend_from_slice(b"y\x1B[25m\x1B]8;id=1;https://a\x1B\\z");
+        expected.extend(link(""));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn overlong_osc_is_flushed() {
+        let mut input = b"\x1B]133;".to_vec();
+        input.extend(std::iter::repeat(b'x').take(OSC_MAX_LEN + 16));
+        input.push(0x07);
+        assert_eq!(filter(&[input.as_slice()]).0, input);
+    }
+
+    #[test]
+    fn file_url() {
+        assert_eq!(
+            parse_file_url(b"file://localhost/tmp/a%20b"),
+            Some(PathBuf::from("/tmp/a b"))
+        );
+        assert_eq!(parse_file_url(b"file:///tmp"), Some(PathBuf::from("/tmp")));
+        assert_eq!(parse_file_url(b"file://invalid.host.example/tmp"), None);
+        assert_eq!(parse_file_url(b"https://localhost/tmp"), None);
+
+        let mut url = b"file://".to_vec();
+        url.extend(hostname().unwrap());
+        url.extend(b"/tmp");
+        assert_eq!(parse_file_url(&url), Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn paused_output_restarts_after_newline() {
+        let mut shell_state = ShellState::default();
+        shell_state.set_paused(true);
+        let mut bytes = vec![b'a'; PAUSED_OUTPUT_MAX_LEN];
+        bytes.extend_from_slice(b"\x1B[1mb\nc");
+        shell_state.pause_output(&bytes);
+        assert!(shell_state.paused_dropped());
+        assert_eq!(shell_state.paused_output, b"c");
+    }
+
+    #[test]
+    fn image_rows_clamped_to_screen() {
+        let shell_state = Arc::new(Mutex::new(ShellState::default()));
+        shell_state
+            .lock()
+            .unwrap()
+            .set_cell_size(10.0, 20.0, 80, 24);
+        let mut filter = SequenceFilter::new(shell_state.clone());
+        let mut output = Vec::new();
+        // A single RGBA pixel placed over 4294967295 rows
+        for &byte in b"\x1B_Ga=T,f=32,s=1,v=1,r=4294967295;AAAAAA==\x1B\\".iter() {
+            filter.push(byte, &mut output);
+        }
+        let lines = output.iter().filter(|&&b| b == b'\n').count();
+        assert_eq!(lines, 23);
+    }
+}
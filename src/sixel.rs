@@ -0,0 +1,269 @@
+// Decoder for DEC sixel graphics
+//
+// https://vt100.net/docs/vt3xx-gp/chapter14.html
+
+// Images larger than this in either dimension are clipped
+const MAX_DIMENSION: usize = 4096;
+
+// VT340 default color registers, in percent
+const DEFAULT_PALETTE: [(u32, u32, u32); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// A decoded sixel image, with RGBA pixels. Pixels that were never drawn are transparent.
+#[derive(Clone, Debug)]
+pub struct SixelImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    stride: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            stride: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    fn reserve(&mut self, width: usize, height: usize) {
+        let width = width.min(MAX_DIMENSION);
+        let height = height.min(MAX_DIMENSION);
+        if width > self.stride {
+            let stride = width.max(self.stride * 2).min(MAX_DIMENSION);
+            let rows = self.pixels.len() / self.stride.max(1);
+            let mut pixels = vec![[0; 4]; stride * rows];
+            for y in 0..rows {
+                pixels[y * stride..y * stride + self.stride]
+                    .copy_from_slice(&self.pixels[y * self.stride..(y + 1) * self.stride]);
+            }
+            self.pixels = pixels;
+            self.stride = stride;
+        }
+        let rows = self.pixels.len() / self.stride.max(1);
+        if height > rows {
+            let rows = height.max(rows * 2).min(MAX_DIMENSION);
+            self.pixels.resize(self.stride * rows, [0; 4]);
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x >= MAX_DIMENSION || y >= MAX_DIMENSION {
+            return;
+        }
+        self.reserve(x + 1, y + 1);
+        self.pixels[y * self.stride + x] = color;
+        self.width = self.width.max(x + 1);
+        self.height = self.height.max(y + 1);
+    }
+}
+
+fn percent_to_u8(value: u32) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+// Sixel HLS has blue at 0 degrees and red at 120 degrees
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 4] {
+    let h = ((hue + 240) % 360) as f32 / 360.0;
+    let l = lightness.min(100) as f32 / 100.0;
+    let s = saturation.min(100) as f32 / 100.0;
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let value = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (value * 255.0).round() as u8
+    };
+    [
+        channel(h + 1.0 / 3.0),
+        channel(h),
+        channel(h - 1.0 / 3.0),
+        255,
+    ]
+}
+
+// Parse numeric parameters separated by semicolons, returning them and the remaining data
+fn parse_params(data: &[u8]) -> (Vec<u32>, &[u8]) {
+    let mut params = vec![0u32];
+    let mut i = 0;
+    while let Some(&b) = data.get(i) {
+        match b {
+            b'0'..=b'9' => {
+                let last = params.last_mut().unwrap();
+                *last = last.saturating_mul(10).saturating_add(u32::from(b - b'0'));
+            }
+            b';' => params.push(0),
+            _ => break,
+        }
+        i += 1;
+    }
+    (params, &data[i..])
+}
+
+/// Decode the data of a sixel sequence, which follows the `q` of the DCS introducer
+pub fn decode(mut data: &[u8]) -> Option<SixelImage> {
+    let mut palette = [[0, 0, 0, 255]; 256];
+    for (i, (r, g, b)) in DEFAULT_PALETTE.iter().enumerate() {
+        palette[i] = [percent_to_u8(*r), percent_to_u8(*g), percent_to_u8(*b), 255];
+    }
+    let mut color = palette[0];
+    let mut canvas = Canvas::new();
+    let mut x = 0;
+    let mut y = 0;
+
+    while let Some((&b, rest)) = data.split_first() {
+        data = rest;
+        let mut repeat = 1;
+        let sixel = match b {
+            // Raster attributes: aspect numerator and denominator, then width and height
+            b'"' => {
+                let (params, rest) = parse_params(data);
+                data = rest;
+                if let (Some(&width), Some(&height)) = (params.get(2), params.get(3)) {
+                    canvas.reserve(width as usize, height as usize);
+                    canvas.width = canvas.width.max((width as usize).min(MAX_DIMENSION));
+                    canvas.height = canvas.height.max((height as usize).min(MAX_DIMENSION));
+                }
+                continue;
+            }
+            // Color introducer, which selects a register and optionally defines it
+            b'#' => {
+                let (params, rest) = parse_params(data);
+                data = rest;
+                let index = params[0] as usize % palette.len();
+                if params.len() >= 5 {
+                    palette[index] = match params[1] {
+                        1 => hls_to_rgb(params[2], params[3], params[4]),
+                        _ => [
+                            percent_to_u8(params[2]),
+                            percent_to_u8(params[3]),
+                            percent_to_u8(params[4]),
+                            255,
+                        ],
+                    };
+                }
+                color = palette[index];
+                continue;
+            }
+            // Graphics repeat introducer
+            b'!' => {
+                let (params, rest) = parse_params(data);
+                let Some((&sixel, rest)) = rest.split_first() else {
+                    break;
+                };
+                data = rest;
+                repeat = (params[0] as usize).clamp(1, MAX_DIMENSION);
+                sixel
+            }
+            // Graphics carriage return
+            b'$' => {
+                x = 0;
+                continue;
+            }
+            // Graphics new line
+            b'-' => {
+                x = 0;
+                y += 6;
+                continue;
+            }
+            _ => b,
+        };
+
+        if !(0x3F..=0x7E).contains(&sixel) {
+            continue;
+        }
+        let bits = sixel - 0x3F;
+        for _ in 0..repeat {
+            if bits != 0 {
+                for i in 0..6 {
+                    if bits & (1 << i) != 0 {
+                        canvas.set(x, y + i, color);
+                    }
+                }
+            }
+            x += 1;
+        }
+    }
+
+    if canvas.width == 0 || canvas.height == 0 {
+        return None;
+    }
+    canvas.reserve(canvas.width, canvas.height);
+    let mut pixels = Vec::with_capacity(canvas.width * canvas.height * 4);
+    for row in 0..canvas.height {
+        let start = row * canvas.stride;
+        for pixel in canvas.pixels[start..start + canvas.width].iter() {
+            pixels.extend_from_slice(pixel);
+        }
+    }
+    Some(SixelImage {
+        width: canvas.width as u32,
+        height: canvas.height as u32,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_color_and_repeat() {
+        let image = decode(b"#1;2;100;0;0!3~-#2;2;0;0;100@").unwrap();
+        assert_eq!((image.width, image.height), (3, 7));
+        assert_eq!(image.pixels[..4], [255, 0, 0, 255]);
+        // Second band only sets its top pixel in the first column
+        let pixel = |x: usize, y: usize| &image.pixels[(y * 3 + x) * 4..(y * 3 + x + 1) * 4];
+        assert_eq!(pixel(0, 6), [0, 0, 255, 255]);
+        assert_eq!(pixel(1, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_raster_attributes() {
+        let image = decode(b"\"1;1;4;12#0?").unwrap();
+        assert_eq!((image.width, image.height), (4, 12));
+        assert!(image.pixels.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn decode_empty() {
+        assert!(decode(b"").is_none());
+        assert!(decode(b"#0").is_none());
+    }
+}
@@ -6,42 +6,66 @@ use alacritty_terminal::{
     selection::{Selection, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Flags,
+        cell::{Cell, Flags, Hyperlink},
         color::{self, Colors},
-        search::RegexSearch,
+        search::{Match, RegexIter, RegexSearch},
         viewport_to_point, Config, TermDamage, TermMode,
     },
-    tty::{self, Options},
-    vte::ansi::{Color, NamedColor, Rgb},
+    tty::{self, EventedPty, Options},
+    vi_mode::ViMotion,
+    vte::ansi::{Color, CursorShape, CursorStyle, NamedColor, Processor, Rgb},
     Term,
 };
 use cosmic::{
     iced::advanced::graphics::text::font_system,
+    iced::advanced::image,
     iced::mouse::ScrollDelta,
     widget::{pane_grid, segmented_button},
 };
 use cosmic_text::{
-    Attrs, AttrsList, Buffer, BufferLine, CacheKeyFlags, Family, Metrics, Shaping, Weight, Wrap,
+    Attrs, AttrsList, AttrsOwned, Buffer, BufferLine, CacheKeyFlags, Family, Metrics, Shaping,
+    Weight, Wrap,
 };
+use hex_color::HexColor;
 use indexmap::IndexSet;
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    future::Future,
     io, mem,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU32, Ordering},
-        Arc, Weak,
+        Arc, Mutex, Weak,
     },
-    time::Instant,
+    thread,
+    time::{Duration, Instant},
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use unicode_width::UnicodeWidthStr;
 
 pub use alacritty_terminal::grid::Scroll as TerminalScroll;
 
 use crate::{
-    config::{ColorSchemeKind, Config as AppConfig, ProfileId},
+    box_drawing,
+    config::{
+        BackgroundImageScale, ColorSchemeKind, Config as AppConfig, Highlight, Hint, HintAction,
+        OutputLogFormat, PasteFilter, ProfileId, Trigger, TriggerAction,
+    },
     mouse_reporter::MouseReporter,
+    output_log::OutputLog,
+    recording::Recording,
+    scrollback_archive::ScrollbackArchive,
+    shell_integration::{
+        parse_image_uri, FinishedCommand, ImageEvent, Notification, ShellState, TerminalImage,
+        BLINK_URI, IMAGE_URI_PREFIX, PROMPT_URI,
+    },
+    terminal_box::KeyInput,
+    trigger::TriggerMatch,
 };
+#[cfg(unix)]
+use crate::{playback::PlaybackPty, serial::SerialPty, shell_integration::ShellIntegrationPty};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Size {
@@ -90,11 +114,91 @@ impl EventListener for EventProxy {
     }
 }
 
-fn as_bright(mut color: Color) -> Color {
-    if let Color::Named(named) = color {
-        color = Color::Named(named.to_bright());
+// Only standard (0-7) colors have bright counterparts, explicit 256 and truecolor
+// foregrounds are never remapped
+fn as_bright(color: Color) -> Color {
+    match color {
+        Color::Named(named) if (named as usize) < 8 => Color::Named(named.to_bright()),
+        _ => color,
     }
-    color
+}
+
+// Same URL schemes and terminating characters as alacritty
+pub const URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
+
+// Compiler style file references like src/main.rs:12:5, where the path ends at whitespace or a
+// word separator
+fn file_ref_regex(word_separators: &str) -> RegexSearch {
+    let mut path_chars = String::from("\\s:");
+    for c in word_separators.chars().filter(|c| *c != ':') {
+        if c.is_alphanumeric() {
+            path_chars.push(c);
+        } else {
+            path_chars.push_str(&format!("\\x{{{:x}}}", c as u32));
+        }
+    }
+    let regex = format!("[^{}]+:[0-9]+(:[0-9]+)?", path_chars);
+    RegexSearch::new(&regex).expect("failed to parse file reference regex")
+}
+
+/// A reference to a line, and optionally a column, of an existing file
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRef {
+    pub path: PathBuf,
+    pub line: u32,
+    pub column: u32,
+}
+
+// Hyperlink URI used to mark the cells of a line marked by the user
+const MARK_URI: &str = "cosmic-term:mark";
+
+// Home row first, so the most common labels are easy to type
+const HINT_ALPHABET: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Labels for hints, all of the same length so no label is a prefix of another
+fn hint_labels(count: usize) -> Vec<String> {
+    let alphabet: Vec<char> = HINT_ALPHABET.chars().collect();
+    let mut labels: Vec<String> = alphabet.iter().map(|c| c.to_string()).collect();
+    while labels.len() < count {
+        labels = labels
+            .iter()
+            .flat_map(|label| alphabet.iter().map(move |c| format!("{}{}", label, c)))
+            .collect();
+    }
+    labels.truncate(count);
+    labels
+}
+
+/// A match labeled in hints mode
+pub struct HintMatch {
+    pub action: HintAction,
+    pub label: String,
+    pub range: Match,
+    pub text: String,
+}
+
+/// Build the regex for a find bar query. Plain text is escaped, and case sensitivity is set
+/// explicitly so that alacritty's smart case does not apply.
+pub fn search_pattern(value: &str, case_sensitive: bool, regex: bool) -> String {
+    let mut pattern = String::from(if case_sensitive { "(?-i)" } else { "(?i)" });
+    if regex {
+        pattern.push_str(value);
+    } else {
+        pattern.push_str(&regex::escape(value));
+    }
+    pattern
+}
+
+fn hex_to_rgb(hex_color: HexColor) -> Rgb {
+    Rgb {
+        r: hex_color.r,
+        g: hex_color.g,
+        b: hex_color.b,
+    }
+}
+
+fn rgb_to_color(rgb: Rgb) -> cosmic_text::Color {
+    cosmic_text::Color::rgb(rgb.r, rgb.g, rgb.b)
 }
 
 fn as_dim(mut color: Color) -> Color {
@@ -104,6 +208,126 @@ fn as_dim(mut color: Color) -> Color {
     color
 }
 
+// Relative luminance of a color, as used for WCAG contrast ratios
+fn luminance(color: cosmic_text::Color) -> f32 {
+    let channel = |c: u8| {
+        let c = f32::from(c) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b())
+}
+
+fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// Move the foreground towards black or white, whichever contrasts more with the background,
+// until it has the minimum contrast ratio
+fn with_minimum_contrast(
+    fg: cosmic_text::Color,
+    bg: cosmic_text::Color,
+    minimum_contrast: f32,
+) -> cosmic_text::Color {
+    let bg_luminance = luminance(bg);
+    if contrast_ratio(luminance(fg), bg_luminance) >= minimum_contrast {
+        return fg;
+    }
+    let target = if contrast_ratio(1.0, bg_luminance) >= contrast_ratio(0.0, bg_luminance) {
+        255.0
+    } else {
+        0.0
+    };
+    let mix = |amount: f32| {
+        let channel = |c: u8| (f32::from(c) + (target - f32::from(c)) * amount).round() as u8;
+        cosmic_text::Color::rgba(channel(fg.r()), channel(fg.g()), channel(fg.b()), fg.a())
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..8 {
+        let amount = (low + high) / 2.0;
+        if contrast_ratio(luminance(mix(amount)), bg_luminance) >= minimum_contrast {
+            high = amount;
+        } else {
+            low = amount;
+        }
+    }
+    mix(high)
+}
+
+// Find glyphs from fallback fonts that do not match the width of the cells they cover, and
+// store the scale that makes them fit. Returns true if any new glyphs were found.
+fn find_glyph_scales(
+    buffer: &Buffer,
+    cell_width: f32,
+    glyph_scales: &mut HashMap<char, f32>,
+) -> bool {
+    let metrics = buffer.metrics();
+    // Narrow glyphs are not scaled to be taller than the line
+    let max_scale = metrics.line_height / metrics.font_size;
+    let mut found = false;
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            let cluster = &run.text[glyph.start..glyph.end];
+            let Some(c) = cluster.chars().next() else {
+                continue;
+            };
+            let cells = cluster.width();
+            if cells == 0 || glyph.w <= 0.0 || glyph_scales.contains_key(&c) {
+                continue;
+            }
+            let width = cells as f32 * cell_width;
+            if (glyph.w - width).abs() > 0.5 {
+                glyph_scales.insert(c, (width / glyph.w).min(max_scale));
+                found = true;
+            }
+        }
+    }
+    found
+}
+
+// Colors without a dim variant are faded two thirds of the way towards the background
+fn blend_dim(fg: cosmic_text::Color, bg: cosmic_text::Color) -> cosmic_text::Color {
+    let blend = |fg: u8, bg: u8| ((u16::from(fg) * 2 + u16::from(bg)) / 3) as u8;
+    cosmic_text::Color::rgba(
+        blend(fg.r(), bg.r()),
+        blend(fg.g(), bg.g()),
+        blend(fg.b(), bg.b()),
+        fg.a(),
+    )
+}
+
+// Apply the paste filter policy to control characters other than tab and newline, so pasted
+// text cannot smuggle in escape sequences or carriage returns
+pub fn filter_paste(value: &str, paste_filter: PasteFilter, lone_cr_to_lf: bool) -> String {
+    let mut filtered = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' | '\n' => filtered.push(c),
+            '\r' if chars.peek() == Some(&'\n') => filtered.push(c),
+            '\r' if lone_cr_to_lf => filtered.push('\n'),
+            c if c.is_control() => match paste_filter {
+                PasteFilter::None => filtered.push(c),
+                PasteFilter::Strip => {}
+                PasteFilter::Escape => match c as u32 {
+                    // Caret notation for C0 controls and DEL
+                    code @ 0x00..=0x1F => {
+                        filtered.push('^');
+                        filtered.push(char::from(code as u8 + 0x40));
+                    }
+                    0x7F => filtered.push_str("^?"),
+                    _ => filtered.extend(c.escape_unicode()),
+                },
+            },
+            c => filtered.push(c),
+        }
+    }
+    filtered
+}
+
 pub static WINDOW_BG_COLOR: AtomicU32 = AtomicU32::new(0xFF000000);
 
 fn convert_color(colors: &Colors, color: Color) -> cosmic_text::Color {
@@ -133,6 +357,109 @@ fn convert_color(colors: &Colors, color: Color) -> cosmic_text::Color {
     cosmic_text::Color::rgb(rgb.r, rgb.g, rgb.b)
 }
 
+// Foreground and background colors of a cell as they are drawn, without the cursor and blinking
+fn cell_colors(
+    cell: &Cell,
+    colors: &Colors,
+    use_bright_bold: bool,
+    minimum_contrast: f32,
+) -> (cosmic_text::Color, cosmic_text::Color) {
+    let cell_fg = if cell.flags.contains(Flags::DIM) {
+        as_dim(cell.fg)
+    } else if use_bright_bold && cell.flags.contains(Flags::BOLD) {
+        as_bright(cell.fg)
+    } else {
+        cell.fg
+    };
+
+    let (mut fg, bg) = if cell.flags.contains(Flags::INVERSE) {
+        (
+            convert_color(colors, cell.bg),
+            convert_color(colors, cell_fg),
+        )
+    } else {
+        (
+            convert_color(colors, cell_fg),
+            convert_color(colors, cell.bg),
+        )
+    };
+
+    if cell.flags.contains(Flags::DIM) && !matches!(cell.fg, Color::Named(_)) {
+        fg = blend_dim(fg, bg);
+    }
+
+    if minimum_contrast > 1.0 {
+        fg = with_minimum_contrast(fg, bg, minimum_contrast);
+    }
+
+    if cell.flags.contains(Flags::HIDDEN) {
+        fg = bg;
+    }
+
+    (fg, bg)
+}
+
+fn html_color(color: cosmic_text::Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn push_html_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        _ => html.push(c),
+    }
+}
+
+fn selection_text<T>(term: &Term<T>, selection: &Selection) -> Option<String> {
+    let range = selection.to_range(term)?;
+    if !range.is_block {
+        // Like alacritty, whole lines end with a newline
+        let mut text = term.bounds_to_string(range.start, range.end);
+        if selection.ty == SelectionType::Lines {
+            text.push('\n');
+        }
+        return Some(text);
+    }
+
+    let mut text = String::new();
+    for line in range.start.line.0..=range.end.line.0 {
+        if line != range.start.line.0 {
+            text.push('\n');
+        }
+        let row = term.bounds_to_string(
+            Point::new(Line(line), range.start.column),
+            Point::new(Line(line), range.end.column),
+        );
+        text.push_str(row.trim_end());
+    }
+    Some(text)
+}
+
+// From the first line of scrollback through the last non-empty line, regardless of the current
+// scroll position. The alternate screen has no scrollback, so only the visible screen is used.
+fn history_bounds<T>(term: &Term<T>) -> (Point, Point) {
+    let grid = term.grid();
+    let start_line = if term.mode().contains(TermMode::ALT_SCREEN) {
+        Line(0)
+    } else {
+        Line(-(grid.history_size() as i32))
+    };
+    let mut end_line = grid.bottommost_line();
+    while end_line.0 > 0 {
+        if !grid[end_line].is_clear() {
+            break;
+        }
+        end_line.0 -= 1;
+    }
+    (
+        Point::new(start_line, Column(0)),
+        Point::new(end_line, Column(grid.columns() - 1)),
+    )
+}
+
 type TabModel = segmented_button::Model<segmented_button::SingleSelect>;
 pub struct TerminalPaneGrid {
     pub panes: pane_grid::State<TabModel>,
@@ -165,6 +492,10 @@ pub struct Metadata {
     pub bg: cosmic_text::Color,
     pub underline_color: cosmic_text::Color,
     pub flags: Flags,
+    /// Cursor drawn over this cell when it is not a block, which is drawn by swapping colors
+    pub cursor_opt: Option<(CursorShape, cosmic_text::Color)>,
+    /// Box drawing character drawn in this cell with the given color, the text has a space
+    pub box_drawing_opt: Option<(char, cosmic_text::Color)>,
 }
 
 impl Metadata {
@@ -174,9 +505,22 @@ impl Metadata {
             bg,
             underline_color,
             flags,
+            cursor_opt: None,
+            box_drawing_opt: None,
         }
     }
 
+    fn with_box_drawing(self, box_drawing_opt: Option<(char, cosmic_text::Color)>) -> Self {
+        Self {
+            box_drawing_opt,
+            ..self
+        }
+    }
+
+    fn with_cursor(self, cursor_opt: Option<(CursorShape, cosmic_text::Color)>) -> Self {
+        Self { cursor_opt, ..self }
+    }
+
     fn with_underline_color(self, underline_color: cosmic_text::Color) -> Self {
         Self {
             underline_color,
@@ -189,23 +533,160 @@ impl Metadata {
     }
 }
 
+// Where the output of a terminal comes from
+enum Backend {
+    // A process in a PTY, or a serial device if the profile has one
+    Process,
+    #[cfg(unix)]
+    Playback(PlaybackPty),
+}
+
+// Run the event loop that moves data between the terminal and the PTY on its own thread
+fn spawn_event_loop<T: EventedPty + OnResize + Send + 'static>(
+    term: Arc<FairMutex<Term<EventProxy>>>,
+    event_proxy: EventProxy,
+    pty: T,
+    hold: bool,
+) -> io::Result<Notifier> {
+    let pty_event_loop = EventLoop::new(term, event_proxy, pty, hold, false)?;
+    let notifier = Notifier(pty_event_loop.channel());
+    let _pty_join_handle = pty_event_loop.spawn();
+    Ok(notifier)
+}
+
+/// Stored in a tab whose terminal failed to spawn, so the error can be shown and the spawn retried
+pub struct TerminalSpawnError {
+    pub message: String,
+    pub options: Options,
+    pub profile_id_opt: Option<ProfileId>,
+    pub tab_title_override: Option<String>,
+}
+
+/// Connection state of a terminal running ssh for a remote profile
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RemoteState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// Host a remote profile connects to, shown in the tab title along with the connection state
+pub struct RemoteConnection {
+    pub destination: String,
+    pub state: RemoteState,
+}
+
+/// Image drawn behind the text, set by the profile of the terminal
+pub struct BackgroundImage {
+    pub path: String,
+    pub handle: image::Handle,
+    pub scale: BackgroundImageScale,
+    /// Amount of background color drawn over the image, from 0 to 1
+    pub dim: f32,
+}
+
+/// An image sent by a program, placed over the cells it covers
+pub struct ImagePlacement {
+    pub handle: image::Handle,
+    pub width: u32,
+    pub height: u32,
+    /// Column of the left edge of the image
+    pub column: usize,
+    /// Line of the top edge of the image, relative to the top of the screen
+    pub line: i32,
+}
+
+/// Cells of one line colored by a highlight of the profile
+pub struct HighlightRect {
+    /// Line relative to the top of the screen
+    pub line: i32,
+    pub start_column: usize,
+    /// Last column, included in the highlight
+    pub end_column: usize,
+    pub color: HexColor,
+}
+
+// Decoded images are dropped, oldest first, once they use more memory than this
+const IMAGE_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Statistics shown by the performance overlay, collected since they were last taken
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PerfStats {
+    pub frames: u32,
+    pub bytes_read: u64,
+    pub redraw_latency: Duration,
+    pub queue_depth: usize,
+}
+
 pub struct Terminal {
+    pub background_image_opt: Option<BackgroundImage>,
     pub context_menu: Option<cosmic::iced::Point>,
+    pub context_menu_url: Option<String>,
     pub metadata_set: IndexSet<Metadata>,
     pub needs_update: bool,
+    /// Set when only program output changed, rebuilds just the lines it damaged
+    pub needs_damage_update: bool,
+    /// Options the process was spawned with, used to restart it
+    pub options: Options,
     pub profile_id_opt: Option<ProfileId>,
+    pub remote_opt: Option<RemoteConnection>,
+    /// When the process was spawned, used to slow down restarts of processes that exit quickly
+    pub spawn_instant: Instant,
     pub tab_title_override: Option<String>,
+    /// Set when the program set the title, which then replaces the process title
+    pub title_from_program: bool,
     pub term: Arc<FairMutex<Term<EventProxy>>>,
+    alternate_scroll: bool,
     bold_font_weight: Weight,
     buffer: Arc<Buffer>,
+    child_pid_opt: Option<u32>,
     colors: Colors,
+    cursor_blink_hidden: bool,
+    // Blink phase of the cursor and text when lines were last rebuilt
+    damage_blink_phase: (bool, bool),
+    cursor_color_opt: Option<Rgb>,
+    cursor_text_color_opt: Option<Rgb>,
     default_attrs: Attrs<'static>,
+    default_cursor_style: CursorStyle,
     dim_font_weight: Weight,
+    font_ligatures: bool,
+    hints: Vec<HintMatch>,
+    hints_input: String,
+    hovered_url_opt: Option<Match>,
+    image_placements: Vec<ImagePlacement>,
+    // Highlights of the profile as configured, and their compiled patterns
+    highlights: Vec<Highlight>,
+    highlight_regexes: Vec<(RegexSearch, Highlight)>,
+    highlight_rects: Vec<HighlightRect>,
+    triggers: Vec<Trigger>,
+    glyph_scales: HashMap<char, f32>,
+    images: BTreeMap<u32, TerminalImage>,
+    minimum_contrast: f32,
     mouse_reporter: MouseReporter,
     notifier: Notifier,
+    paste_filter: PasteFilter,
+    paste_lone_cr_to_lf: bool,
+    // Whether output goes through the PTY reader, which holds it back while paused
+    pausable: bool,
+    paused: bool,
+    perf_stats: PerfStats,
+    // When output arrived that has not been drawn yet
+    output_instant_opt: Option<Instant>,
+    read_only: bool,
+    scroll_on_keypress: bool,
+    scroll_on_output: bool,
+    scrollback_archive: bool,
+    // Created once the first lines are archived
+    scrollback_archive_opt: Option<ScrollbackArchive>,
+    scrolling_history: usize,
     search_regex_opt: Option<RegexSearch>,
     search_value: String,
+    shell_state: Arc<Mutex<ShellState>>,
     size: Size,
+    text_blink_hidden: bool,
+    text_blinking: bool,
+    url_regex: RegexSearch,
+    file_ref_regex: RegexSearch,
     use_bright_bold: bool,
 }
 
@@ -221,14 +702,76 @@ impl Terminal {
         colors: Colors,
         profile_id_opt: Option<ProfileId>,
         tab_title_override: Option<String>,
+    ) -> Result<Self, io::Error> {
+        Self::with_backend(
+            pane,
+            entity,
+            event_tx,
+            config,
+            options,
+            app_config,
+            colors,
+            profile_id_opt,
+            tab_title_override,
+            Backend::Process,
+        )
+    }
+
+    /// Create a terminal that plays a recording instead of running a process
+    #[cfg(unix)]
+    pub fn new_playback(
+        pane: pane_grid::Pane,
+        entity: segmented_button::Entity,
+        event_tx: mpsc::Sender<(pane_grid::Pane, segmented_button::Entity, Event)>,
+        config: Config,
+        app_config: &AppConfig,
+        colors: Colors,
+        playback: PlaybackPty,
+    ) -> Result<Self, io::Error> {
+        Self::with_backend(
+            pane,
+            entity,
+            event_tx,
+            config,
+            Options::default(),
+            app_config,
+            colors,
+            None,
+            None,
+            Backend::Playback(playback),
+        )
+    }
+
+    fn with_backend(
+        pane: pane_grid::Pane,
+        entity: segmented_button::Entity,
+        event_tx: mpsc::Sender<(pane_grid::Pane, segmented_button::Entity, Event)>,
+        config: Config,
+        options: Options,
+        app_config: &AppConfig,
+        colors: Colors,
+        profile_id_opt: Option<ProfileId>,
+        tab_title_override: Option<String>,
+        backend: Backend,
     ) -> Result<Self, io::Error> {
         let font_stretch = app_config.typed_font_stretch();
         let font_weight = app_config.font_weight;
         let dim_font_weight = app_config.dim_font_weight;
         let bold_font_weight = app_config.bold_font_weight;
         let use_bright_bold = app_config.use_bright_bold;
+        let alternate_scroll = app_config.alternate_scroll;
+        let cursor_color_opt = app_config.cursor_color(profile_id_opt).map(hex_to_rgb);
+        let cursor_text_color_opt = app_config.cursor_text_color(profile_id_opt).map(hex_to_rgb);
+        let paste_filter = app_config.paste_filter;
+        let paste_lone_cr_to_lf = app_config.paste_lone_cr_to_lf;
+        let scroll_on_keypress = app_config.scroll_on_keypress;
+        let scroll_on_output = app_config.scroll_on_output;
+        let scrollback_archive = app_config.scrollback_archive;
 
         let metrics = Metrics::new(14.0, 20.0);
+        let scrolling_history = config.scrolling_history;
+        let default_cursor_style = config.default_cursor_style;
+        let file_ref_regex = file_ref_regex(&config.semantic_escape_chars);
 
         let default_bg = convert_color(&colors, Color::Named(NamedColor::Background));
         let default_fg = convert_color(&colors, Color::Named(NamedColor::Foreground));
@@ -274,33 +817,153 @@ impl Terminal {
         )));
 
         let window_id = 0;
-        let pty = tty::new(&options, size.into(), window_id)?;
-
-        let pty_event_loop = EventLoop::new(term.clone(), event_proxy, pty, options.hold, false)?;
-        let notifier = Notifier(pty_event_loop.channel());
-        let _pty_join_handle = pty_event_loop.spawn();
+        let shell_state = Arc::new(Mutex::new(ShellState::default()));
+        shell_state.lock().unwrap().set_cell_size(
+            cell_width,
+            cell_height,
+            size.columns(),
+            size.screen_lines(),
+        );
+        shell_state
+            .lock()
+            .unwrap()
+            .set_triggers(&app_config.triggers);
+        // Profiles may log the output of every tab they open
+        if let Some(profile) = profile_id_opt
+            .and_then(|profile_id| app_config.profiles.get(&profile_id))
+            .filter(|profile| !profile.output_log_file.trim().is_empty())
+        {
+            let path = Path::new(profile.output_log_file.trim());
+            match OutputLog::open(path, profile.output_log_format) {
+                Ok(output_log) => shell_state.lock().unwrap().start_output_log(output_log),
+                Err(err) => log::error!("failed to open output log {:?}: {}", path, err),
+            }
+        }
+        // Serial profiles connect the event loop to the device instead of a PTY
+        #[cfg(unix)]
+        let serial_opt = profile_id_opt
+            .and_then(|profile_id| app_config.profiles.get(&profile_id))
+            .filter(|profile| profile.is_serial())
+            .map(|profile| {
+                SerialPty::open(
+                    profile.serial_device.trim(),
+                    profile.serial_baud_rate,
+                    profile.serial_parity,
+                )
+            })
+            .transpose()?;
+        // Playback has its own pause, its output does not go through the PTY reader
+        let pausable = cfg!(unix) && matches!(backend, Backend::Process);
+        #[cfg(unix)]
+        let (notifier, child_pid_opt) = match (backend, serial_opt) {
+            (Backend::Playback(playback), _) => (
+                spawn_event_loop(term.clone(), event_proxy, playback, false)?,
+                None,
+            ),
+            (Backend::Process, Some(serial)) => {
+                let serial = ShellIntegrationPty::new(serial, shell_state.clone())?;
+                (
+                    spawn_event_loop(term.clone(), event_proxy, serial, false)?,
+                    None,
+                )
+            }
+            (Backend::Process, None) => {
+                let pty = tty::new(&options, size.into(), window_id)?;
+                let pty = ShellIntegrationPty::new(pty, shell_state.clone())?;
+                let child_pid_opt = Some(pty.child().id());
+                (
+                    spawn_event_loop(term.clone(), event_proxy, pty, options.hold)?,
+                    child_pid_opt,
+                )
+            }
+        };
+        #[cfg(not(unix))]
+        let (notifier, child_pid_opt) = match backend {
+            Backend::Process => {
+                let pty = tty::new(&options, size.into(), window_id)?;
+                (
+                    spawn_event_loop(term.clone(), event_proxy, pty, options.hold)?,
+                    None,
+                )
+            }
+        };
 
         Ok(Self {
+            alternate_scroll,
+            background_image_opt: None,
             bold_font_weight: Weight(bold_font_weight),
             buffer: Arc::new(buffer),
+            child_pid_opt,
             colors,
             context_menu: None,
+            context_menu_url: None,
+            cursor_blink_hidden: false,
+            damage_blink_phase: (false, false),
+            cursor_color_opt,
+            cursor_text_color_opt,
             default_attrs,
+            default_cursor_style,
             dim_font_weight: Weight(dim_font_weight),
+            hints: Vec::new(),
+            hints_input: String::new(),
+            hovered_url_opt: None,
+            image_placements: Vec::new(),
+            highlights: Vec::new(),
+            highlight_regexes: Vec::new(),
+            highlight_rects: Vec::new(),
+            triggers: app_config.triggers.clone(),
+            font_ligatures: app_config.font_ligatures,
+            glyph_scales: HashMap::new(),
+            images: BTreeMap::new(),
+            minimum_contrast: f32::from(app_config.minimum_contrast_mul_10) / 10.0,
             metadata_set,
             mouse_reporter: Default::default(),
             needs_update: true,
+            needs_damage_update: false,
             notifier,
+            options,
+            paste_filter,
+            paste_lone_cr_to_lf,
+            pausable,
+            paused: false,
+            read_only: false,
+            perf_stats: PerfStats::default(),
+            output_instant_opt: None,
             profile_id_opt,
+            spawn_instant: Instant::now(),
+            remote_opt: None,
+            scroll_on_keypress,
+            scroll_on_output,
+            scrollback_archive,
+            scrollback_archive_opt: None,
             search_regex_opt: None,
+            scrolling_history,
             search_value: String::new(),
+            shell_state,
+            text_blink_hidden: false,
+            text_blinking: false,
             size,
             tab_title_override,
+            title_from_program: false,
             term,
+            url_regex: RegexSearch::new(URL_REGEX).expect("failed to parse URL regex"),
+            file_ref_regex,
             use_bright_bold,
         })
     }
 
+    /// Show text as if the process wrote it, used after the process has exited
+    pub fn write_output(&mut self, text: &str) {
+        let mut processor: Processor = Processor::new();
+        {
+            let mut term = self.term.lock();
+            for byte in text.bytes() {
+                processor.advance(&mut *term, byte);
+            }
+        }
+        self.needs_update = true;
+    }
+
     pub fn buffer_weak(&self) -> Weak<Buffer> {
         Arc::downgrade(&self.buffer)
     }
@@ -319,6 +982,292 @@ impl Terminal {
         &self.colors
     }
 
+    /// Find the URL at a point, either an OSC 8 hyperlink or text that looks like a URL
+    pub fn url_at(&mut self, point: Point) -> Option<(String, Match)> {
+        let term = self.term.lock();
+        let grid = term.grid();
+        if point.line < grid.topmost_line()
+            || point.line > grid.bottommost_line()
+            || point.column > grid.last_column()
+        {
+            return None;
+        }
+
+        if let Some(hyperlink) = grid[point].hyperlink().filter(|hyperlink| {
+            hyperlink.uri() != PROMPT_URI
+                && hyperlink.uri() != MARK_URI
+                && hyperlink.uri() != BLINK_URI
+                && !hyperlink.uri().starts_with(IMAGE_URI_PREFIX)
+        }) {
+            // Extend to neighboring cells with the same hyperlink
+            let mut start = point;
+            while start.column.0 > 0 {
+                let prev = Point::new(start.line, start.column - 1);
+                if grid[prev].hyperlink().as_ref() != Some(&hyperlink) {
+                    break;
+                }
+                start = prev;
+            }
+            let mut end = point;
+            while end.column < grid.last_column() {
+                let next = Point::new(end.line, end.column + 1);
+                if grid[next].hyperlink().as_ref() != Some(&hyperlink) {
+                    break;
+                }
+                end = next;
+            }
+            return Some((hyperlink.uri().to_string(), start..=end));
+        }
+
+        let start = term.line_search_left(point);
+        let end = term.line_search_right(point);
+        let url_match = RegexIter::new(start, end, Direction::Right, &term, &mut self.url_regex)
+            .find(|url_match| url_match.contains(&point))?;
+        let mut url = term.bounds_to_string(*url_match.start(), *url_match.end());
+
+        // Drop trailing punctuation and unbalanced closing parentheses
+        let mut trimmed = 0;
+        while let Some(c) = url.chars().last() {
+            let unbalanced = c == ')' && url.matches('(').count() < url.matches(')').count();
+            if !(unbalanced || ".,:;!?'\"".contains(c)) {
+                break;
+            }
+            url.pop();
+            trimmed += 1;
+        }
+        let end = url_match.end().sub(&*term, Boundary::Grid, trimmed);
+        Some((url, *url_match.start()..=end))
+    }
+
+    /// Find a file reference at a point, if the file exists relative to the working directory
+    pub fn file_ref_at(&mut self, point: Point) -> Option<(FileRef, Match)> {
+        let (text, file_ref_match) = {
+            let term = self.term.lock();
+            let grid = term.grid();
+            if point.line < grid.topmost_line()
+                || point.line > grid.bottommost_line()
+                || point.column > grid.last_column()
+            {
+                return None;
+            }
+            let start = term.line_search_left(point);
+            let end = term.line_search_right(point);
+            let file_ref_match = RegexIter::new(
+                start,
+                end,
+                Direction::Right,
+                &term,
+                &mut self.file_ref_regex,
+            )
+            .find(|file_ref_match| file_ref_match.contains(&point))?;
+            (
+                term.bounds_to_string(*file_ref_match.start(), *file_ref_match.end()),
+                file_ref_match,
+            )
+        };
+
+        let mut parts = text.split(':');
+        let path = parts.next()?;
+        let line = parts.next()?.parse().ok()?;
+        let column = parts
+            .next()
+            .and_then(|column| column.parse().ok())
+            .unwrap_or(1);
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+            None => self.current_working_directory()?.join(path),
+        };
+        if !path.is_file() {
+            return None;
+        }
+        Some((FileRef { path, line, column }, file_ref_match))
+    }
+
+    /// Set the URL under the mouse, which is underlined
+    pub fn set_hovered_url(&mut self, hovered_url_opt: Option<Match>) {
+        if self.hovered_url_opt != hovered_url_opt {
+            self.hovered_url_opt = hovered_url_opt;
+            self.needs_update = true;
+        }
+    }
+
+    pub fn image_placements(&self) -> &[ImagePlacement] {
+        &self.image_placements
+    }
+
+    pub fn highlight_rects(&self) -> &[HighlightRect] {
+        &self.highlight_rects
+    }
+
+    // Find the highlights of the profile on the screen
+    fn update_highlights(&mut self) {
+        self.highlight_rects.clear();
+        if self.highlight_regexes.is_empty() {
+            return;
+        }
+
+        let term = self.term.lock();
+        let grid = term.grid();
+        let display_offset = grid.display_offset() as i32;
+        let start = Point::new(Line(-display_offset), Column(0));
+        let end = Point::new(
+            Line(grid.screen_lines() as i32 - 1 - display_offset),
+            grid.last_column(),
+        );
+        let mut matches: Vec<(Match, &Highlight)> = Vec::new();
+        for (regex, highlight) in self.highlight_regexes.iter_mut() {
+            for highlight_match in RegexIter::new(start, end, Direction::Right, &term, regex) {
+                let overlaps = matches.iter().any(|(other, _)| {
+                    highlight_match.start() <= other.end() && other.start() <= highlight_match.end()
+                });
+                if !overlaps {
+                    matches.push((highlight_match, &*highlight));
+                }
+            }
+        }
+
+        // Matches that wrap are split into one rectangle per line
+        let mut rects = Vec::new();
+        for (highlight_match, highlight) in matches {
+            let start = *highlight_match.start();
+            let end = *highlight_match.end();
+            for line in start.line.0..=end.line.0 {
+                let (start_column, end_column) = if highlight.line {
+                    (0, grid.last_column().0)
+                } else {
+                    (
+                        if line == start.line.0 {
+                            start.column.0
+                        } else {
+                            0
+                        },
+                        if line == end.line.0 {
+                            end.column.0
+                        } else {
+                            grid.last_column().0
+                        },
+                    )
+                };
+                rects.push(HighlightRect {
+                    line: line + display_offset,
+                    start_column,
+                    end_column,
+                    color: highlight.color,
+                });
+            }
+        }
+        drop(term);
+        self.highlight_rects = rects;
+    }
+
+    pub fn hovered_url(&self) -> Option<&Match> {
+        self.hovered_url_opt.as_ref()
+    }
+
+    /// Working directory of the shell, as reported with OSC 7 or else read from the process
+    pub fn current_working_directory(&self) -> Option<PathBuf> {
+        // Reported directories follow the shell even when it is not the process started here
+        if let Some(path) = self.shell_state.lock().unwrap().working_directory() {
+            return Some(path);
+        }
+        // Only Linux has /proc, elsewhere the directory is only known if the shell reports it
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+        let pid = self.child_pid_opt?;
+        match fs::read_link(format!("/proc/{}/cwd", pid)) {
+            Ok(path) => Some(path),
+            Err(err) => {
+                log::warn!(
+                    "failed to read working directory of process {}: {}",
+                    pid,
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Name of the program in the foreground of the terminal, if it is not the shell itself
+    #[cfg(target_os = "linux")]
+    pub fn foreground_process_name(&self) -> Option<String> {
+        let pid = self.child_pid_opt?;
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name may contain spaces and parentheses, so skip past the last one
+        let (_, fields) = stat.rsplit_once(')')?;
+        let tpgid: i64 = fields.split_whitespace().nth(5)?.parse().ok()?;
+        if tpgid <= 0 || tpgid == i64::from(pid) {
+            return None;
+        }
+        let comm = fs::read_to_string(format!("/proc/{}/comm", tpgid)).ok()?;
+        Some(comm.trim().to_string())
+    }
+
+    /// Without /proc the foreground program is not known, so a running shell may have one. Its
+    /// name is left empty.
+    #[cfg(not(target_os = "linux"))]
+    pub fn foreground_process_name(&self) -> Option<String> {
+        self.child_pid_opt.map(|_| String::new())
+    }
+
+    /// Title made from the foreground program and the working directory of the shell, with
+    /// {process} and {directory} in the format replaced. None if the format is empty.
+    #[cfg(target_os = "linux")]
+    pub fn process_title(&self, format: &str) -> Option<String> {
+        if format.is_empty() {
+            return None;
+        }
+        let pid = self.child_pid_opt?;
+        let process = match self.foreground_process_name() {
+            Some(name) => name,
+            None => fs::read_to_string(format!("/proc/{}/comm", pid))
+                .ok()?
+                .trim()
+                .to_string(),
+        };
+        // Read directly, as this is checked often and failures are not worth a warning
+        let path_opt = self
+            .shell_state
+            .lock()
+            .unwrap()
+            .working_directory()
+            .or_else(|| fs::read_link(format!("/proc/{}/cwd", pid)).ok());
+        let directory = match path_opt {
+            Some(path) => match std::env::var_os("HOME")
+                .and_then(|home| path.strip_prefix(home).ok().map(PathBuf::from))
+            {
+                Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+                Some(rest) => format!("~/{}", rest.display()),
+                None => path.display().to_string(),
+            },
+            None => String::new(),
+        };
+        Some(
+            format
+                .replace("{process}", &process)
+                .replace("{directory}", &directory),
+        )
+    }
+
+    /// Process titles are read from /proc, so tabs keep their other titles elsewhere
+    #[cfg(not(target_os = "linux"))]
+    pub fn process_title(&self, _format: &str) -> Option<String> {
+        None
+    }
+
+    /// Resolve a color as programs see it: colors set dynamically with OSC 4/10/11/12 take
+    /// precedence over the configured cursor colors, which take precedence over the theme.
+    pub fn effective_color(&self, index: usize) -> Option<Rgb> {
+        let term = self.term.lock();
+        term.colors()[index]
+            .or(if index == NamedColor::Cursor as usize {
+                self.cursor_color_opt
+            } else {
+                None
+            })
+            .or(self.colors[index])
+    }
+
     pub fn default_attrs(&self) -> &Attrs<'static> {
         &self.default_attrs
     }
@@ -327,6 +1276,16 @@ impl Terminal {
         self.size
     }
 
+    /// Offset of the grid in the view, which splits the space that does not fit a whole cell
+    /// evenly between the sides
+    pub fn grid_offset(&self) -> (f32, f32) {
+        let size = self.size;
+        (
+            ((size.width as f32 - size.columns() as f32 * size.cell_width) / 2.0).max(0.0),
+            ((size.height as f32 - size.screen_lines() as f32 * size.cell_height) / 2.0).max(0.0),
+        )
+    }
+
     pub fn redraw(&self) -> bool {
         self.buffer.redraw()
     }
@@ -335,16 +1294,218 @@ impl Terminal {
         self.with_buffer_mut(|buffer| buffer.set_redraw(redraw));
     }
 
+    /// Returns true if a full screen program has switched to the alternate screen, which has no
+    /// scrollback
+    pub fn is_alt_screen(&self) -> bool {
+        let term = self.term.lock();
+        term.mode().contains(TermMode::ALT_SCREEN)
+    }
+
+    /// Returns true if the output can be paused, which playback tabs and non-Unix systems lack
+    pub fn pausable(&self) -> bool {
+        self.pausable
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Freeze or unfreeze the displayed output. While paused, output is still read from the PTY
+    /// so no XOFF is sent to the process, but it is kept by the PTY reader instead of being
+    /// applied to the grid.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.pausable && paused != self.paused {
+            self.paused = paused;
+            // Output read while paused is applied by the PTY reader thread, before newer output
+            self.shell_state.lock().unwrap().set_paused(paused);
+            self.needs_update = true;
+        }
+    }
+
+    /// Returns true if output received while paused was dropped to bound its memory
+    pub fn paused_overflow(&self) -> bool {
+        self.paused && self.shell_state.lock().unwrap().paused_dropped()
+    }
+
+    /// Start recording the output to an asciicast file
+    pub fn start_recording(&self, path: &Path) -> io::Result<()> {
+        let recording = Recording::new(path, self.size.columns(), self.size.screen_lines())?;
+        self.shell_state.lock().unwrap().start_recording(recording);
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) {
+        self.shell_state.lock().unwrap().stop_recording();
+    }
+
+    /// File the output is being recorded to, if it is
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.shell_state.lock().unwrap().recording_path()
+    }
+
+    /// Start appending the output to a log file
+    pub fn start_output_log(&self, path: &Path, format: OutputLogFormat) -> io::Result<()> {
+        let output_log = OutputLog::open(path, format)?;
+        self.shell_state
+            .lock()
+            .unwrap()
+            .start_output_log(output_log);
+        Ok(())
+    }
+
+    pub fn stop_output_log(&self) {
+        self.shell_state.lock().unwrap().stop_output_log();
+    }
+
+    /// File the output is being logged to, if it is
+    pub fn output_log_path(&self) -> Option<PathBuf> {
+        self.shell_state.lock().unwrap().output_log_path()
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Block keyboard, mouse and pasted input from reaching the process, output is still shown
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
     pub fn input_no_scroll<I: Into<Cow<'static, [u8]>>>(&self, input: I) {
         self.notifier.notify(input);
     }
 
+    /// Send key releases and mouse reports, which do not scroll and are blocked when read-only
+    pub fn input_event<I: Into<Cow<'static, [u8]>>>(&self, input: I) {
+        if !self.read_only {
+            self.input_no_scroll(input);
+        }
+    }
+
+    /// Whether the cursor blinks, either by default or as requested by the program
+    pub fn cursor_blinking(&self) -> bool {
+        self.term.lock().cursor_style().blinking
+    }
+
+    /// Whether the cursor or any visible text blinks
+    pub fn blinking(&self) -> bool {
+        self.text_blinking || self.cursor_blinking()
+    }
+
+    /// Advance blinking of the cursor and text, showing them if they no longer blink
+    pub fn blink(&mut self) {
+        let cursor_blink_hidden = !self.cursor_blink_hidden && self.cursor_blinking();
+        if cursor_blink_hidden != self.cursor_blink_hidden {
+            self.cursor_blink_hidden = cursor_blink_hidden;
+            self.needs_update = true;
+        }
+        let text_blink_hidden = !self.text_blink_hidden && self.text_blinking;
+        if text_blink_hidden != self.text_blink_hidden {
+            self.text_blink_hidden = text_blink_hidden;
+            self.needs_update = true;
+        }
+    }
+
+    /// Show the cursor and text hidden by blinking, so blinking starts over in phase
+    pub fn reset_blink(&mut self) {
+        if self.cursor_blink_hidden || self.text_blink_hidden {
+            self.cursor_blink_hidden = false;
+            self.text_blink_hidden = false;
+            self.needs_update = true;
+        }
+    }
+
+    /// Whether a program started blinking text since the last call
+    pub fn take_blink_started(&self) -> bool {
+        self.shell_state.lock().unwrap().take_blink_started()
+    }
+
+    /// Mark new output to be drawn, which starts measuring the redraw latency
+    pub fn output_ready(&mut self) {
+        self.needs_damage_update = true;
+        self.output_instant_opt.get_or_insert_with(Instant::now);
+
+        // Removing archived lines moves the lines that are left
+        if self.archive_scrollback() {
+            self.needs_update = true;
+        }
+
+        // Copy mode keeps the view where its cursor is
+        if self.scroll_on_output && !self.copy_mode() {
+            let mut term = self.term.lock();
+            if term.grid().display_offset() != 0 {
+                term.scroll_display(TerminalScroll::Bottom);
+                self.needs_update = true;
+            }
+        }
+    }
+
+    /// Count a drawn frame, and how long its output waited to be drawn
+    pub fn frame_drawn(&mut self) {
+        self.perf_stats.frames += 1;
+        if let Some(instant) = self.output_instant_opt.take() {
+            self.perf_stats.redraw_latency = instant.elapsed();
+        }
+    }
+
+    /// Statistics collected since the last call
+    pub fn take_perf_stats(&mut self) -> PerfStats {
+        let mut perf_stats = mem::take(&mut self.perf_stats);
+        perf_stats.bytes_read = self.shell_state.lock().unwrap().take_bytes_read();
+        perf_stats
+    }
+
+    /// Send typed input, which scrolls to the bottom unless scroll on keypress is disabled
     pub fn input_scroll<I: Into<Cow<'static, [u8]>>>(&self, input: I) {
+        if self.read_only {
+            return;
+        }
         self.input_no_scroll(input);
-        self.scroll(TerminalScroll::Bottom);
+        if self.scroll_on_keypress {
+            self.scroll(TerminalScroll::Bottom);
+        }
+    }
+
+    /// Send a key encoded for the current mode, returns false if the key sends nothing
+    pub fn input_key(&self, key_input: &KeyInput) -> bool {
+        let mode = *self.term.lock().mode();
+        match key_input.encode(mode) {
+            Some(escape_code) if key_input.release => self.input_event(escape_code),
+            Some(escape_code) => self.input_scroll(escape_code),
+            None => return false,
+        }
+        true
+    }
+
+    /// Text that pasting this value sends, after the paste filter and line break settings
+    pub fn filter_paste(&self, value: &str) -> String {
+        filter_paste(value, self.paste_filter, self.paste_lone_cr_to_lf)
+    }
+
+    /// Whether pasting this text could run commands or send controls the user did not expect
+    pub fn paste_needs_confirm(&self, value: &str) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let value = self.filter_paste(value);
+        let bracketed_paste = {
+            let term = self.term.lock();
+            term.mode().contains(TermMode::BRACKETED_PASTE)
+        };
+        value.chars().any(|c| match c {
+            // Line breaks are only harmless when the program knows they were pasted
+            '\n' | '\r' => !bracketed_paste,
+            '\t' => false,
+            c => c.is_control(),
+        })
     }
 
     pub fn paste(&self, value: String) {
+        if self.read_only {
+            return;
+        }
+        let value = self.filter_paste(&value);
+
         // This code is ported from alacritty
         let bracketed_paste = {
             let term = self.term.lock();
@@ -374,6 +1535,16 @@ impl Terminal {
 
             self.notifier.on_resize(self.size.into());
             self.term.lock().resize(self.size);
+            {
+                let mut shell_state = self.shell_state.lock().unwrap();
+                shell_state.set_cell_size(
+                    self.size.cell_width,
+                    self.size.cell_height,
+                    self.size.columns(),
+                    self.size.screen_lines(),
+                );
+                shell_state.record_resize(self.size.columns(), self.size.screen_lines());
+            }
 
             self.with_buffer_mut(|buffer| {
                 let mut font_system = font_system().write().unwrap();
@@ -390,6 +1561,34 @@ impl Terminal {
         self.term.lock().scroll_display(scroll);
     }
 
+    /// Scroll the display by a number of lines, positive being up. On the alternate screen,
+    /// which has no scrollback, arrow keys are sent instead if alternate scroll is enabled.
+    pub fn scroll_lines(&self, lines: i32) {
+        let (alternate_scroll, app_cursor) = {
+            let term = self.term.lock();
+            let mode = term.mode();
+            (
+                self.alternate_scroll
+                    && mode.contains(TermMode::ALT_SCREEN)
+                    && mode.contains(TermMode::ALTERNATE_SCROLL),
+                mode.contains(TermMode::APP_CURSOR),
+            )
+        };
+        if alternate_scroll {
+            let code: &'static [u8] = match (lines > 0, app_cursor) {
+                (true, true) => b"\x1BOA",
+                (true, false) => b"\x1B[A",
+                (false, true) => b"\x1BOB",
+                (false, false) => b"\x1B[B",
+            };
+            for _ in 0..lines.unsigned_abs() {
+                self.input_event(code);
+            }
+        } else {
+            self.scroll(TerminalScroll::Delta(lines));
+        }
+    }
+
     pub fn scroll_to(&self, ratio: f32) {
         let mut term = self.term.lock();
         let grid = term.grid();
@@ -402,6 +1601,87 @@ impl Terminal {
         ));
     }
 
+    /// Whether the view is at the first line of scrollback
+    pub fn scrolled_to_top(&self) -> bool {
+        let term = self.term.lock();
+        let grid = term.grid();
+        grid.history_size() > 0 && grid.display_offset() == grid.history_size()
+    }
+
+    /// Number of lines moved out of the scrollback into the archive
+    pub fn archived_lines(&self) -> usize {
+        self.scrollback_archive_opt
+            .as_ref()
+            .map_or(0, |archive| archive.lines())
+    }
+
+    /// Text of the lines moved out of the scrollback into the archive, oldest first
+    pub fn archived_text(&self) -> io::Result<String> {
+        match &self.scrollback_archive_opt {
+            Some(archive) => archive.text(),
+            None => Ok(String::new()),
+        }
+    }
+
+    // Move the oldest half of the scrollback to the archive once it is three quarters full,
+    // leaving room for the output that arrives before the next check. Returns true if lines
+    // were removed from the scrollback.
+    fn archive_scrollback(&mut self) -> bool {
+        if !self.scrollback_archive {
+            return false;
+        }
+
+        let keep = self.scrolling_history / 2;
+        let (text, lines) = {
+            let mut term = self.term.lock();
+            let grid = term.grid();
+            let history_size = grid.history_size();
+            if history_size <= keep || history_size < self.scrolling_history * 3 / 4 {
+                return false;
+            }
+
+            let first_kept = Line(-(keep as i32));
+            let start = Point::new(Line(-(history_size as i32)), Column(0));
+            let end = Point::new(Line(-(keep as i32) - 1), grid.last_column());
+            let mut text = term.bounds_to_string(start, end);
+            text.push('\n');
+
+            // Nothing may point at the lines that are removed
+            if term
+                .selection
+                .as_ref()
+                .and_then(|selection| selection.to_range(&term))
+                .is_some_and(|range| range.start.line < first_kept)
+            {
+                term.selection = None;
+            }
+            if term.vi_mode_cursor.point.line < first_kept {
+                term.vi_mode_cursor.point.line = first_kept;
+            }
+
+            // Shrinking the history drops its oldest lines, then the limit is restored
+            let grid = term.grid_mut();
+            grid.update_history(keep);
+            grid.update_history(self.scrolling_history);
+            (text, history_size - keep)
+        };
+
+        let archive = match &mut self.scrollback_archive_opt {
+            Some(archive) => archive,
+            None => match ScrollbackArchive::new() {
+                Ok(archive) => self.scrollback_archive_opt.insert(archive),
+                Err(err) => {
+                    log::error!("failed to create scrollback archive: {}", err);
+                    return true;
+                }
+            },
+        };
+        if let Err(err) = archive.push(text, lines) {
+            log::error!("failed to archive scrollback: {}", err);
+        }
+        true
+    }
+
     pub fn scrollbar(&self) -> Option<(f32, f32)> {
         let term = self.term.lock();
         let grid = term.grid();
@@ -418,25 +1698,223 @@ impl Terminal {
         }
     }
 
+    /// Line ranges of the prompts marked by shell integration, from oldest to newest
+    fn prompt_blocks(&self) -> Vec<(Line, Line)> {
+        self.link_blocks(&[PROMPT_URI])
+    }
+
+    // Line ranges with cells linked to one of the URIs, from oldest to newest
+    fn link_blocks(&self, uris: &[&str]) -> Vec<(Line, Line)> {
+        let term = self.term.lock();
+        let grid = term.grid();
+        let mut blocks: Vec<(Line, Line)> = Vec::new();
+        for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+            let line = Line(line);
+            let is_linked = (0..grid.columns()).any(|column| {
+                grid[line][Column(column)]
+                    .hyperlink()
+                    .map_or(false, |hyperlink| uris.contains(&hyperlink.uri()))
+            });
+            if !is_linked {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some((_, end)) if end.0 + 1 == line.0 => *end = line,
+                _ => blocks.push((line, line)),
+            }
+        }
+        blocks
+    }
+
+    /// Scroll so the previous or next shell prompt is at the top of the screen
+    pub fn scroll_to_prompt(&mut self, previous: bool) {
+        let blocks = self.prompt_blocks();
+        self.scroll_to_block(&blocks, previous);
+    }
+
+    /// Scroll to the previous or next manual mark or prompt
+    pub fn scroll_to_mark(&mut self, previous: bool) {
+        let blocks = self.link_blocks(&[MARK_URI, PROMPT_URI]);
+        self.scroll_to_block(&blocks, previous);
+    }
+
+    fn scroll_to_block(&self, blocks: &[(Line, Line)], previous: bool) {
+        let mut term = self.term.lock();
+        let old_display_offset = term.grid().display_offset() as i32;
+        let top = Line(-old_display_offset);
+        let target_opt = if previous {
+            blocks.iter().rev().find(|(start, _)| *start < top)
+        } else {
+            blocks.iter().find(|(start, _)| *start > top)
+        };
+        match target_opt {
+            Some((start, _)) => {
+                let new_display_offset = (-start.0).max(0);
+                term.scroll_display(TerminalScroll::Delta(
+                    new_display_offset - old_display_offset,
+                ));
+            }
+            None if !previous => term.scroll_display(TerminalScroll::Bottom),
+            None => {}
+        }
+    }
+
+    /// Set or clear a manual mark on the line of the copy mode cursor, the selection, or the
+    /// cursor. The mark links the cells that are not already linked, so it moves with them.
+    pub fn toggle_mark(&mut self) {
+        {
+            let mut term = self.term.lock();
+            let line = if term.mode().contains(TermMode::VI) {
+                term.vi_mode_cursor.point.line
+            } else if let Some(range) = term
+                .selection
+                .as_ref()
+                .and_then(|selection| selection.to_range(&term))
+            {
+                range.start.line
+            } else {
+                term.grid().cursor.point.line
+            };
+
+            let grid = term.grid_mut();
+            let marked = (0..grid.columns()).any(|column| {
+                grid[line][Column(column)]
+                    .hyperlink()
+                    .is_some_and(|hyperlink| hyperlink.uri() == MARK_URI)
+            });
+            for column in 0..grid.columns() {
+                let cell = &mut grid[line][Column(column)];
+                match cell.hyperlink() {
+                    Some(hyperlink) if marked && hyperlink.uri() == MARK_URI => {
+                        cell.set_hyperlink(None);
+                    }
+                    None if !marked => {
+                        cell.set_hyperlink(Some(Hyperlink::new(None, MARK_URI)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.needs_update = true;
+    }
+
+    /// Positions of manual marks and prompts as a ratio of all lines from the top of the
+    /// scrollback, and whether each one is a manual mark. Only the first column is checked,
+    /// where prompts usually start, which keeps this fast enough to draw.
+    pub fn mark_positions(&self) -> Vec<(f32, bool)> {
+        let term = self.term.lock();
+        let grid = term.grid();
+        let total = grid.history_size() + grid.screen_lines();
+        let mut positions = Vec::new();
+        let mut last_opt = None;
+        for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+            let mark_opt = grid[Line(line)][Column(0)]
+                .hyperlink()
+                .and_then(|hyperlink| match hyperlink.uri() {
+                    MARK_URI => Some(true),
+                    PROMPT_URI => Some(false),
+                    _ => None,
+                });
+            // Only the first line of a multi-line prompt is shown
+            if mark_opt.is_some() && mark_opt != last_opt {
+                let index = (line + grid.history_size() as i32) as usize;
+                positions.push((index as f32 / total as f32, mark_opt == Some(true)));
+            }
+            last_opt = mark_opt;
+        }
+        positions
+    }
+
+    /// Take the last command reported finished by shell integration, along with its command
+    /// line as shown after the prompt
+    pub fn take_finished_command(&self) -> Option<(String, FinishedCommand)> {
+        let finished = self.shell_state.lock().unwrap().take_finished()?;
+        let blocks = self.prompt_blocks();
+        let index = blocks.len().checked_sub(finished.prompts_after + 1);
+        let command = match index.and_then(|index| blocks.get(index)) {
+            Some((_, end)) => {
+                let term = self.term.lock();
+                let grid = term.grid();
+                let prompt_end_opt = (0..grid.columns()).rev().find(|column| {
+                    grid[*end][Column(*column)]
+                        .hyperlink()
+                        .map_or(false, |hyperlink| hyperlink.uri() == PROMPT_URI)
+                });
+                match prompt_end_opt {
+                    Some(prompt_end) if prompt_end < grid.last_column().0 => term
+                        .bounds_to_string(
+                            Point::new(*end, Column(prompt_end + 1)),
+                            Point::new(*end, grid.last_column()),
+                        )
+                        .trim()
+                        .to_string(),
+                    _ => String::new(),
+                }
+            }
+            None => String::new(),
+        };
+        Some((command, finished))
+    }
+
+    /// Take the replies to programs, like kitty graphics protocol responses
+    pub fn take_responses(&self) -> Vec<Vec<u8>> {
+        self.shell_state.lock().unwrap().take_responses()
+    }
+
+    /// Take the triggers that matched output since the last call
+    pub fn take_trigger_matches(&self) -> Vec<TriggerMatch> {
+        self.shell_state.lock().unwrap().take_trigger_matches()
+    }
+
+    /// Take the notifications requested by programs with OSC 9 or OSC 777
+    pub fn take_notifications(&self) -> Vec<Notification> {
+        self.shell_state.lock().unwrap().take_notifications()
+    }
+
+    /// Get the output of the last command, which lies between the last two shell prompts
+    pub fn last_command_output(&self) -> Option<String> {
+        let blocks = self.prompt_blocks();
+        let (_, prev_end) = blocks.get(blocks.len().checked_sub(2)?)?;
+        let (last_start, _) = blocks.last()?;
+        let term = self.term.lock();
+        let start = Point::new(Line(prev_end.0 + 1), Column(0));
+        let end = Point::new(Line(last_start.0 - 1), term.grid().last_column());
+        if end.line < start.line {
+            return Some(String::new());
+        }
+        let text = term.bounds_to_string(start, end);
+        Some(text.trim_end().to_string())
+    }
+
+    /// Set the search pattern, which also highlights all visible matches. An empty pattern
+    /// clears the search.
+    pub fn set_search(&mut self, value: &str) {
+        if self.search_value == value {
+            return;
+        }
+        self.search_value = value.to_string();
+        self.search_regex_opt = None;
+        self.term.lock().selection = None;
+        self.needs_update = true;
+        if !value.is_empty() {
+            match RegexSearch::new(value) {
+                Ok(search_regex) => {
+                    self.search_regex_opt = Some(search_regex);
+                }
+                Err(err) => {
+                    log::warn!("failed to parse regex {:?}: {}", value, err);
+                }
+            }
+        }
+    }
+
     pub fn search(&mut self, value: &str, forwards: bool) {
+        self.set_search(value);
+
         //TODO: set max lines, run in thread?
         {
             let mut term = self.term.lock();
 
-            if self.search_value != value {
-                match RegexSearch::new(value) {
-                    Ok(search_regex) => {
-                        self.search_regex_opt = Some(search_regex);
-                        self.search_value = value.to_string();
-                        term.selection = None;
-                    }
-                    Err(err) => {
-                        log::warn!("failed to parse regex {:?}: {}", value, err);
-                        return;
-                    }
-                }
-            }
-
             let Some(search_regex) = &mut self.search_regex_opt else {
                 return;
             };
@@ -493,25 +1971,207 @@ impl Terminal {
                     Selection::new(SelectionType::Simple, *search_match.start(), Side::Left);
                 selection.update(*search_match.end(), Side::Right);
                 term.selection = Some(selection);
+
+                // Continue copy mode from the match
+                if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point = *search_match.start();
+                }
             }
         }
 
         self.update();
     }
 
-    pub fn select_all(&mut self) {
+    /// Label matches of the hint patterns on the visible screen
+    pub fn start_hints(&mut self, hints: &[Hint]) {
+        let mut matches: Vec<(Match, HintAction)> = Vec::new();
         {
-            let mut term = self.term.lock();
+            let term = self.term.lock();
             let grid = term.grid();
-            let start = Point::new(Line(-(grid.history_size() as i32)), Column(0));
-            let mut end_line = grid.bottommost_line();
-            while end_line.0 > 0 {
-                if !grid[end_line].is_clear() {
-                    break;
+            let display_offset = grid.display_offset() as i32;
+            let start = Point::new(Line(-display_offset), Column(0));
+            let end = Point::new(
+                Line(grid.screen_lines() as i32 - 1 - display_offset),
+                grid.last_column(),
+            );
+            for hint in hints {
+                let mut regex = match RegexSearch::new(&hint.regex) {
+                    Ok(regex) => regex,
+                    Err(err) => {
+                        log::warn!("failed to parse hint regex {:?}: {}", hint.regex, err);
+                        continue;
+                    }
+                };
+                for hint_match in RegexIter::new(start, end, Direction::Right, &term, &mut regex) {
+                    let overlaps = matches.iter().any(|(other, _)| {
+                        hint_match.start() <= other.end() && other.start() <= hint_match.end()
+                    });
+                    if !overlaps {
+                        matches.push((hint_match, hint.action));
+                    }
                 }
-                end_line.0 -= 1;
             }
-            let end = Point::new(end_line, Column(grid.columns() - 1));
+            matches.sort_by_key(|(hint_match, _)| *hint_match.start());
+
+            let labels = hint_labels(matches.len());
+            self.hints = matches
+                .into_iter()
+                .zip(labels)
+                .map(|((range, action), label)| {
+                    let mut text = term.bounds_to_string(*range.start(), *range.end());
+                    // Drop trailing punctuation, like for clicked URLs
+                    while text.ends_with(|c| ".,:;!?'\"".contains(c)) {
+                        text.pop();
+                    }
+                    HintMatch {
+                        action,
+                        label,
+                        range,
+                        text,
+                    }
+                })
+                .collect();
+        }
+        self.hints_input.clear();
+        self.needs_update = true;
+    }
+
+    /// Whether hints are shown, which takes over keyboard input
+    pub fn hints_active(&self) -> bool {
+        !self.hints.is_empty()
+    }
+
+    pub fn cancel_hints(&mut self) {
+        self.hints.clear();
+        self.hints_input.clear();
+        self.needs_update = true;
+    }
+
+    /// Type a character of a hint label, or remove one with None. Returns the hint once its
+    /// whole label is typed, and cancels hints if no label starts with the input.
+    pub fn hint_input(&mut self, c_opt: Option<char>) -> Option<(HintAction, String)> {
+        match c_opt {
+            Some(c) => self.hints_input.push(c),
+            None => {
+                self.hints_input.pop();
+            }
+        }
+        self.needs_update = true;
+
+        if let Some(hint) = self
+            .hints
+            .iter()
+            .find(|hint| hint.label == self.hints_input)
+        {
+            let result = (hint.action, hint.text.clone());
+            self.cancel_hints();
+            return Some(result);
+        }
+        if !self
+            .hints
+            .iter()
+            .any(|hint| hint.label.starts_with(&self.hints_input))
+        {
+            self.cancel_hints();
+        }
+        None
+    }
+
+    /// Whether copy mode is active, where the keyboard moves a separate cursor to select text
+    pub fn copy_mode(&self) -> bool {
+        self.term.lock().mode().contains(TermMode::VI)
+    }
+
+    pub fn toggle_copy_mode(&mut self) {
+        {
+            let mut term = self.term.lock();
+            term.toggle_vi_mode();
+            if !term.mode().contains(TermMode::VI) {
+                term.selection = None;
+                term.scroll_display(TerminalScroll::Bottom);
+            }
+        }
+        self.needs_update = true;
+    }
+
+    pub fn copy_mode_motion(&mut self, motion: ViMotion) {
+        self.term.lock().vi_motion(motion);
+        self.needs_update = true;
+    }
+
+    /// Move the copy mode cursor to the first or last line of scrollback
+    pub fn copy_mode_goto(&mut self, top: bool) {
+        {
+            let mut term = self.term.lock();
+            let line = if top {
+                term.topmost_line()
+            } else {
+                term.bottommost_line()
+            };
+            term.vi_goto_point(Point::new(line, Column(0)));
+        }
+        self.needs_update = true;
+    }
+
+    /// Scroll the copy mode cursor by a number of lines, positive being up
+    pub fn copy_mode_scroll(&mut self, lines: i32) {
+        {
+            let mut term = self.term.lock();
+            term.scroll_display(TerminalScroll::Delta(lines));
+            let point = term.vi_mode_cursor.scroll(&*term, lines).point;
+            term.vi_goto_point(point);
+        }
+        self.needs_update = true;
+    }
+
+    /// Start selecting from the copy mode cursor, or stop if a selection of this type is active
+    pub fn copy_mode_select(&mut self, ty: SelectionType) {
+        {
+            let mut term = self.term.lock();
+            if term
+                .selection
+                .as_ref()
+                .is_some_and(|selection| selection.ty == ty)
+            {
+                term.selection = None;
+            } else {
+                let mut selection = Selection::new(ty, term.vi_mode_cursor.point, Side::Left);
+                selection.include_all();
+                term.selection = Some(selection);
+            }
+        }
+        self.needs_update = true;
+    }
+
+    /// Move the copy mode cursor to the next or previous match of the find bar search
+    pub fn copy_mode_search(&mut self, forwards: bool) {
+        {
+            let mut term = self.term.lock();
+            let Some(search_regex) = &mut self.search_regex_opt else {
+                return;
+            };
+            let point = term.vi_mode_cursor.point;
+            let (origin, direction) = if forwards {
+                (point.add(&*term, Boundary::None, 1), Direction::Right)
+            } else {
+                (point.sub(&*term, Boundary::None, 1), Direction::Left)
+            };
+            if let Some(search_match) =
+                term.search_next(search_regex, origin, direction, Side::Left, None)
+            {
+                term.vi_goto_point(*search_match.start());
+            }
+        }
+        self.needs_update = true;
+    }
+
+    /// Select from the first line of scrollback through the last non-empty line, regardless of
+    /// the current scroll position. The alternate screen has no scrollback, so only the visible
+    /// screen is selected there.
+    pub fn select_all(&mut self) {
+        {
+            let mut term = self.term.lock();
+            let (start, end) = history_bounds(&*term);
             let mut selection = Selection::new(SelectionType::Lines, start, Side::Left);
             selection.update(end, Side::Right);
             term.selection = Some(selection);
@@ -519,6 +2179,194 @@ impl Terminal {
         self.update();
     }
 
+    /// Get the selected text. Rows of a block selection are joined with newlines and have their
+    /// trailing whitespace trimmed.
+    pub fn selection_text(&self) -> Option<String> {
+        let term = self.term.lock();
+        let selection = term.selection.clone()?;
+        selection_text(&term, &selection)
+    }
+
+    /// Get the selected text like [`Self::selection_text`], but build it on another thread, as
+    /// a selection spanning a long scrollback can take a while to convert. The terminal stays
+    /// locked until the text is built, so this tab reads no new output meanwhile.
+    pub fn selection_text_task(&self) -> impl Future<Output = Option<String>> + Send + 'static {
+        let term = self.term.clone();
+        let selection_opt = term.lock().selection.clone();
+        let (tx, rx) = oneshot::channel();
+        if let Some(selection) = selection_opt {
+            thread::spawn(move || {
+                let _ = tx.send(selection_text(&term.lock(), &selection));
+            });
+        }
+        async move { rx.await.ok().flatten() }
+    }
+
+    /// Text of the scrollback and the screen, like [`Self::select_all`] would select
+    pub fn history_text(&self) -> String {
+        // Archived lines come first, as they are older than the scrollback
+        let mut text = self.archived_text().unwrap_or_else(|err| {
+            log::error!("failed to read scrollback archive: {}", err);
+            String::new()
+        });
+        let term = self.term.lock();
+        let (start, end) = history_bounds(&*term);
+        text.push_str(&term.bounds_to_string(start, end));
+        text
+    }
+
+    /// The scrollback and the screen as an HTML document that keeps colors and text styles
+    pub fn history_html(&self) -> String {
+        // Archived lines lead like in history_text, in the default colors as only their text
+        // was stored
+        let archived = self.archived_text().unwrap_or_else(|err| {
+            log::error!("failed to read scrollback archive: {}", err);
+            String::new()
+        });
+        let term = self.term.lock();
+        let (start, end) = history_bounds(&*term);
+        let mut html = self.cells_html(&term, start, end, false);
+        if !archived.is_empty() {
+            let mut archived_html = String::with_capacity(archived.len());
+            for c in archived.chars() {
+                push_html_escaped(&mut archived_html, c);
+            }
+            // Inside the opening pre tag, so both parts share its colors
+            let pre_end = html.find('>').map_or(0, |i| i + 1);
+            html.insert_str(pre_end, &archived_html);
+        }
+        format!(
+            concat!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n",
+                "<body>\n{}\n</body>\n</html>\n"
+            ),
+            html
+        )
+    }
+
+    // Cells from start to end as preformatted HTML. Block selections take the same columns from
+    // every line.
+    fn cells_html(
+        &self,
+        term: &Term<EventProxy>,
+        start: Point,
+        end: Point,
+        is_block: bool,
+    ) -> String {
+        let default_fg = convert_color(&self.colors, Color::Named(NamedColor::Foreground));
+        let default_bg = convert_color(&self.colors, Color::Named(NamedColor::Background));
+        let mut html = format!(
+            "<pre style=\"color:{};background-color:{}\">",
+            html_color(default_fg),
+            html_color(default_bg)
+        );
+        let grid = term.grid();
+        for line in start.line.0..=end.line.0 {
+            let row = &grid[Line(line)];
+            let first = if is_block || line == start.line.0 {
+                start.column.0
+            } else {
+                0
+            };
+            let last = if is_block || line == end.line.0 {
+                end.column.0 + 1
+            } else {
+                grid.columns()
+            };
+            // Trailing blank cells are left out
+            let last = last.min(row.line_length().0);
+
+            let mut span_style = String::new();
+            for column in first..last {
+                let cell = &row[Column(column)];
+                if cell
+                    .flags
+                    .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    continue;
+                }
+
+                let (fg, bg) = cell_colors(
+                    cell,
+                    &self.colors,
+                    self.use_bright_bold,
+                    self.minimum_contrast,
+                );
+                let mut style = String::new();
+                if fg != default_fg {
+                    style.push_str(&format!("color:{};", html_color(fg)));
+                }
+                if bg != default_bg {
+                    style.push_str(&format!("background-color:{};", html_color(bg)));
+                }
+                if cell.flags.contains(Flags::BOLD) {
+                    style.push_str("font-weight:bold;");
+                }
+                if cell.flags.contains(Flags::ITALIC) {
+                    style.push_str("font-style:italic;");
+                }
+                match (
+                    cell.flags.intersects(Flags::ALL_UNDERLINES),
+                    cell.flags.contains(Flags::STRIKEOUT),
+                ) {
+                    (true, true) => style.push_str("text-decoration:underline line-through;"),
+                    (true, false) => style.push_str("text-decoration:underline;"),
+                    (false, true) => style.push_str("text-decoration:line-through;"),
+                    (false, false) => {}
+                }
+
+                if style != span_style {
+                    if !span_style.is_empty() {
+                        html.push_str("</span>");
+                    }
+                    if !style.is_empty() {
+                        html.push_str(&format!("<span style=\"{}\">", style));
+                    }
+                    span_style = style;
+                }
+                push_html_escaped(&mut html, cell.c);
+                for c in cell.zerowidth().into_iter().flatten() {
+                    push_html_escaped(&mut html, *c);
+                }
+            }
+            if !span_style.is_empty() {
+                html.push_str("</span>");
+            }
+
+            let wrapped = row[Column(grid.columns() - 1)]
+                .flags
+                .contains(Flags::WRAPLINE);
+            if line != end.line.0 && (is_block || !wrapped) {
+                html.push('\n');
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+
+    /// Get the selection as preformatted HTML that keeps colors and text styles
+    pub fn selection_html(&self) -> Option<String> {
+        let term = self.term.lock();
+        let range = term.selection.as_ref()?.to_range(&term)?;
+        Some(self.cells_html(&term, range.start, range.end, range.is_block))
+    }
+
+    /// Update terminal options, shrinking or growing the scrollback of an existing terminal
+    pub fn set_term_config(&mut self, config: &Config) {
+        if self.scrolling_history != config.scrolling_history
+            || self.default_cursor_style != config.default_cursor_style
+        {
+            self.scrolling_history = config.scrolling_history;
+            self.default_cursor_style = config.default_cursor_style;
+            self.term.lock().set_options(config.clone());
+            self.needs_update = true;
+        }
+        if self.term.lock().semantic_escape_chars() != config.semantic_escape_chars {
+            self.file_ref_regex = file_ref_regex(&config.semantic_escape_chars);
+            self.term.lock().set_options(config.clone());
+        }
+    }
+
     pub fn set_config(
         &mut self,
         config: &AppConfig,
@@ -548,11 +2396,110 @@ impl Terminal {
             update_cell_size = true;
         }
 
+        self.alternate_scroll = config.alternate_scroll;
+
+        match self
+            .profile_id_opt
+            .and_then(|profile_id| config.profiles.get(&profile_id))
+            .filter(|profile| !profile.background_image.is_empty())
+        {
+            Some(profile) => {
+                let dim = f32::from(profile.background_image_dim.min(100)) / 100.0;
+                match &mut self.background_image_opt {
+                    Some(background_image) if background_image.path == profile.background_image => {
+                        background_image.scale = profile.background_image_scale;
+                        background_image.dim = dim;
+                    }
+                    _ => {
+                        self.background_image_opt = Some(BackgroundImage {
+                            path: profile.background_image.clone(),
+                            handle: image::Handle::from_path(&profile.background_image),
+                            scale: profile.background_image_scale,
+                            dim,
+                        });
+                    }
+                }
+            }
+            None => self.background_image_opt = None,
+        }
+
+        if self.triggers != config.triggers {
+            self.shell_state
+                .lock()
+                .unwrap()
+                .set_triggers(&config.triggers);
+            self.triggers = config.triggers.clone();
+        }
+
+        // Highlight triggers are drawn after the highlights of the profile
+        let mut highlights = self
+            .profile_id_opt
+            .and_then(|profile_id| config.profiles.get(&profile_id))
+            .map_or_else(Vec::new, |profile| profile.highlights.clone());
+        highlights.extend(
+            config
+                .triggers
+                .iter()
+                .filter_map(|trigger| match trigger.action {
+                    TriggerAction::Highlight(color) => Some(Highlight {
+                        regex: trigger.regex.clone(),
+                        color,
+                        line: false,
+                    }),
+                    _ => None,
+                }),
+        );
+        if self.highlights != highlights {
+            self.highlight_regexes = highlights
+                .iter()
+                .filter_map(|highlight| match RegexSearch::new(&highlight.regex) {
+                    Ok(regex) => Some((regex, highlight.clone())),
+                    Err(err) => {
+                        log::warn!(
+                            "failed to parse highlight regex {:?}: {}",
+                            highlight.regex,
+                            err
+                        );
+                        None
+                    }
+                })
+                .collect();
+            self.highlights = highlights;
+            update = true;
+        }
+        let cursor_color_opt = config.cursor_color(self.profile_id_opt).map(hex_to_rgb);
+        let cursor_text_color_opt = config
+            .cursor_text_color(self.profile_id_opt)
+            .map(hex_to_rgb);
+        if self.cursor_color_opt != cursor_color_opt
+            || self.cursor_text_color_opt != cursor_text_color_opt
+        {
+            self.cursor_color_opt = cursor_color_opt;
+            self.cursor_text_color_opt = cursor_text_color_opt;
+            update = true;
+        }
+        self.paste_filter = config.paste_filter;
+        self.paste_lone_cr_to_lf = config.paste_lone_cr_to_lf;
+        self.scroll_on_keypress = config.scroll_on_keypress;
+        self.scroll_on_output = config.scroll_on_output;
+        self.scrollback_archive = config.scrollback_archive;
+
         if self.use_bright_bold != config.use_bright_bold {
             self.use_bright_bold = config.use_bright_bold;
             update_cell_size = true;
         }
 
+        if self.font_ligatures != config.font_ligatures {
+            self.font_ligatures = config.font_ligatures;
+            update = true;
+        }
+
+        let minimum_contrast = f32::from(config.minimum_contrast_mul_10) / 10.0;
+        if self.minimum_contrast != minimum_contrast {
+            self.minimum_contrast = minimum_contrast;
+            update = true;
+        }
+
         let metrics = config.metrics(zoom_adj);
         if metrics != self.buffer.metrics() {
             {
@@ -586,6 +2533,15 @@ impl Terminal {
         }
     }
 
+    /// Show different colors until the next call to set_config, used to preview color schemes
+    pub fn set_colors(&mut self, colors: &Colors, config: &AppConfig) {
+        for i in 0..color::COUNT {
+            self.colors[i] = colors[i];
+        }
+        self.update_colors(config);
+        self.update();
+    }
+
     pub fn update_colors(&mut self, config: &AppConfig) {
         self.metadata_set.clear();
         let default_bg = convert_color(&self.colors, Color::Named(NamedColor::Background));
@@ -603,6 +2559,9 @@ impl Terminal {
     }
 
     pub fn update_cell_size(&mut self) {
+        // Glyphs are measured again with the new font
+        self.glyph_scales.clear();
+
         let default_attrs = self.default_attrs;
         let (cell_width, cell_height) = {
             let mut font_system = font_system().write().unwrap();
@@ -630,38 +2589,179 @@ impl Terminal {
         self.update();
     }
 
+    /// Rebuild the text of all visible lines
     pub fn update(&mut self) -> bool {
+        self.update_lines(true)
+    }
+
+    /// Rebuild the text of the lines damaged since the last update
+    pub fn update_damage(&mut self) -> bool {
+        self.update_lines(false)
+    }
+
+    fn update_lines(&mut self, full: bool) -> bool {
         // LEFT‑TO‑RIGHT ISOLATE character.
         // This will be added to the beginning of lines to force the shaper to treat detected RTL
         // lines as LTR. RTL text would still be rendered correctly. But this fixes the wrong
         // behavior of it being aligned to the right.
         const LRI: char = '\u{2066}';
+        // ZERO WIDTH NON-JOINER.
+        // Added between cells when ligatures are disabled, which keeps the shaper from joining
+        // the characters of neighboring cells into one glyph.
+        const ZWNJ: char = '\u{200C}';
 
         let instant = Instant::now();
 
-        // Only keep default
-        self.metadata_set.truncate(1);
+        // Apply images sent through the PTY reader, dropping the oldest when over the memory limit
+        for event in self.shell_state.lock().unwrap().take_image_events() {
+            match event {
+                ImageEvent::Add(id, image) => {
+                    self.images.insert(id, image);
+                }
+                ImageEvent::DeleteKitty(kitty_id_opt) => {
+                    self.images
+                        .retain(|_, image| match (image.kitty_id_opt, kitty_id_opt) {
+                            (Some(image_kitty_id), Some(kitty_id)) => image_kitty_id != kitty_id,
+                            (Some(_), None) => false,
+                            (None, _) => true,
+                        });
+                }
+            }
+        }
+        let mut image_bytes: usize = self
+            .images
+            .values()
+            .map(|image| image.width as usize * image.height as usize * 4)
+            .sum();
+        while image_bytes > IMAGE_CACHE_BYTES {
+            let Some((_, image)) = self.images.pop_first() else {
+                break;
+            };
+            image_bytes -= image.width as usize * image.height as usize * 4;
+        }
+        self.image_placements.clear();
+        self.update_highlights();
 
         //TODO: is redraw needed after all events?
         //TODO: use LineDamageBounds
         {
             let buffer = Arc::make_mut(&mut self.buffer);
+            let metrics = buffer.metrics();
 
             let mut line_i = 0;
+            let mut last_line_damaged = true;
             let mut last_point = None;
             let mut text = String::from(LRI);
             let mut attrs_list = AttrsList::new(self.default_attrs);
             {
                 let mut term = self.term.lock();
-                //TODO: use damage?
-                match term.damage() {
-                    TermDamage::Full => {}
-                    TermDamage::Partial(_damage_lines) => {}
-                }
+                let damaged_lines_opt = match term.damage() {
+                    TermDamage::Full => None,
+                    TermDamage::Partial(damage_lines) => Some(
+                        damage_lines
+                            .filter(|bounds| bounds.is_damaged())
+                            .map(|bounds| bounds.line)
+                            .collect::<HashSet<_>>(),
+                    ),
+                };
                 term.reset_damage();
+                // Selections, search matches, hints, highlights, hovered links and the copy mode
+                // cursor are not part of the damage, and neither is the blink phase, so any of
+                // them needs all lines to be rebuilt. Toggling a mark requests a full update.
+                let blink_phase = (self.cursor_blink_hidden, self.text_blink_hidden);
+                let damaged_lines_opt = damaged_lines_opt.filter(|_| {
+                    !full
+                        && term.selection.is_none()
+                        && self.search_regex_opt.is_none()
+                        && self.hints.is_empty()
+                        && self.highlight_regexes.is_empty()
+                        && self.hovered_url_opt.is_none()
+                        && !term.mode().contains(TermMode::VI)
+                        && blink_phase == self.damage_blink_phase
+                });
+                self.damage_blink_phase = blink_phase;
+                if damaged_lines_opt.is_none() {
+                    // Only keep default
+                    self.metadata_set.truncate(1);
+                }
+                let line_damaged = |line_i: usize| {
+                    damaged_lines_opt
+                        .as_ref()
+                        .map_or(true, |lines| lines.contains(&line_i))
+                };
 
                 let grid = term.grid();
+                // Cursor color set by a program with OSC 12, then the configured one. When neither
+                // is set the cursor inverts the colors of the cell under it, which keeps it
+                // visible against any background.
+                let cursor_bg_opt = term.colors()[NamedColor::Cursor]
+                    .or(self.cursor_color_opt)
+                    .map(rgb_to_color);
+                let cursor_fg_opt = self.cursor_text_color_opt.map(rgb_to_color);
+                // Copy mode shows its own cursor as a block instead of the terminal cursor
+                let copy_mode = term.mode().contains(TermMode::VI);
+                let (cursor_point, cursor_shape) = if copy_mode {
+                    (term.vi_mode_cursor.point, CursorShape::Block)
+                } else if self.cursor_blink_hidden {
+                    (grid.cursor.point, CursorShape::Hidden)
+                } else {
+                    (grid.cursor.point, term.cursor_style().shape)
+                };
+                // Find matches in the visible lines
+                let search_matches: Vec<Match> = match &mut self.search_regex_opt {
+                    Some(search_regex) => {
+                        let display_offset = grid.display_offset() as i32;
+                        let start = Point::new(Line(-display_offset), Column(0));
+                        let end = Point::new(
+                            Line(grid.screen_lines() as i32 - 1 - display_offset),
+                            grid.last_column(),
+                        );
+                        RegexIter::new(start, end, Direction::Right, &term, search_regex).collect()
+                    }
+                    None => Vec::new(),
+                };
+                // Resolve the selection once, as this is expensive for large selections
+                let selection_range = term
+                    .selection
+                    .as_ref()
+                    .and_then(|selection| selection.to_range(&term));
+                // Hints with labels starting with the typed input, and where to draw the rest
+                let hints: Vec<&HintMatch> = self
+                    .hints
+                    .iter()
+                    .filter(|hint| hint.label.starts_with(&self.hints_input))
+                    .collect();
+                let mut hint_labels = HashMap::new();
+                for hint in hints.iter() {
+                    for (i, c) in hint.label[self.hints_input.len()..].chars().enumerate() {
+                        let point = hint.range.start().add(&*term, Boundary::Grid, i);
+                        hint_labels.insert(point, c);
+                    }
+                }
+                let display_offset = grid.display_offset() as i32;
+                let mut placed_images = HashSet::new();
+                let mut text_blinking = false;
                 for indexed in grid.display_iter() {
+                    // The first visible cell of an image gives its position, as rows above it
+                    // may have scrolled out of view
+                    if let Some((id, row)) = indexed
+                        .cell
+                        .hyperlink()
+                        .and_then(|hyperlink| parse_image_uri(hyperlink.uri()))
+                    {
+                        if placed_images.insert(id) {
+                            if let Some(image) = self.images.get(&id) {
+                                self.image_placements.push(ImagePlacement {
+                                    handle: image.handle.clone(),
+                                    width: image.width,
+                                    height: image.height,
+                                    column: indexed.point.column.0,
+                                    line: indexed.point.line.0 + display_offset - row as i32,
+                                });
+                            }
+                        }
+                    }
+
                     if indexed.point.line != last_point.unwrap_or(indexed.point).line {
                         while line_i >= buffer.lines.len() {
                             buffer.lines.push(BufferLine::new(
@@ -672,7 +2772,9 @@ impl Terminal {
                             buffer.set_redraw(true);
                         }
 
-                        if buffer.lines[line_i].set_text(text.clone(), attrs_list.clone()) {
+                        if line_damaged(line_i)
+                            && buffer.lines[line_i].set_text(text.clone(), attrs_list.clone())
+                        {
                             buffer.set_redraw(true);
                         }
                         line_i += 1;
@@ -683,71 +2785,111 @@ impl Terminal {
                     }
                     //TODO: use indexed.point.column?
 
+                    if !line_damaged(line_i) {
+                        last_point = Some(indexed.point);
+                        continue;
+                    }
+
                     //TODO: skip leading spacer?
                     if indexed.cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
                         // Skip wide spacers (cells after wide characters)
                         continue;
                     }
 
+                    if !self.font_ligatures
+                        && indexed.cell.c != ' '
+                        && text.ends_with(|c| c != ' ' && c != LRI)
+                    {
+                        text.push(ZWNJ);
+                    }
+
                     let start = text.len();
-                    // Tab skip/stop is handled by alacritty_terminal
-                    text.push(match indexed.cell.c {
-                        '\t' => ' ',
-                        c => c,
-                    });
-                    if let Some(zerowidth) = indexed.cell.zerowidth() {
-                        for &c in zerowidth {
-                            text.push(c);
+                    let hint_label_opt = hint_labels.get(&indexed.point).copied();
+                    let mut box_drawing_opt = None;
+                    if let Some(c) = hint_label_opt {
+                        text.push(c);
+                    } else {
+                        // Tab skip/stop is handled by alacritty_terminal
+                        text.push(match indexed.cell.c {
+                            '\t' => ' ',
+                            // Drawn by terminal_box so that lines connect between cells
+                            c if box_drawing::is_drawn(c) => {
+                                box_drawing_opt = Some(c);
+                                ' '
+                            }
+                            c => c,
+                        });
+                        if let Some(zerowidth) = indexed.cell.zerowidth() {
+                            for &c in zerowidth {
+                                text.push(c);
+                            }
                         }
                     }
                     let end = text.len();
 
                     let mut attrs = self.default_attrs;
 
-                    let cell_fg = if indexed.cell.flags.contains(Flags::DIM) {
-                        as_dim(indexed.cell.fg)
-                    } else if self.use_bright_bold && indexed.cell.flags.contains(Flags::BOLD) {
-                        as_bright(indexed.cell.fg)
-                    } else {
-                        indexed.cell.fg
-                    };
-
-                    let (mut fg, mut bg) = if indexed.cell.flags.contains(Flags::INVERSE) {
-                        (
-                            convert_color(&self.colors, indexed.cell.bg),
-                            convert_color(&self.colors, cell_fg),
-                        )
-                    } else {
-                        (
-                            convert_color(&self.colors, cell_fg),
-                            convert_color(&self.colors, indexed.cell.bg),
-                        )
-                    };
+                    let (mut fg, mut bg) = cell_colors(
+                        indexed.cell,
+                        &self.colors,
+                        self.use_bright_bold,
+                        self.minimum_contrast,
+                    );
 
-                    if indexed.cell.flags.contains(Flags::HIDDEN) {
-                        fg = bg;
+                    if indexed
+                        .cell
+                        .hyperlink()
+                        .is_some_and(|hyperlink| hyperlink.uri() == BLINK_URI)
+                    {
+                        text_blinking = true;
+                        if self.text_blink_hidden {
+                            fg = bg;
+                        }
                     }
 
-                    // Change color if cursor
-                    if indexed.point == grid.cursor.point {
-                        //TODO: better handling of cursor
-                        if term.mode().contains(TermMode::SHOW_CURSOR) {
-                            mem::swap(&mut fg, &mut bg);
+                    // Change color if block cursor, other shapes are drawn over the cell
+                    let mut cursor_opt = None;
+                    if indexed.point == cursor_point {
+                        if copy_mode || term.mode().contains(TermMode::SHOW_CURSOR) {
+                            match cursor_shape {
+                                CursorShape::Block => match cursor_bg_opt {
+                                    Some(cursor_bg) => {
+                                        fg = cursor_fg_opt.unwrap_or(bg);
+                                        bg = cursor_bg;
+                                    }
+                                    None => mem::swap(&mut fg, &mut bg),
+                                },
+                                CursorShape::Hidden => {}
+                                shape => cursor_opt = Some((shape, cursor_bg_opt.unwrap_or(fg))),
+                            }
                         } else {
                             fg = bg;
                         }
                     }
 
+                    // Change color if search match
+                    if search_matches
+                        .iter()
+                        .any(|search_match| search_match.contains(&indexed.point))
+                    {
+                        fg = convert_color(&self.colors, Color::Named(NamedColor::Black));
+                        bg = convert_color(&self.colors, Color::Named(NamedColor::Yellow));
+                    }
+
                     // Change color if selected
-                    if let Some(selection) = &term.selection {
-                        if let Some(range) = selection.to_range(&term) {
-                            if range.contains(indexed.point) {
-                                //TODO: better handling of selection
-                                mem::swap(&mut fg, &mut bg);
-                            }
+                    if let Some(range) = &selection_range {
+                        if range.contains(indexed.point) {
+                            //TODO: better handling of selection
+                            mem::swap(&mut fg, &mut bg);
                         }
                     }
 
+                    // Change color if hint label
+                    if hint_label_opt.is_some() {
+                        fg = convert_color(&self.colors, Color::Named(NamedColor::Black));
+                        bg = convert_color(&self.colors, Color::Named(NamedColor::Yellow));
+                    }
+
                     // Convert foreground to linear
                     attrs = attrs.color(fg);
 
@@ -756,9 +2898,20 @@ impl Terminal {
                         .underline_color()
                         .map(|c| convert_color(&self.colors, c))
                         .unwrap_or(fg);
+                    let mut flags = indexed.cell.flags;
+                    if self
+                        .hovered_url_opt
+                        .as_ref()
+                        .is_some_and(|url_match| url_match.contains(&indexed.point))
+                        || hints.iter().any(|hint| hint.range.contains(&indexed.point))
+                    {
+                        flags.insert(Flags::UNDERLINE);
+                    }
                     let metadata = Metadata::new(bg, fg)
-                        .with_flags(indexed.cell.flags)
-                        .with_underline_color(underline_color);
+                        .with_flags(flags)
+                        .with_underline_color(underline_color)
+                        .with_cursor(cursor_opt)
+                        .with_box_drawing(box_drawing_opt.map(|c| (c, fg)));
                     let (meta_idx, _) = self.metadata_set.insert_full(metadata);
                     attrs = attrs.metadata(meta_idx);
 
@@ -773,12 +2926,21 @@ impl Terminal {
                         //TODO: automatically use fake italic
                         attrs = attrs.cache_key_flags(CacheKeyFlags::FAKE_ITALIC);
                     }
+                    if let Some(scale) = self.glyph_scales.get(&indexed.cell.c) {
+                        attrs = attrs
+                            .metrics(Metrics::new(metrics.font_size * scale, metrics.line_height));
+                    }
                     if attrs != attrs_list.defaults() {
                         attrs_list.add_span(start..end, attrs);
                     }
 
                     last_point = Some(indexed.point);
                 }
+                // Blinking text may still be on lines that were not rebuilt
+                self.text_blinking =
+                    text_blinking || (damaged_lines_opt.is_some() && self.text_blinking);
+                // The last line is set below
+                last_line_damaged = line_damaged(line_i);
             }
 
             //TODO: do not repeat!
@@ -791,7 +2953,7 @@ impl Terminal {
                 buffer.set_redraw(true);
             }
 
-            if buffer.lines[line_i].set_text(text, attrs_list) {
+            if last_line_damaged && buffer.lines[line_i].set_text(text, attrs_list) {
                 buffer.set_redraw(true);
             }
             line_i += 1;
@@ -805,6 +2967,34 @@ impl Terminal {
             {
                 let mut font_system = font_system().write().unwrap();
                 buffer.shape_until_scroll(font_system.raw(), true);
+
+                // Glyphs from fallback fonts are often wider or narrower than the cells they
+                // cover, which would shift the rest of the line. Scale them to fit and shape again.
+                if find_glyph_scales(buffer, self.size.cell_width, &mut self.glyph_scales) {
+                    for line in buffer.lines.iter_mut() {
+                        let mut attrs_list = line.attrs_list().clone();
+                        let mut changed = false;
+                        for (i, c) in line.text().char_indices() {
+                            if let Some(scale) = self.glyph_scales.get(&c) {
+                                let attrs = AttrsOwned::new(attrs_list.get_span(i));
+                                attrs_list.add_span(
+                                    i..i + c.len_utf8(),
+                                    attrs.as_attrs().metrics(Metrics::new(
+                                        metrics.font_size * scale,
+                                        metrics.line_height,
+                                    )),
+                                );
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            line.set_attrs_list(attrs_list);
+                        }
+                    }
+                    buffer.set_redraw(true);
+                    buffer.shape_until_scroll(font_system.raw(), true);
+                }
+
                 font_system.raw().shape_run_cache.trim(1024);
             }
         }
@@ -828,20 +3018,32 @@ impl Terminal {
     ) {
         let term_lock = self.term.lock();
         let mode = term_lock.mode();
+        // Click tracking (1000) ignores motion, drag tracking (1002) reports it while a button is
+        // held and any event tracking (1003) reports all motion
+        if let cosmic::iced::Event::Mouse(cosmic::iced::mouse::Event::CursorMoved { .. }) = event {
+            if !mode.intersects(TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION) {
+                return;
+            }
+        }
+        let all_motion = mode.contains(TermMode::MOUSE_MOTION);
         #[allow(clippy::collapsible_else_if)]
         if mode.contains(TermMode::SGR_MOUSE) {
-            if let Some(code) = self.mouse_reporter.sgr_mouse_code(event, modifiers, x, y) {
-                self.input_no_scroll(code)
+            if let Some(code) = self
+                .mouse_reporter
+                .sgr_mouse_code(event, modifiers, all_motion, x, y)
+            {
+                self.input_event(code)
             }
         } else {
             if let Some(code) = self.mouse_reporter.normal_mouse_code(
                 event,
                 modifiers,
                 mode.contains(TermMode::UTF8_MOUSE),
+                all_motion,
                 x,
                 y,
             ) {
-                self.input_no_scroll(code)
+                self.input_event(code)
             }
         }
     }
@@ -865,11 +3067,15 @@ impl Terminal {
                 y,
             );
         } else {
-            MouseReporter::report_mouse_wheel_as_arrows(
+            MouseReporter::report_normal_mouse_wheel_scroll(
                 self,
                 self.size().cell_width,
                 self.size().cell_height,
                 delta,
+                modifiers,
+                mode.contains(TermMode::UTF8_MOUSE),
+                x,
+                y,
             );
         }
     }
@@ -883,3 +3089,90 @@ impl Drop for Terminal {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bold_brightens_only_standard_colors() {
+        assert_eq!(
+            as_bright(Color::Named(NamedColor::Red)),
+            Color::Named(NamedColor::BrightRed)
+        );
+        // Set with CSI 38;5;1, which names an exact entry of the 256 color palette
+        assert_eq!(as_bright(Color::Indexed(1)), Color::Indexed(1));
+        let rgb = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        assert_eq!(as_bright(rgb), rgb);
+    }
+
+    #[test]
+    fn filter_paste_none_keeps_controls() {
+        assert_eq!(
+            filter_paste("a\x1b[31mb\x07", PasteFilter::None, false),
+            "a\x1b[31mb\x07"
+        );
+    }
+
+    #[test]
+    fn filter_paste_strip_removes_controls() {
+        assert_eq!(
+            filter_paste("a\x1b[31mb\x07\tc\n", PasteFilter::Strip, false),
+            "a[31mb\tc\n"
+        );
+    }
+
+    #[test]
+    fn filter_paste_escape_uses_caret_notation() {
+        assert_eq!(
+            filter_paste("a\x1bb\x7fc\u{9b}", PasteFilter::Escape, false),
+            "a^[b^?c\\u{9b}"
+        );
+    }
+
+    #[test]
+    fn filter_paste_embedded_osc() {
+        let payload = "ls\x1b]0;pwned\x07\r";
+
+        let stripped = filter_paste(payload, PasteFilter::Strip, false);
+        assert_eq!(stripped, "ls]0;pwned");
+        let stripped_cr = filter_paste(payload, PasteFilter::Strip, true);
+        assert_eq!(stripped_cr, "ls]0;pwned\n");
+
+        let escaped = filter_paste(payload, PasteFilter::Escape, false);
+        assert_eq!(escaped, "ls^[]0;pwned^G^M");
+        let escaped_cr = filter_paste(payload, PasteFilter::Escape, true);
+        assert_eq!(escaped_cr, "ls^[]0;pwned^G\n");
+
+        for filtered in [stripped, stripped_cr, escaped, escaped_cr] {
+            assert!(!filtered.contains(&['\x1b', '\x07', '\r'][..]));
+        }
+
+        // Turning the filter off passes the sequence on unchanged
+        assert_eq!(filter_paste(payload, PasteFilter::None, false), payload);
+    }
+
+    #[test]
+    fn filter_paste_lone_cr() {
+        assert_eq!(
+            filter_paste("a\rb\r\nc", PasteFilter::Strip, true),
+            "a\nb\r\nc"
+        );
+        assert_eq!(filter_paste("a\rb", PasteFilter::Strip, false), "ab");
+        assert_eq!(filter_paste("a\rb", PasteFilter::None, false), "a\rb");
+    }
+
+    #[test]
+    fn filter_paste_twice_is_unchanged() {
+        let value = "a\x1b[31mb\x07\rc\r\nd\u{9b}";
+        for paste_filter in [PasteFilter::None, PasteFilter::Strip, PasteFilter::Escape] {
+            for lone_cr_to_lf in [false, true] {
+                let filtered = filter_paste(value, paste_filter, lone_cr_to_lf);
+                assert_eq!(
+                    filter_paste(&filtered, paste_filter, lone_cr_to_lf),
+                    filtered
+                );
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use alacritty_terminal::{
+    grid::Dimensions,
     index::{Column as TermColumn, Point as TermPoint, Side as TermSide},
     selection::{Selection, SelectionType},
     term::{cell::Flags, TermMode},
+    vi_mode::ViMotion,
+    vte::ansi::CursorShape,
 };
 use cosmic::widget::menu::key_bind::KeyBind;
 use cosmic::{
@@ -11,12 +14,15 @@ use cosmic::{
     iced::{
         advanced::graphics::text::Raw,
         event::{Event, Status},
-        keyboard::{Event as KeyEvent, Key, Modifiers},
+        keyboard::{Event as KeyEvent, Key, Location, Modifiers},
         mouse::{self, Button, Event as MouseEvent, ScrollDelta},
+        touch::{self, Event as TouchEvent},
+        window::{self, RedrawRequest},
         Color, Element, Length, Padding, Point, Rectangle, Size, Vector,
     },
     iced_core::{
         clipboard::Clipboard,
+        image,
         keyboard::key::Named,
         layout::{self, Layout},
         renderer::{self, Quad, Renderer as _},
@@ -41,7 +47,200 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::{key_bind::key_binds, terminal::Metadata, Action, Terminal, TerminalScroll};
+use crate::{
+    box_drawing,
+    config::{BackgroundImageScale, HintAction},
+    kitty_keyboard,
+    terminal::{FileRef, Metadata},
+    Action, Terminal, TerminalScroll,
+};
+
+// Lines scrolled per second for every line the mouse is dragged past the top or bottom edge
+const AUTOSCROLL_SPEED: f32 = 10.0;
+// Opacity of highlight colors, so the text over them stays readable
+const HIGHLIGHT_ALPHA: f32 = 0.35;
+// Fraction of the speed a touch scroll keeps after coasting for one second
+const MOMENTUM_DECAY: f32 = 0.05;
+// Speed in pixels per second below which a touch scroll stops coasting
+const MOMENTUM_MIN_VELOCITY: f32 = 20.0;
+
+/// A key typed into a terminal, kept unencoded so that broadcasting it encodes it for the mode
+/// of each terminal that receives it
+#[derive(Clone, Debug)]
+pub struct KeyInput {
+    pub key: Key,
+    pub location: Location,
+    pub modifiers: Modifiers,
+    pub text_opt: Option<String>,
+    pub release: bool,
+}
+
+impl KeyInput {
+    /// Bytes sent for this key to a terminal in the given mode, if it sends any
+    pub fn encode(&self, mode: TermMode) -> Option<Vec<u8>> {
+        // Programs that enabled the kitty keyboard protocol get keys in its encoding, keys
+        // that it leaves alone are handled by the legacy encoding below
+        let keyboard_mode = mode & TermMode::KITTY_KEYBOARD_PROTOCOL;
+        if !keyboard_mode.is_empty() {
+            let escape_code = kitty_keyboard::encode(
+                &self.key,
+                self.location,
+                self.modifiers,
+                self.text_opt.as_deref(),
+                self.release,
+                keyboard_mode,
+            );
+            if escape_code.is_some() {
+                return escape_code;
+            }
+        }
+        if self.release {
+            return None;
+        }
+
+        match self.key {
+            Key::Named(named) => {
+                encode_named(named, self.modifiers, mode.contains(TermMode::APP_CURSOR))
+            }
+            _ => encode_character(&self.key, self.text_opt.as_deref(), self.modifiers),
+        }
+    }
+}
+
+fn encode_named(named: Named, modifiers: Modifiers, is_app_cursor: bool) -> Option<Vec<u8>> {
+    let mod_no = calculate_modifier_number(modifiers);
+    let escape_code = match named {
+        Named::Insert => csi("2", "~", mod_no),
+        Named::Delete => csi("3", "~", mod_no),
+        Named::PageUp => csi("5", "~", mod_no),
+        Named::PageDown => csi("6", "~", mod_no),
+        Named::ArrowUp => {
+            if is_app_cursor {
+                ss3("A", mod_no)
+            } else {
+                csi("A", "", mod_no)
+            }
+        }
+        Named::ArrowDown => {
+            if is_app_cursor {
+                ss3("B", mod_no)
+            } else {
+                csi("B", "", mod_no)
+            }
+        }
+        Named::ArrowRight => {
+            if is_app_cursor {
+                ss3("C", mod_no)
+            } else {
+                csi("C", "", mod_no)
+            }
+        }
+        Named::ArrowLeft => {
+            if is_app_cursor {
+                ss3("D", mod_no)
+            } else {
+                csi("D", "", mod_no)
+            }
+        }
+        Named::End => {
+            if is_app_cursor {
+                ss3("F", mod_no)
+            } else {
+                csi("F", "", mod_no)
+            }
+        }
+        Named::Home => {
+            if is_app_cursor {
+                ss3("H", mod_no)
+            } else {
+                csi("H", "", mod_no)
+            }
+        }
+        Named::F1 => ss3("P", mod_no),
+        Named::F2 => ss3("Q", mod_no),
+        Named::F3 => ss3("R", mod_no),
+        Named::F4 => ss3("S", mod_no),
+        Named::F5 => csi("15", "~", mod_no),
+        Named::F6 => csi("17", "~", mod_no),
+        Named::F7 => csi("18", "~", mod_no),
+        Named::F8 => csi("19", "~", mod_no),
+        Named::F9 => csi("20", "~", mod_no),
+        Named::F10 => csi("21", "~", mod_no),
+        Named::F11 => csi("23", "~", mod_no),
+        Named::F12 => csi("24", "~", mod_no),
+        _ => None,
+    };
+    if escape_code.is_some() {
+        return escape_code;
+    }
+
+    //Special handle Enter, Escape, Backspace and Tab as described in
+    //https://sw.kovidgoyal.net/kitty/keyboard-protocol/#legacy-key-event-encoding
+    let alt_prefix = if modifiers.alt() { "\x1B" } else { "" };
+    let code = match named {
+        Named::Backspace => {
+            if modifiers.control() {
+                "\x08"
+            } else {
+                "\x7f"
+            }
+        }
+        Named::Enter => "\x0D",
+        Named::Escape => "\x1B",
+        Named::Space => " ",
+        Named::Tab => {
+            if modifiers.shift() {
+                "\x1b[Z"
+            } else {
+                "\x09"
+            }
+        }
+        _ => return None,
+    };
+    Some(format!("{alt_prefix}{code}").into_bytes())
+}
+
+fn encode_character(key: &Key, text_opt: Option<&str>, modifiers: Modifiers) -> Option<Vec<u8>> {
+    let character = text_opt.and_then(|c| c.chars().next()).unwrap_or_default();
+    match (
+        modifiers.logo(),
+        modifiers.control(),
+        modifiers.alt(),
+        modifiers.shift(),
+    ) {
+        (true, _, _, _) => {
+            // Ignore super
+            None
+        }
+        (false, true, true, _) => {
+            // Handle ctrl-alt for non-control characters
+            // and control characters 0-32
+            (!character.is_control() || (character as u32) < 32)
+                .then(|| format!("\x1B{character}").into_bytes())
+        }
+        (false, true, _, false) => {
+            // Handle ctrl for control characters (Ctrl-A to Ctrl-Z)
+            character
+                .is_control()
+                .then(|| character.to_string().into_bytes())
+        }
+        (false, true, _, true) => {
+            //This is normally Ctrl+Minus, but since that
+            //is taken by zoom, we send that code for
+            //Ctrl+Underline instead, like xterm and
+            //gnome-terminal
+            (*key == Key::Character("_".into())).then(|| b"\x1F".to_vec())
+        }
+        (false, false, true, _) => {
+            // Handle alt for non-control characters
+            (!character.is_control()).then(|| format!("\x1B{character}").into_bytes())
+        }
+        (false, false, false, _) => {
+            // Handle no modifiers for non-control characters
+            (!character.is_control()).then(|| character.to_string().into_bytes())
+        }
+    }
+}
 
 pub struct TerminalBox<'a, Message> {
     terminal: &'a Mutex<Terminal>,
@@ -51,18 +250,27 @@ pub struct TerminalBox<'a, Message> {
     click_timing: Duration,
     context_menu: Option<Point>,
     on_context_menu: Option<Box<dyn Fn(Option<Point>) -> Message + 'a>>,
+    on_copy_mode_yank: Option<Box<dyn Fn() -> Message + 'a>>,
+    on_find: Option<Box<dyn Fn() -> Message + 'a>>,
+    on_hint: Option<Box<dyn Fn(HintAction, String) -> Message + 'a>>,
+    on_input: Option<Box<dyn Fn(KeyInput) -> Message + 'a>>,
     on_mouse_enter: Option<Box<dyn Fn() -> Message + 'a>>,
     opacity: Option<f32>,
+    bell_flash: bool,
     mouse_inside_boundary: Option<bool>,
     on_middle_click: Option<Box<dyn Fn() -> Message + 'a>>,
-    key_binds: HashMap<KeyBind, Action>,
+    on_open_file_ref: Option<Box<dyn Fn(FileRef) -> Message + 'a>>,
+    on_open_url: Option<Box<dyn Fn(String) -> Message + 'a>>,
+    on_zoom_in: Option<Box<dyn Fn() -> Message + 'a>>,
+    on_zoom_out: Option<Box<dyn Fn() -> Message + 'a>>,
+    key_binds: &'a HashMap<KeyBind, Action>,
 }
 
 impl<'a, Message> TerminalBox<'a, Message>
 where
     Message: Clone,
 {
-    pub fn new(terminal: &'a Mutex<Terminal>) -> Self {
+    pub fn new(terminal: &'a Mutex<Terminal>, key_binds: &'a HashMap<KeyBind, Action>) -> Self {
         Self {
             terminal,
             id: None,
@@ -71,11 +279,20 @@ where
             click_timing: Duration::from_millis(500),
             context_menu: None,
             on_context_menu: None,
+            on_copy_mode_yank: None,
+            on_find: None,
+            on_hint: None,
+            on_input: None,
             on_mouse_enter: None,
             opacity: None,
+            bell_flash: false,
             mouse_inside_boundary: None,
             on_middle_click: None,
-            key_binds: key_binds(),
+            on_open_file_ref: None,
+            on_open_url: None,
+            on_zoom_in: None,
+            on_zoom_out: None,
+            key_binds,
         }
     }
 
@@ -99,6 +316,17 @@ where
         self
     }
 
+    // Padding around the grid, including the space that does not fit a whole cell
+    fn grid_padding(&self, terminal: &Terminal) -> Padding {
+        let (offset_x, offset_y) = terminal.grid_offset();
+        Padding {
+            top: self.padding.top + offset_y,
+            right: self.padding.right + offset_x,
+            bottom: self.padding.bottom + offset_y,
+            left: self.padding.left + offset_x,
+        }
+    }
+
     pub fn context_menu(mut self, position: Point) -> Self {
         self.context_menu = Some(position);
         self
@@ -112,6 +340,27 @@ where
         self
     }
 
+    pub fn on_copy_mode_yank(mut self, on_copy_mode_yank: impl Fn() -> Message + 'a) -> Self {
+        self.on_copy_mode_yank = Some(Box::new(on_copy_mode_yank));
+        self
+    }
+
+    pub fn on_find(mut self, on_find: impl Fn() -> Message + 'a) -> Self {
+        self.on_find = Some(Box::new(on_find));
+        self
+    }
+
+    pub fn on_hint(mut self, on_hint: impl Fn(HintAction, String) -> Message + 'a) -> Self {
+        self.on_hint = Some(Box::new(on_hint));
+        self
+    }
+
+    /// Called with the keys typed into the terminal, used to broadcast them to other terminals
+    pub fn on_input(mut self, on_input: impl Fn(KeyInput) -> Message + 'a) -> Self {
+        self.on_input = Some(Box::new(on_input));
+        self
+    }
+
     pub fn on_mouse_enter(mut self, on_mouse_enter: impl Fn() -> Message + 'a) -> Self {
         self.on_mouse_enter = Some(Box::new(on_mouse_enter));
         self
@@ -122,17 +371,62 @@ where
         self
     }
 
+    pub fn on_open_file_ref(mut self, on_open_file_ref: impl Fn(FileRef) -> Message + 'a) -> Self {
+        self.on_open_file_ref = Some(Box::new(on_open_file_ref));
+        self
+    }
+
+    pub fn on_open_url(mut self, on_open_url: impl Fn(String) -> Message + 'a) -> Self {
+        self.on_open_url = Some(Box::new(on_open_url));
+        self
+    }
+
+    pub fn on_zoom_in(mut self, on_zoom_in: impl Fn() -> Message + 'a) -> Self {
+        self.on_zoom_in = Some(Box::new(on_zoom_in));
+        self
+    }
+
+    pub fn on_zoom_out(mut self, on_zoom_out: impl Fn() -> Message + 'a) -> Self {
+        self.on_zoom_out = Some(Box::new(on_zoom_out));
+        self
+    }
+
+    // Send a key to the terminal, publishing it as well if anything listens for it. Returns
+    // true if the key sent anything to the terminal.
+    fn input(
+        &self,
+        terminal: &Terminal,
+        shell: &mut Shell<'_, Message>,
+        key_input: KeyInput,
+    ) -> bool {
+        let sent = terminal.input_key(&key_input);
+        if let Some(on_input) = &self.on_input {
+            if !terminal.read_only() {
+                shell.publish(on_input(key_input));
+            }
+        }
+        sent
+    }
+
+    pub fn bell_flash(mut self, bell_flash: bool) -> Self {
+        self.bell_flash = bell_flash;
+        self
+    }
+
     pub fn opacity(mut self, opacity: f32) -> Self {
         self.opacity = Some(opacity);
         self
     }
 }
 
-pub fn terminal_box<Message>(terminal: &Mutex<Terminal>) -> TerminalBox<'_, Message>
+pub fn terminal_box<'a, Message>(
+    terminal: &'a Mutex<Terminal>,
+    key_binds: &'a HashMap<KeyBind, Action>,
+) -> TerminalBox<'a, Message>
 where
     Message: Clone,
 {
-    TerminalBox::new(terminal)
+    TerminalBox::new(terminal, key_binds)
 }
 
 impl<'a, Message> Widget<Message, cosmic::Theme, Renderer> for TerminalBox<'a, Message>
@@ -167,6 +461,10 @@ where
         if terminal.needs_update {
             terminal.update();
             terminal.needs_update = false;
+            terminal.needs_damage_update = false;
+        } else if terminal.needs_damage_update {
+            terminal.update_damage();
+            terminal.needs_damage_update = false;
         }
 
         // Calculate layout lines
@@ -214,10 +512,27 @@ where
         if let Some(p) = cursor_position.position_in(layout.bounds()) {
             let terminal = self.terminal.lock().unwrap();
             let buffer_size = terminal.with_buffer(|buffer| buffer.size());
+            let padding = self.grid_padding(&terminal);
 
-            let x = p.x - self.padding.left;
-            let y = p.y - self.padding.top;
+            let x = p.x - padding.left;
+            let y = p.y - padding.top;
             if x >= 0.0 && x < buffer_size.0 && y >= 0.0 && y < buffer_size.1 {
+                if terminal.hovered_url().is_some() {
+                    return mouse::Interaction::Pointer;
+                }
+                // Holding Alt starts a block selection, and keeps it while dragging
+                let block_selection = match state.dragging {
+                    Some(Dragging::Buffer) => terminal
+                        .term
+                        .lock()
+                        .selection
+                        .as_ref()
+                        .is_some_and(|selection| selection.ty == SelectionType::Block),
+                    _ => state.modifiers.alt(),
+                };
+                if block_selection {
+                    return mouse::Interaction::Crosshair;
+                }
                 return mouse::Interaction::Text;
             }
         }
@@ -263,8 +578,16 @@ where
         if terminal.needs_update {
             terminal.update();
             terminal.needs_update = false;
+            terminal.needs_damage_update = false;
+        } else if terminal.needs_damage_update {
+            terminal.update_damage();
+            terminal.needs_damage_update = false;
         }
 
+        // The scrollbar stays at the edge of the view, the grid is centered in it
+        let padding = self.grid_padding(&terminal);
+        let grid_position = layout.position() + [padding.left, padding.top].into();
+
         // Render default background
         {
             let meta = &terminal.metadata_set[terminal.default_attrs().metadata];
@@ -286,6 +609,38 @@ where
                     },
                 ),
             );
+
+            // Render background image, dimmed by drawing the background color over it
+            if let Some(background_image) = &terminal.background_image_opt {
+                let image_size = image::Renderer::dimensions(renderer, &background_image.handle);
+                if image_size.width > 0 && image_size.height > 0 {
+                    let bounds = layout.bounds();
+                    renderer.with_layer(bounds, |renderer| {
+                        image::Renderer::draw(
+                            renderer,
+                            background_image.handle.clone(),
+                            image::FilterMethod::Linear,
+                            background_image_bounds(
+                                background_image.scale,
+                                bounds,
+                                Size::new(image_size.width as f32, image_size.height as f32),
+                            ),
+                        );
+                        renderer.fill_quad(
+                            Quad {
+                                bounds,
+                                ..Default::default()
+                            },
+                            Color::new(
+                                f32::from(background_color.r()) / 255.0,
+                                f32::from(background_color.g()) / 255.0,
+                                f32::from(background_color.b()) / 255.0,
+                                background_image.dim,
+                            ),
+                        );
+                    });
+                }
+            }
         }
 
         // Render cell backgrounds that do not match default
@@ -368,6 +723,40 @@ where
                             );
                         }
 
+                        if let Some((cursor_shape, cursor_color)) = metadata.cursor_opt {
+                            let cursor_color = cosmic_text_to_iced_color(cursor_color);
+                            let thickness = (self.glyph_font_size / 10.0).clamp(2.0, 16.0);
+                            let width = self.end_x - self.start_x;
+                            let mut cursor_quads = Vec::with_capacity(4);
+                            match cursor_shape {
+                                CursorShape::Beam => {
+                                    let pos_offset = mk_pos_offset!(0.0, self.line_height);
+                                    cursor_quads.push(mk_quad!(
+                                        pos_offset,
+                                        self.line_height,
+                                        thickness
+                                    ));
+                                }
+                                CursorShape::Underline => {
+                                    let pos_offset = mk_pos_offset!(0.0, thickness);
+                                    cursor_quads.push(mk_quad!(pos_offset, thickness));
+                                }
+                                CursorShape::HollowBlock => {
+                                    let top = mk_pos_offset!(0.0, self.line_height);
+                                    let bottom = mk_pos_offset!(0.0, thickness);
+                                    let right = mk_pos_offset!(width - thickness, self.line_height);
+                                    cursor_quads.push(mk_quad!(top, thickness));
+                                    cursor_quads.push(mk_quad!(bottom, thickness));
+                                    cursor_quads.push(mk_quad!(top, self.line_height, thickness));
+                                    cursor_quads.push(mk_quad!(right, self.line_height, thickness));
+                                }
+                                CursorShape::Block | CursorShape::Hidden => {}
+                            }
+                            for cursor_quad in cursor_quads {
+                                renderer.fill_quad(cursor_quad, cursor_color);
+                            }
+                        }
+
                         if !metadata.flags.is_empty() {
                             let style_line_height = (self.glyph_font_size / 10.0).clamp(2.0, 16.0);
 
@@ -402,46 +791,36 @@ where
                                 renderer.fill_quad(underline2_quad, line_color);
                             }
 
-                            if metadata.flags.contains(Flags::DOTTED_UNDERLINE) {
-                                let bottom_offset = style_line_height * 2.0;
-
-                                let full_width = self.end_x - self.start_x;
-                                let mut accu_width = 0.0;
-                                let mut dot_width = 2.0f32.min(full_width - accu_width);
+                            // Dots and dashes are aligned to the view, so that they continue
+                            // across runs with different styles
+                            macro_rules! draw_pattern {
+                                ($period:expr, $segment_width:expr, $bottom_offset:expr) => {
+                                    let full_width = self.end_x - self.start_x;
+                                    let mut accu_width = 0.0;
+                                    while accu_width < full_width {
+                                        let phase = (self.start_x + accu_width).rem_euclid($period);
+                                        if phase < $segment_width {
+                                            let width = ($segment_width - phase)
+                                                .min(full_width - accu_width);
+                                            let pos_offset =
+                                                mk_pos_offset!(accu_width, $bottom_offset);
+                                            let underline_quad =
+                                                mk_quad!(pos_offset, style_line_height, width);
+                                            renderer.fill_quad(underline_quad, line_color);
+                                            accu_width += width;
+                                        } else {
+                                            accu_width += $period - phase;
+                                        }
+                                    }
+                                };
+                            }
 
-                                while accu_width < full_width {
-                                    dot_width = dot_width.min(full_width - accu_width);
-                                    let pos_offset = mk_pos_offset!(accu_width, bottom_offset);
-                                    let underline_quad =
-                                        mk_quad!(pos_offset, style_line_height, dot_width);
-                                    renderer.fill_quad(underline_quad, line_color);
-                                    accu_width += 2.0 * dot_width;
-                                }
+                            if metadata.flags.contains(Flags::DOTTED_UNDERLINE) {
+                                draw_pattern!(4.0, 2.0, style_line_height * 2.0);
                             }
 
                             if metadata.flags.contains(Flags::DASHED_UNDERLINE) {
-                                let bottom_offset = style_line_height * 2.0;
-
-                                let full_width = self.end_x - self.start_x;
-                                let mut accu_width = 0.0;
-                                let mut dash_width = 6.0f32.min(full_width - accu_width);
-                                let gap_width = dash_width / 2.0;
-
-                                // gap-width dash first
-                                let pos_offset = mk_pos_offset!(accu_width, bottom_offset);
-                                let underline_quad =
-                                    mk_quad!(pos_offset, style_line_height, gap_width);
-                                renderer.fill_quad(underline_quad, line_color);
-                                accu_width += gap_width * 2.0;
-
-                                while accu_width < full_width {
-                                    dash_width = dash_width.min(full_width - accu_width);
-                                    let pos_offset = mk_pos_offset!(accu_width, bottom_offset);
-                                    let underline_quad =
-                                        mk_quad!(pos_offset, style_line_height, dash_width);
-                                    renderer.fill_quad(underline_quad, line_color);
-                                    accu_width += dash_width + gap_width;
-                                }
+                                draw_pattern!(9.0, 6.0, style_line_height * 2.0);
                             }
 
                             if metadata.flags.contains(Flags::UNDERCURL) {
@@ -455,7 +834,8 @@ where
                                 while accu_width < full_width {
                                     dot_width = dot_width.min(full_width - accu_width);
 
-                                    let dot_bottom_offset = match accu_width as u32 % 8 {
+                                    let phase = (self.start_x + accu_width).max(0.0) as u32;
+                                    let dot_bottom_offset = match phase % 8 {
                                         3..=5 => bottom_offset + style_line_height,
                                         2 | 6 => bottom_offset + 2.0 * style_line_height / 3.0,
                                         1 | 7 => bottom_offset + 1.0 * style_line_height / 3.0,
@@ -483,29 +863,126 @@ where
                     end_x: 0.0,
                     line_height: buffer.metrics().line_height,
                     line_top: run.line_top,
-                    view_position,
+                    view_position: grid_position,
                     metadata_set,
                 };
                 for glyph in run.glyphs {
                     bg_rect.update(glyph, renderer, state.is_focused);
                 }
                 bg_rect.fill(renderer, state.is_focused);
+
+                // Box drawing characters are drawn over the backgrounds, the text has spaces
+                for glyph in run.glyphs {
+                    let Some((c, color)) = metadata_set[glyph.metadata].box_drawing_opt else {
+                        continue;
+                    };
+                    let line_height = buffer.metrics().line_height;
+                    for (rect, alpha) in box_drawing::rects(c, glyph.w, line_height) {
+                        renderer.fill_quad(
+                            Quad {
+                                bounds: Rectangle::new(
+                                    grid_position
+                                        + Vector::new(glyph.x + rect.x, run.line_top + rect.y),
+                                    rect.size(),
+                                ),
+                                ..Default::default()
+                            },
+                            Color::new(
+                                f32::from(color.r()) / 255.0,
+                                f32::from(color.g()) / 255.0,
+                                f32::from(color.b()) / 255.0,
+                                f32::from(color.a()) / 255.0 * alpha,
+                            ),
+                        );
+                    }
+                }
             }
         });
 
+        // Render highlights of the profile over the cell backgrounds and under the text
+        {
+            let size = terminal.size();
+            for rect in terminal.highlight_rects() {
+                renderer.fill_quad(
+                    Quad {
+                        bounds: Rectangle::new(
+                            grid_position
+                                + Vector::new(
+                                    rect.start_column as f32 * size.cell_width,
+                                    rect.line as f32 * size.cell_height,
+                                ),
+                            Size::new(
+                                (rect.end_column + 1 - rect.start_column) as f32 * size.cell_width,
+                                size.cell_height,
+                            ),
+                        ),
+                        ..Default::default()
+                    },
+                    Color::from_rgba8(
+                        rect.color.r,
+                        rect.color.g,
+                        rect.color.b,
+                        f32::from(rect.color.a) / 255.0 * HIGHLIGHT_ALPHA,
+                    ),
+                );
+            }
+        }
+
         renderer.fill_raw(Raw {
             buffer: terminal.buffer_weak(),
-            position: view_position,
+            position: grid_position,
             color: Color::new(1.0, 1.0, 1.0, 1.0), // TODO
             clip_bounds: Rectangle::new(view_position, Size::new(view_w as f32, view_h as f32)),
         });
 
+        // Render images sent by programs over the cells they cover
+        let image_placements = terminal.image_placements();
+        if !image_placements.is_empty() {
+            let size = terminal.size();
+            let clip_bounds =
+                Rectangle::new(view_position, Size::new(view_w as f32, view_h as f32));
+            renderer.with_layer(clip_bounds, |renderer| {
+                for placement in image_placements {
+                    image::Renderer::draw(
+                        renderer,
+                        placement.handle.clone(),
+                        image::FilterMethod::Nearest,
+                        Rectangle::new(
+                            grid_position
+                                + Vector::new(
+                                    placement.column as f32 * size.cell_width,
+                                    placement.line as f32 * size.cell_height,
+                                ),
+                            Size::new(placement.width as f32, placement.height as f32),
+                        ),
+                    );
+                }
+            });
+        }
+
+        // Flash for the visual bell
+        if self.bell_flash {
+            renderer.fill_quad(
+                Quad {
+                    bounds: layout.bounds(),
+                    border: self.border,
+                    ..Default::default()
+                },
+                Color::from(cosmic_theme.accent_color().without_alpha().with_alpha(0.2)),
+            );
+        }
+
         // Draw scrollbar
         if let Some((start, end)) = terminal.scrollbar() {
             let scrollbar_y = start * view_h as f32;
             let scrollbar_h = end * view_h as f32 - scrollbar_y;
+            // Relative to the grid, like the positions of mouse events
             let scrollbar_rect = Rectangle::new(
-                [view_w as f32, scrollbar_y].into(),
+                [
+                    view_w as f32 + self.padding.left - padding.left,
+                    scrollbar_y + self.padding.top - padding.top,
+                ]
+                .into(),
                 Size::new(scrollbar_w, scrollbar_h),
             );
 
@@ -513,13 +990,13 @@ where
 
             let mut hover = false;
             if let Some(p) = cursor_position.position_in(layout.bounds()) {
-                let x = p.x - self.padding.left;
+                let x = p.x - padding.left;
                 if x >= scrollbar_rect.x && x < (scrollbar_rect.x + scrollbar_rect.width) {
                     hover = true;
                 }
             }
 
-            let mut scrollbar_draw = scrollbar_rect + Vector::new(view_position.x, view_position.y);
+            let mut scrollbar_draw = scrollbar_rect + Vector::new(grid_position.x, grid_position.y);
             if !hover && !pressed {
                 // Decrease draw width and keep centered when not hovered or pressed
                 scrollbar_draw.width /= 2.0;
@@ -556,24 +1033,83 @@ where
                 base_color.into()
             };
 
-            renderer.fill_quad(
-                Quad {
-                    bounds: scrollbar_draw,
-                    border: Border {
-                        radius: (scrollbar_draw.width / 2.0).into(),
-                        width: 0.0,
-                        color: Color::TRANSPARENT,
+            // Hide when scrolled to the bottom, unless the pointer is over the scrollbar
+            let at_bottom = end >= 1.0;
+            if !at_bottom || hover || pressed {
+                renderer.fill_quad(
+                    Quad {
+                        bounds: scrollbar_draw,
+                        border: Border {
+                            radius: (scrollbar_draw.width / 2.0).into(),
+                            width: 0.0,
+                            color: Color::TRANSPARENT,
+                        },
+                        ..Default::default()
                     },
-                    ..Default::default()
-                },
-                scrollbar_color,
-            );
+                    scrollbar_color,
+                );
+            }
+
+            // Draw manual marks and prompts in the scrollbar gutter, marks in the accent color
+            for (position, manual) in terminal.mark_positions() {
+                let mark_h = 2.0;
+                let mark_rect = Rectangle::new(
+                    view_position
+                        + Vector::new(
+                            view_w as f32,
+                            (position * view_h as f32).min(view_h as f32 - mark_h),
+                        ),
+                    Size::new(scrollbar_w, mark_h),
+                );
+                let mark_color: Color = if manual {
+                    cosmic_theme.accent_color().into()
+                } else {
+                    base_color.into()
+                };
+                renderer.fill_quad(
+                    Quad {
+                        bounds: mark_rect,
+                        ..Default::default()
+                    },
+                    mark_color,
+                );
+            }
 
             state.scrollbar_rect.set(scrollbar_rect);
         } else {
-            state.scrollbar_rect.set(Rectangle::default())
+            state.scrollbar_rect.set(Rectangle::default());
+
+            // Draw a faint line in place of the scrollbar to show that the alternate screen
+            // has no history
+            if terminal.is_alt_screen() {
+                let indicator_w = scrollbar_w / 4.0;
+                let indicator_rect = Rectangle::new(
+                    view_position
+                        + Vector::new(view_w as f32 + (scrollbar_w - indicator_w) / 2.0, 0.0),
+                    Size::new(indicator_w, view_h as f32),
+                );
+                let indicator_color: Color = cosmic_theme
+                    .accent_color()
+                    .without_alpha()
+                    .with_alpha(0.3)
+                    .into();
+                renderer.fill_quad(
+                    Quad {
+                        bounds: indicator_rect,
+                        border: Border {
+                            radius: (indicator_w / 2.0).into(),
+                            width: 0.0,
+                            color: Color::TRANSPARENT,
+                        },
+                        ..Default::default()
+                    },
+                    indicator_color,
+                );
+            }
         }
 
+        terminal.frame_drawn();
+
         let duration = instant.elapsed();
         log::trace!("redraw {}, {}: {:?}", view_w, view_h, duration);
     }
@@ -593,240 +1129,235 @@ where
         let scrollbar_rect = state.scrollbar_rect.get();
         let mut terminal = self.terminal.lock().unwrap();
         let buffer_size = terminal.with_buffer(|buffer| buffer.size());
+        let padding = self.grid_padding(&terminal);
 
-        let is_app_cursor = terminal.term.lock().mode().contains(TermMode::APP_CURSOR);
         let is_mouse_mode = terminal.term.lock().mode().intersects(TermMode::MOUSE_MODE);
+        let keyboard_mode = *terminal.term.lock().mode() & TermMode::KITTY_KEYBOARD_PROTOCOL;
 
         let mut status = Status::Ignored;
         match event {
             Event::Keyboard(KeyEvent::KeyPressed {
-                key: Key::Named(named),
+                key,
                 modifiers,
+                text,
                 ..
-            }) if state.is_focused => {
+            }) if state.is_focused && terminal.hints_active() => {
                 for key_bind in self.key_binds.keys() {
-                    if key_bind.matches(modifiers, &Key::Named(named)) {
+                    if key_bind.matches(modifiers, &key) {
                         return Status::Captured;
                     }
                 }
-                let mod_no = calculate_modifier_number(state);
-                let escape_code = match named {
-                    Named::Insert => csi("2", "~", mod_no),
-                    Named::Delete => csi("3", "~", mod_no),
-                    Named::PageUp => {
-                        if modifiers.shift() {
-                            terminal.scroll(TerminalScroll::PageUp);
-                            None
-                        } else {
-                            csi("5", "~", mod_no)
-                        }
+                match key {
+                    Key::Named(Named::Escape) => terminal.cancel_hints(),
+                    Key::Named(Named::Backspace) => {
+                        terminal.hint_input(None);
                     }
-                    Named::PageDown => {
-                        if modifiers.shift() {
-                            terminal.scroll(TerminalScroll::PageDown);
-                            None
-                        } else {
-                            csi("6", "~", mod_no)
+                    _ => {
+                        if let Some(c) = text.and_then(|text| text.chars().next()) {
+                            if let Some((action, hint_text)) = terminal.hint_input(Some(c)) {
+                                if let Some(on_hint) = &self.on_hint {
+                                    shell.publish(on_hint(action, hint_text));
+                                }
+                            }
                         }
                     }
-                    Named::ArrowUp => {
-                        if is_app_cursor {
-                            ss3("A", mod_no)
-                        } else {
-                            csi("A", "", mod_no)
-                        }
+                }
+                status = Status::Captured;
+            }
+            Event::Keyboard(KeyEvent::KeyPressed { key, modifiers, .. })
+                if state.is_focused && terminal.copy_mode() =>
+            {
+                for key_bind in self.key_binds.keys() {
+                    if key_bind.matches(modifiers, &key) {
+                        return Status::Captured;
                     }
-                    Named::ArrowDown => {
-                        if is_app_cursor {
-                            ss3("B", mod_no)
-                        } else {
-                            csi("B", "", mod_no)
-                        }
+                }
+                // Copy mode keys follow vi, nothing is sent to the program
+                let half_page = cmp::max(terminal.term.lock().screen_lines() as i32 / 2, 1);
+                match (key.as_ref(), modifiers.control()) {
+                    (Key::Named(Named::Escape), _) | (Key::Character("q" | "i"), false) => {
+                        terminal.toggle_copy_mode();
                     }
-                    Named::ArrowRight => {
-                        if is_app_cursor {
-                            ss3("C", mod_no)
-                        } else {
-                            csi("C", "", mod_no)
+                    (Key::Named(Named::Enter), _) | (Key::Character("y"), false) => {
+                        if let Some(on_copy_mode_yank) = &self.on_copy_mode_yank {
+                            shell.publish(on_copy_mode_yank());
                         }
                     }
-                    Named::ArrowLeft => {
-                        if is_app_cursor {
-                            ss3("D", mod_no)
-                        } else {
-                            csi("D", "", mod_no)
+                    (Key::Character("/" | "?"), false) => {
+                        if let Some(on_find) = &self.on_find {
+                            shell.publish(on_find());
                         }
                     }
-                    Named::End => {
-                        if modifiers.shift() {
-                            terminal.scroll(TerminalScroll::Bottom);
-                            None
-                        } else if is_app_cursor {
-                            ss3("F", mod_no)
-                        } else {
-                            csi("F", "", mod_no)
-                        }
+                    (Key::Character("n"), false) => terminal.copy_mode_search(true),
+                    (Key::Character("N"), false) => terminal.copy_mode_search(false),
+                    (Key::Character("v"), false) => {
+                        terminal.copy_mode_select(SelectionType::Simple)
                     }
-                    Named::Home => {
-                        if modifiers.shift() {
-                            terminal.scroll(TerminalScroll::Top);
-                            None
-                        } else if is_app_cursor {
-                            ss3("H", mod_no)
-                        } else {
-                            csi("H", "", mod_no)
-                        }
+                    (Key::Character("V"), false) => terminal.copy_mode_select(SelectionType::Lines),
+                    (Key::Character("v"), true) => terminal.copy_mode_select(SelectionType::Block),
+                    (Key::Character("g"), false) => terminal.copy_mode_goto(true),
+                    (Key::Character("G"), false) => terminal.copy_mode_goto(false),
+                    (Key::Character("u"), true) => terminal.copy_mode_scroll(half_page),
+                    (Key::Character("d"), true) => terminal.copy_mode_scroll(-half_page),
+                    (Key::Named(Named::PageUp), _) | (Key::Character("b"), true) => {
+                        terminal.copy_mode_scroll(half_page * 2)
                     }
-                    Named::F1 => ss3("P", mod_no),
-                    Named::F2 => ss3("Q", mod_no),
-                    Named::F3 => ss3("R", mod_no),
-                    Named::F4 => ss3("S", mod_no),
-                    Named::F5 => csi("15", "~", mod_no),
-                    Named::F6 => csi("17", "~", mod_no),
-                    Named::F7 => csi("18", "~", mod_no),
-                    Named::F8 => csi("19", "~", mod_no),
-                    Named::F9 => csi("20", "~", mod_no),
-                    Named::F10 => csi("21", "~", mod_no),
-                    Named::F11 => csi("23", "~", mod_no),
-                    Named::F12 => csi("24", "~", mod_no),
-                    _ => None,
-                };
-                if let Some(escape_code) = escape_code {
-                    terminal.input_scroll(escape_code);
-                    return Status::Captured;
-                }
-
-                //Special handle Enter, Escape, Backspace and Tab as described in
-                //https://sw.kovidgoyal.net/kitty/keyboard-protocol/#legacy-key-event-encoding
-                //Also special handle Ctrl-_ to behave like xterm
-                let alt_prefix = if modifiers.alt() { "\x1B" } else { "" };
-                match named {
-                    Named::Backspace => {
-                        let code = if modifiers.control() { "\x08" } else { "\x7f" };
-                        terminal.input_scroll(format!("{alt_prefix}{code}").as_bytes().to_vec());
-                        status = Status::Captured;
-                    }
-                    Named::Enter => {
-                        terminal
-                            .input_scroll(format!("{}{}", alt_prefix, "\x0D").as_bytes().to_vec());
-                        status = Status::Captured;
+                    (Key::Named(Named::PageDown), _) | (Key::Character("f"), true) => {
+                        terminal.copy_mode_scroll(-half_page * 2)
                     }
-                    Named::Escape => {
-                        //Escape with any modifier will cancel selection
-                        let had_selection = {
-                            let mut term = terminal.term.lock();
-                            term.selection.take().is_some()
+                    (key, false) => {
+                        let motion_opt = match key {
+                            Key::Named(Named::ArrowLeft) | Key::Character("h") => {
+                                Some(ViMotion::Left)
+                            }
+                            Key::Named(Named::ArrowDown) | Key::Character("j") => {
+                                Some(ViMotion::Down)
+                            }
+                            Key::Named(Named::ArrowUp) | Key::Character("k") => Some(ViMotion::Up),
+                            Key::Named(Named::ArrowRight) | Key::Character("l") => {
+                                Some(ViMotion::Right)
+                            }
+                            Key::Named(Named::Home) | Key::Character("0") => Some(ViMotion::First),
+                            Key::Named(Named::End) | Key::Character("$") => Some(ViMotion::Last),
+                            Key::Character("^") => Some(ViMotion::FirstOccupied),
+                            Key::Character("w") => Some(ViMotion::SemanticRight),
+                            Key::Character("b") => Some(ViMotion::SemanticLeft),
+                            Key::Character("e") => Some(ViMotion::SemanticRightEnd),
+                            Key::Character("W") => Some(ViMotion::WordRight),
+                            Key::Character("B") => Some(ViMotion::WordLeft),
+                            Key::Character("E") => Some(ViMotion::WordRightEnd),
+                            Key::Character("H") => Some(ViMotion::High),
+                            Key::Character("M") => Some(ViMotion::Middle),
+                            Key::Character("L") => Some(ViMotion::Low),
+                            Key::Character("%") => Some(ViMotion::Bracket),
+                            Key::Character("{") => Some(ViMotion::ParagraphUp),
+                            Key::Character("}") => Some(ViMotion::ParagraphDown),
+                            _ => None,
                         };
-                        if had_selection {
-                            terminal.update();
-                        } else {
-                            terminal.input_scroll(
-                                format!("{}{}", alt_prefix, "\x1B").as_bytes().to_vec(),
-                            );
+                        if let Some(motion) = motion_opt {
+                            terminal.copy_mode_motion(motion);
                         }
-                        status = Status::Captured;
-                    }
-                    Named::Space => {
-                        terminal.input_scroll(format!("{}{}", alt_prefix, " ").as_bytes().to_vec());
-                        status = Status::Captured;
-                    }
-                    Named::Tab => {
-                        let code = if modifiers.shift() { "\x1b[Z" } else { "\x09" };
-                        terminal.input_scroll(format!("{alt_prefix}{code}").as_bytes().to_vec());
-                        status = Status::Captured;
                     }
                     _ => {}
                 }
-            }
-            Event::Keyboard(KeyEvent::ModifiersChanged(modifiers)) => {
-                state.modifiers = modifiers;
+                status = Status::Captured;
             }
             Event::Keyboard(KeyEvent::KeyPressed {
-                text,
-                modifiers,
                 key,
-                ..
+                location,
+                modifiers,
+                text,
             }) if state.is_focused => {
                 for key_bind in self.key_binds.keys() {
                     if key_bind.matches(modifiers, &key) {
                         return Status::Captured;
                     }
                 }
-                let character = text.and_then(|c| c.chars().next()).unwrap_or_default();
-                match (
-                    modifiers.logo(),
-                    modifiers.control(),
-                    modifiers.alt(),
-                    modifiers.shift(),
-                ) {
-                    (true, _, _, _) => {
-                        // Ignore super
-                    }
-                    (false, true, true, _) => {
-                        // Handle ctrl-alt for non-control characters
-                        // and control characters 0-32
-                        if !character.is_control() || (character as u32) < 32 {
-                            // Handle alt for non-control characters
-                            let mut buf = [0x1B, 0, 0, 0, 0];
-                            let len = {
-                                let str = character.encode_utf8(&mut buf[1..]);
-                                str.len() + 1
-                            };
-                            terminal.input_scroll(buf[..len].to_vec());
-                            status = Status::Captured;
-                        }
-                    }
-                    (false, true, _, false) => {
-                        // Handle ctrl for control characters (Ctrl-A to Ctrl-Z)
-                        if character.is_control() {
-                            let mut buf = [0, 0, 0, 0];
-                            let str = character.encode_utf8(&mut buf);
-                            terminal.input_scroll(str.as_bytes().to_vec());
-                            status = Status::Captured;
+
+                // Shift scrolls and Escape cancels the selection, unless the program asked for
+                // these keys with the kitty keyboard protocol
+                if keyboard_mode.is_empty() {
+                    let scroll_opt = match key {
+                        Key::Named(Named::PageUp) if modifiers.shift() => {
+                            Some(TerminalScroll::PageUp)
                         }
-                    }
-                    (false, true, _, true) => {
-                        //This is normally Ctrl+Minus, but since that
-                        //is taken by zoom, we send that code for
-                        //Ctrl+Underline instead, like xterm and
-                        //gnome-terminal
-                        if key == Key::Character("_".into()) {
-                            terminal.input_scroll(b"\x1F".as_slice());
-                            status = Status::Captured;
-                        }
-                    }
-                    (false, false, true, _) => {
-                        if !character.is_control() {
-                            // Handle alt for non-control characters
-                            let mut buf = [0x1B, 0, 0, 0, 0];
-                            let len = {
-                                let str = character.encode_utf8(&mut buf[1..]);
-                                str.len() + 1
-                            };
-                            terminal.input_scroll(buf[..len].to_vec());
-                            status = Status::Captured;
+                        Key::Named(Named::PageDown) if modifiers.shift() => {
+                            Some(TerminalScroll::PageDown)
                         }
+                        Key::Named(Named::Home) if modifiers.shift() => Some(TerminalScroll::Top),
+                        Key::Named(Named::End) if modifiers.shift() => Some(TerminalScroll::Bottom),
+                        _ => None,
+                    };
+                    if let Some(scroll) = scroll_opt {
+                        terminal.scroll(scroll);
+                        return Status::Captured;
                     }
-                    (false, false, false, _) => {
-                        // Handle no modifiers for non-control characters
-                        if !character.is_control() {
-                            let mut buf = [0, 0, 0, 0];
-                            let str = character.encode_utf8(&mut buf);
-                            terminal.input_scroll(str.as_bytes().to_vec());
-                            status = Status::Captured;
+
+                    //Escape with any modifier will cancel selection
+                    if key == Key::Named(Named::Escape) {
+                        let had_selection = {
+                            let mut term = terminal.term.lock();
+                            term.selection.take().is_some()
+                        };
+                        if had_selection {
+                            terminal.update();
+                            return Status::Captured;
                         }
                     }
                 }
+
+                let key_input = KeyInput {
+                    key,
+                    location,
+                    modifiers,
+                    text_opt: text.map(|text| text.to_string()),
+                    release: false,
+                };
+                if self.input(&terminal, shell, key_input) {
+                    status = Status::Captured;
+                }
+            }
+            Event::Keyboard(KeyEvent::KeyReleased {
+                key,
+                location,
+                modifiers,
+            }) if state.is_focused && !terminal.hints_active() && !terminal.copy_mode() => {
+                // Only sent to programs that asked for releases with the kitty keyboard protocol
+                let key_input = KeyInput {
+                    key,
+                    location,
+                    modifiers,
+                    text_opt: None,
+                    release: true,
+                };
+                if self.input(&terminal, shell, key_input) {
+                    status = Status::Captured;
+                }
+            }
+            Event::Keyboard(KeyEvent::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+                if !modifiers.control() {
+                    terminal.set_hovered_url(None);
+                }
             }
             Event::Mouse(MouseEvent::ButtonPressed(button)) => {
                 if let Some(p) = cursor_position.position_in(layout.bounds()) {
-                    let x = p.x - self.padding.left;
-                    let y = p.y - self.padding.top;
+                    let x = p.x - padding.left;
+                    let y = p.y - padding.top;
                     //TODO: better calculation of position
                     let col = x / terminal.size().cell_width;
                     let row = y / terminal.size().cell_height;
 
-                    if is_mouse_mode {
+                    // Ctrl+click opens URLs and file references, even when the mouse is
+                    // reported to the program
+                    if button == Button::Left
+                        && state.modifiers.control()
+                        && x >= 0.0
+                        && x < buffer_size.0
+                        && y >= 0.0
+                        && y < buffer_size.1
+                    {
+                        let point = terminal.viewport_to_point(TermPoint::new(
+                            row as usize,
+                            TermColumn(col as usize),
+                        ));
+                        if let Some(on_open_url) = &self.on_open_url {
+                            if let Some((url, _url_match)) = terminal.url_at(point) {
+                                shell.publish(on_open_url(url));
+                                return Status::Captured;
+                            }
+                        }
+                        if let Some(on_open_file_ref) = &self.on_open_file_ref {
+                            if let Some((file_ref, _file_ref_match)) = terminal.file_ref_at(point) {
+                                shell.publish(on_open_file_ref(file_ref));
+                                return Status::Captured;
+                            }
+                        }
+                    }
+
+                    // Shift+right click shows the context menu, even when the mouse is reported
+                    let force_context_menu = button == Button::Right && state.modifiers.shift();
+                    if is_mouse_mode && !force_context_menu {
                         terminal.report_mouse(event, &state.modifiers, col as u32, row as u32);
                     } else {
                         state.is_focused = true;
@@ -834,8 +1365,8 @@ where
                         // Handle left click drag
                         #[allow(clippy::collapsible_if)]
                         if let Button::Left = button {
-                            let x = p.x - self.padding.left;
-                            let y = p.y - self.padding.top;
+                            let x = p.x - padding.left;
+                            let y = p.y - padding.top;
                             if x >= 0.0 && x < buffer_size.0 && y >= 0.0 && y < buffer_size.1 {
                                 let click_kind =
                                     if let Some((click_kind, click_time)) = state.click.take() {
@@ -860,7 +1391,26 @@ where
                                 } else {
                                     TermSide::Right
                                 };
+                                let selection_opt = terminal.term.lock().selection.clone();
                                 let selection = match click_kind {
+                                    // Shift+click extends the current selection
+                                    ClickKind::Single if state.modifiers.shift() => {
+                                        match selection_opt {
+                                            Some(mut selection) => {
+                                                selection.update(location, side);
+                                                selection
+                                            }
+                                            None => Selection::new(
+                                                SelectionType::Simple,
+                                                location,
+                                                side,
+                                            ),
+                                        }
+                                    }
+                                    // Alt+drag selects a rectangular block of cells
+                                    ClickKind::Single if state.modifiers.alt() => {
+                                        Selection::new(SelectionType::Block, location, side)
+                                    }
                                     ClickKind::Single => {
                                         Selection::new(SelectionType::Simple, location, side)
                                     }
@@ -907,13 +1457,25 @@ where
                         }
                         // Update context menu state
                         if let Some(on_context_menu) = &self.on_context_menu {
-                            shell.publish((on_context_menu)(match self.context_menu {
+                            let position_opt = match self.context_menu {
                                 Some(_) => None,
                                 None => match button {
                                     Button::Right => Some(p),
                                     _ => None,
                                 },
-                            }));
+                            };
+                            // Remember the link under the cursor so the menu can open it
+                            let url_opt = if position_opt.is_some() {
+                                let point = terminal.viewport_to_point(TermPoint::new(
+                                    row as usize,
+                                    TermColumn(col as usize),
+                                ));
+                                terminal.url_at(point).map(|(url, _url_match)| url)
+                            } else {
+                                None
+                            };
+                            terminal.context_menu_url = url_opt;
+                            shell.publish((on_context_menu)(position_opt));
                         }
                         status = Status::Captured;
                     }
@@ -921,9 +1483,10 @@ where
             }
             Event::Mouse(MouseEvent::ButtonReleased(Button::Left)) => {
                 state.dragging = None;
+                state.autoscroll = None;
                 if let Some(p) = cursor_position.position_in(layout.bounds()) {
-                    let x = p.x - self.padding.left;
-                    let y = p.y - self.padding.top;
+                    let x = p.x - padding.left;
+                    let y = p.y - padding.top;
                     //TODO: better calculation of position
                     let col = x / terminal.size().cell_width;
                     let row = y / terminal.size().cell_height;
@@ -938,8 +1501,8 @@ where
             }
             Event::Mouse(MouseEvent::ButtonReleased(_button)) => {
                 if let Some(p) = cursor_position.position_in(layout.bounds()) {
-                    let x = p.x - self.padding.left;
-                    let y = p.y - self.padding.top;
+                    let x = p.x - padding.left;
+                    let y = p.y - padding.top;
                     //TODO: better calculation of position
                     let col = x / terminal.size().cell_width;
                     let row = y / terminal.size().cell_height;
@@ -963,11 +1526,42 @@ where
                     }
                 }
                 if let Some(p) = cursor_position.position() {
-                    let x = (p.x - layout.bounds().x) - self.padding.left;
-                    let y = (p.y - layout.bounds().y) - self.padding.top;
+                    let x = (p.x - layout.bounds().x) - padding.left;
+                    let y = (p.y - layout.bounds().y) - padding.top;
                     //TODO: better calculation of position
                     let col = x / terminal.size().cell_width;
                     let row = y / terminal.size().cell_height;
+
+                    // Ctrl+hover underlines URLs and file references
+                    if self.on_open_url.is_some() || self.on_open_file_ref.is_some() {
+                        let hovered_url_opt = if state.modifiers.control()
+                            && x >= 0.0
+                            && x < buffer_size.0
+                            && y >= 0.0
+                            && y < buffer_size.1
+                        {
+                            let point = terminal.viewport_to_point(TermPoint::new(
+                                row as usize,
+                                TermColumn(col as usize),
+                            ));
+                            let url_match_opt = match self.on_open_url {
+                                Some(_) => {
+                                    terminal.url_at(point).map(|(_url, url_match)| url_match)
+                                }
+                                None => None,
+                            };
+                            url_match_opt.or_else(|| match self.on_open_file_ref {
+                                Some(_) => terminal
+                                    .file_ref_at(point)
+                                    .map(|(_file_ref, file_ref_match)| file_ref_match),
+                                None => None,
+                            })
+                        } else {
+                            None
+                        };
+                        terminal.set_hovered_url(hovered_url_opt);
+                    }
+
                     if is_mouse_mode {
                         terminal.report_mouse(event, &state.modifiers, col as u32, row as u32);
                     } else {
@@ -990,6 +1584,28 @@ where
                                         }
                                     }
                                     terminal.needs_update = true;
+
+                                    // Dragging past the top or bottom edge scrolls, see the
+                                    // RedrawRequested event
+                                    let overshoot = if y < 0.0 {
+                                        y
+                                    } else {
+                                        (y - buffer_size.1).max(0.0)
+                                    };
+                                    if overshoot == 0.0 {
+                                        state.autoscroll = None;
+                                    } else if let Some(autoscroll) = &mut state.autoscroll {
+                                        autoscroll.col = col;
+                                        autoscroll.overshoot = overshoot;
+                                    } else {
+                                        state.autoscroll = Some(AutoScroll {
+                                            col,
+                                            overshoot,
+                                            instant: Instant::now(),
+                                            lines: 0.0,
+                                        });
+                                        shell.request_redraw(RedrawRequest::NextFrame);
+                                    }
                                 }
                                 Dragging::Scrollbar {
                                     start_y,
@@ -1005,11 +1621,149 @@ where
                     }
                 }
             }
+            Event::Window(_id, window::Event::RedrawRequested(now)) => {
+                if let (Some(Dragging::Buffer), Some(autoscroll)) =
+                    (&state.dragging, &mut state.autoscroll)
+                {
+                    let cell_height = terminal.size().cell_height;
+                    let elapsed = now.saturating_duration_since(autoscroll.instant);
+                    autoscroll.instant = now;
+                    autoscroll.lines += autoscroll.overshoot / cell_height
+                        * AUTOSCROLL_SPEED
+                        * elapsed.as_secs_f32();
+                    let lines = autoscroll.lines as i32;
+                    if lines != 0 {
+                        autoscroll.lines -= lines as f32;
+                        // Positive deltas scroll up, into the scrollback
+                        terminal.scroll(TerminalScroll::Delta(-lines));
+
+                        // Extend the selection to the edge that was scrolled to
+                        let row = if autoscroll.overshoot < 0.0 {
+                            0
+                        } else {
+                            terminal.term.lock().screen_lines().saturating_sub(1)
+                        };
+                        let location = terminal.viewport_to_point(TermPoint::new(
+                            row,
+                            TermColumn(autoscroll.col.max(0.0) as usize),
+                        ));
+                        let side = if autoscroll.overshoot < 0.0 {
+                            TermSide::Left
+                        } else {
+                            TermSide::Right
+                        };
+                        {
+                            let mut term = terminal.term.lock();
+                            if let Some(selection) = &mut term.selection {
+                                selection.update(location, side);
+                            }
+                        }
+                        terminal.needs_update = true;
+                    }
+                    shell.request_redraw(RedrawRequest::NextFrame);
+                }
+
+                // Keep scrolling after a touch drag is released, slowing down over time
+                if let Some(momentum) = &mut state.momentum_opt {
+                    let elapsed = now
+                        .saturating_duration_since(momentum.instant)
+                        .as_secs_f32();
+                    momentum.instant = now;
+                    let pixels = momentum.velocity * elapsed;
+                    momentum.velocity *= MOMENTUM_DECAY.powf(elapsed);
+                    if momentum.velocity.abs() < MOMENTUM_MIN_VELOCITY {
+                        state.momentum_opt = None;
+                    } else {
+                        shell.request_redraw(RedrawRequest::NextFrame);
+                    }
+                    scroll_pixels(&terminal, state, pixels);
+                }
+            }
+            Event::Touch(TouchEvent::FingerPressed { id, position }) => {
+                if !is_mouse_mode && layout.bounds().contains(position) {
+                    state.momentum_opt = None;
+                    state.touch_opt = Some(TouchDrag {
+                        finger: id,
+                        y: position.y,
+                        instant: Instant::now(),
+                        velocity: 0.0,
+                    });
+                    status = Status::Captured;
+                }
+            }
+            Event::Touch(TouchEvent::FingerMoved { id, position }) => {
+                if let Some(touch_drag) = &mut state.touch_opt {
+                    if touch_drag.finger == id {
+                        let now = Instant::now();
+                        let elapsed = now.saturating_duration_since(touch_drag.instant);
+                        let pixels = position.y - touch_drag.y;
+                        if elapsed.as_secs_f32() > 0.0 {
+                            // Smooth the speed so the last event does not decide the momentum
+                            touch_drag.velocity =
+                                touch_drag.velocity * 0.5 + pixels / elapsed.as_secs_f32() * 0.5;
+                        }
+                        touch_drag.y = position.y;
+                        touch_drag.instant = now;
+                        scroll_pixels(&terminal, state, pixels);
+                        status = Status::Captured;
+                    }
+                }
+            }
+            Event::Touch(
+                TouchEvent::FingerLifted { id, .. } | TouchEvent::FingerLost { id, .. },
+            ) => {
+                if let Some(touch_drag) = state
+                    .touch_opt
+                    .take()
+                    .filter(|touch_drag| touch_drag.finger == id)
+                {
+                    // Resting the finger before lifting it stops scrolling
+                    if touch_drag.velocity.abs() >= MOMENTUM_MIN_VELOCITY
+                        && touch_drag.instant.elapsed() < Duration::from_millis(100)
+                    {
+                        state.momentum_opt = Some(Momentum {
+                            velocity: touch_drag.velocity,
+                            instant: Instant::now(),
+                        });
+                        shell.request_redraw(RedrawRequest::NextFrame);
+                    }
+                    status = Status::Captured;
+                }
+            }
             Event::Mouse(MouseEvent::WheelScrolled { delta }) => {
                 if let Some(p) = cursor_position.position_in(layout.bounds()) {
-                    if is_mouse_mode {
-                        let x = (p.x - layout.bounds().x) - self.padding.left;
-                        let y = (p.y - layout.bounds().y) - self.padding.top;
+                    // Ctrl+wheel is reported to programs using the mouse, unless Shift is held
+                    let zoom =
+                        state.modifiers.control() && (!is_mouse_mode || state.modifiers.shift());
+                    if zoom {
+                        // Ctrl+wheel zooms, one step per line or line height of pixels
+                        let steps = match delta {
+                            ScrollDelta::Lines { x: _, y } => {
+                                state.scroll_pixels = 0.0;
+                                y.signum() as i32
+                            }
+                            ScrollDelta::Pixels { x: _, y } => {
+                                state.scroll_pixels += y;
+                                let line_height = terminal.size().cell_height;
+                                let steps = (state.scroll_pixels / line_height) as i32;
+                                state.scroll_pixels -= steps as f32 * line_height;
+                                steps
+                            }
+                        };
+                        let on_zoom_opt = match steps.cmp(&0) {
+                            cmp::Ordering::Greater => self.on_zoom_in.as_ref(),
+                            cmp::Ordering::Less => self.on_zoom_out.as_ref(),
+                            cmp::Ordering::Equal => None,
+                        };
+                        if let Some(on_zoom) = on_zoom_opt {
+                            for _ in 0..steps.abs() {
+                                shell.publish(on_zoom());
+                            }
+                        }
+                        status = Status::Captured;
+                    } else if is_mouse_mode {
+                        let x = (p.x - layout.bounds().x) - padding.left;
+                        let y = (p.y - layout.bounds().y) - padding.top;
                         //TODO: better calculation of position
                         let col = x / terminal.size().cell_width;
                         let row = y / terminal.size().cell_height;
@@ -1021,26 +1775,15 @@ where
                                 state.scroll_pixels = 0.0;
                                 let lines = (-y * 6.0) as i32;
                                 if lines != 0 {
-                                    terminal.scroll(TerminalScroll::Delta(-lines));
+                                    terminal.scroll_lines(-lines);
                                 }
                                 status = Status::Captured;
                             }
                             ScrollDelta::Pixels { x: _, y } => {
-                                //TODO: this adjustment is just a guess!
-                                state.scroll_pixels -= y * 6.0;
-                                let mut lines = 0;
-                                let metrics = terminal.with_buffer(|buffer| buffer.metrics());
-                                while state.scroll_pixels <= -metrics.line_height {
-                                    lines -= 1;
-                                    state.scroll_pixels += metrics.line_height;
-                                }
-                                while state.scroll_pixels >= metrics.line_height {
-                                    lines += 1;
-                                    state.scroll_pixels -= metrics.line_height;
-                                }
-                                if lines != 0 {
-                                    terminal.scroll(TerminalScroll::Delta(-lines));
-                                }
+                                // Touchpads scroll by the pixels moved, keeping the remainder
+                                // of a line for the next event
+                                state.momentum_opt = None;
+                                scroll_pixels(&terminal, state, y);
                                 status = Status::Captured;
                             }
                         }
@@ -1054,6 +1797,47 @@ where
     }
 }
 
+// Scroll by pixels, positive being up, keeping the fraction of a line that is left over for the
+// next event. The view still moves by whole lines, as the buffer only holds the visible lines
+// and drawing the fraction would leave a gap at the edge.
+fn scroll_pixels(terminal: &Terminal, state: &mut State, pixels: f32) {
+    state.scroll_pixels += pixels;
+    let line_height = terminal.with_buffer(|buffer| buffer.metrics().line_height);
+    let lines = (state.scroll_pixels / line_height) as i32;
+    if lines != 0 {
+        state.scroll_pixels -= lines as f32 * line_height;
+        terminal.scroll_lines(lines);
+    }
+}
+
+fn background_image_bounds(
+    scale: BackgroundImageScale,
+    bounds: Rectangle,
+    image_size: Size,
+) -> Rectangle {
+    let size = match scale {
+        BackgroundImageScale::Fill | BackgroundImageScale::Fit => {
+            let ratio_w = bounds.width / image_size.width;
+            let ratio_h = bounds.height / image_size.height;
+            let ratio = if scale == BackgroundImageScale::Fill {
+                ratio_w.max(ratio_h)
+            } else {
+                ratio_w.min(ratio_h)
+            };
+            Size::new(image_size.width * ratio, image_size.height * ratio)
+        }
+        BackgroundImageScale::Stretch => bounds.size(),
+        BackgroundImageScale::Center => image_size,
+    };
+    Rectangle::new(
+        Point::new(
+            bounds.x + (bounds.width - size.width) / 2.0,
+            bounds.y + (bounds.height - size.height) / 2.0,
+        ),
+        size,
+    )
+}
+
 fn shade(color: cosmic_text::Color, is_focused: bool) -> cosmic_text::Color {
     if is_focused {
         color
@@ -1091,12 +1875,43 @@ enum Dragging {
     },
 }
 
+// Scrolling while a selection is dragged past the top or bottom edge
+struct AutoScroll {
+    // Column of the mouse
+    col: f32,
+    // Pixels past the edge, negative above the top
+    overshoot: f32,
+    instant: Instant,
+    // Fraction of a line not yet scrolled
+    lines: f32,
+}
+
+// A finger dragging the terminal contents
+struct TouchDrag {
+    finger: touch::Finger,
+    // Position of the last event
+    y: f32,
+    instant: Instant,
+    // Pixels per second, positive when dragging down
+    velocity: f32,
+}
+
+// Scrolling that continues after a touch drag was released
+struct Momentum {
+    // Pixels per second, positive when scrolling up
+    velocity: f32,
+    instant: Instant,
+}
+
 pub struct State {
     modifiers: Modifiers,
     click: Option<(ClickKind, Instant)>,
     dragging: Option<Dragging>,
+    autoscroll: Option<AutoScroll>,
     is_focused: bool,
     scroll_pixels: f32,
+    touch_opt: Option<TouchDrag>,
+    momentum_opt: Option<Momentum>,
     scrollbar_rect: Cell<Rectangle<f32>>,
 }
 
@@ -1107,8 +1922,11 @@ impl State {
             modifiers: Modifiers::empty(),
             click: None,
             dragging: None,
+            autoscroll: None,
             is_focused: false,
             scroll_pixels: 0.0,
+            touch_opt: None,
+            momentum_opt: None,
             scrollbar_rect: Cell::new(Rectangle::default()),
         }
     }
@@ -1138,18 +1956,18 @@ meta      0b100000    (32)
 caps_lock 0b1000000   (64)
 num_lock  0b10000000  (128)
 */
-fn calculate_modifier_number(state: &State) -> u8 {
+fn calculate_modifier_number(modifiers: Modifiers) -> u8 {
     let mut mod_no = 0;
-    if state.modifiers.shift() {
+    if modifiers.shift() {
         mod_no |= 1;
     }
-    if state.modifiers.alt() {
+    if modifiers.alt() {
         mod_no |= 2;
     }
-    if state.modifiers.control() {
+    if modifiers.control() {
         mod_no |= 4;
     }
-    if state.modifiers.logo() {
+    if modifiers.logo() {
         mod_no |= 8;
     }
     mod_no + 1
@@ -1176,3 +1994,40 @@ fn ss3(code: &str, modifiers: u8) -> Option<Vec<u8>> {
         Some(format!("\x1B[1;{modifiers}{code}").as_bytes().to_vec())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_input(key: Key, text_opt: Option<&str>) -> KeyInput {
+        KeyInput {
+            key,
+            location: Location::Standard,
+            modifiers: Modifiers::empty(),
+            text_opt: text_opt.map(str::to_string),
+            release: false,
+        }
+    }
+
+    #[test]
+    fn arrow_follows_cursor_mode() {
+        let up = key_input(Key::Named(Named::ArrowUp), None);
+        assert_eq!(up.encode(TermMode::empty()), Some(b"\x1B[A".to_vec()));
+        assert_eq!(up.encode(TermMode::APP_CURSOR), Some(b"\x1BOA".to_vec()));
+    }
+
+    #[test]
+    fn release_only_sent_with_kitty_keyboard() {
+        let mut a = key_input(Key::Character("a".into()), None);
+        a.release = true;
+        assert_eq!(a.encode(TermMode::empty()), None);
+    }
+
+    #[test]
+    fn character_with_alt() {
+        let mut a = key_input(Key::Character("a".into()), Some("a"));
+        assert_eq!(a.encode(TermMode::empty()), Some(b"a".to_vec()));
+        a.modifiers = Modifiers::ALT;
+        assert_eq!(a.encode(TermMode::empty()), Some(b"\x1Ba".to_vec()));
+    }
+}
@@ -4,10 +4,15 @@ use alacritty_terminal::{
 };
 use hex_color::HexColor;
 use palette::{encoding::Srgb, rgb::Rgb as PRgb, FromColor, Okhsl};
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use crate::config::{
-    ColorScheme, ColorSchemeAnsi, ColorSchemeKind, COSMIC_THEME_DARK, COSMIC_THEME_LIGHT,
+    ColorScheme, ColorSchemeAnsi, ColorSchemeField, ColorSchemeKind, COSMIC_THEME_DARK,
+    COSMIC_THEME_LIGHT,
 };
 
 // Fill missing dim/bright colors with derived values from normal ones.
@@ -212,6 +217,7 @@ impl From<(&str, &Colors)> for ColorScheme {
             foreground: encode_rgb(colors[NamedColor::Foreground]),
             background: encode_rgb(colors[NamedColor::Background]),
             cursor: encode_rgb(colors[NamedColor::Cursor]),
+            cursor_text: None,
             bright_foreground: encode_rgb(colors[NamedColor::BrightForeground]),
             dim_foreground: encode_rgb(colors[NamedColor::DimForeground]),
             normal: ColorSchemeAnsi {
@@ -350,6 +356,313 @@ pub fn terminal_themes() -> HashMap<(String, ColorSchemeKind), Colors> {
     themes
 }
 
+// Directory scanned for color scheme files, usually ~/.local/share/cosmic-term/color-schemes
+fn user_color_schemes_dir() -> Option<PathBuf> {
+    let data_dir = match env::var_os("XDG_DATA_HOME") {
+        Some(data_home) if !data_home.is_empty() => PathBuf::from(data_home),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data_dir.join("cosmic-term").join("color-schemes"))
+}
+
+// Get themes from the user color schemes directory, sorted into dark and light by background
+pub fn user_terminal_themes() -> HashMap<(String, ColorSchemeKind), Colors> {
+    let mut themes = HashMap::new();
+    let Some(dir) = user_color_schemes_dir() else {
+        return themes;
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(ok) => ok,
+        Err(err) => {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("failed to read {:?}: {}", dir, err);
+            }
+            return themes;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let color_scheme = match fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| parse_color_scheme(&path, &data))
+        {
+            Ok(ok) => ok,
+            Err(err) => {
+                log::warn!("failed to load color scheme {:?}: {}", path, err);
+                continue;
+            }
+        };
+        let color_scheme_kind = match color_scheme.background {
+            Some(bg)
+                if u32::from(bg.r) * 299 + u32::from(bg.g) * 587 + u32::from(bg.b) * 114
+                    >= 128 * 1000 =>
+            {
+                ColorSchemeKind::Light
+            }
+            _ => ColorSchemeKind::Dark,
+        };
+        themes.insert(
+            (color_scheme.name.clone(), color_scheme_kind),
+            Colors::from(&color_scheme),
+        );
+    }
+    themes
+}
+
+// Parse a color scheme exported by COSMIC Terminal (RON), Alacritty (TOML or YAML),
+// iTerm2 (.itermcolors), Gogh (YAML) or base16 (YAML). Names default to the file name.
+pub fn parse_color_scheme(path: &Path, data: &str) -> Result<ColorScheme, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut color_scheme = match extension.as_str() {
+        "ron" => return ron::de::from_str(data).map_err(|err| err.to_string()),
+        "itermcolors" => parse_itermcolors(data)?,
+        "toml" => parse_alacritty_toml(data)?,
+        "yml" | "yaml" => parse_yaml(data),
+        _ => return Err(format!("unsupported file type {:?}", extension)),
+    };
+    if color_scheme == ColorScheme::default() {
+        return Err("no colors found".to_string());
+    }
+    if color_scheme.name.is_empty() {
+        color_scheme.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+    Ok(color_scheme)
+}
+
+fn parse_hex(value: &str) -> Option<HexColor> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    let hex = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+    if hex.len() != 6 {
+        return None;
+    }
+    let data = u32::from_str_radix(hex, 16).ok()?;
+    Some(HexColor::rgb(
+        (data >> 16) as u8,
+        (data >> 8) as u8,
+        data as u8,
+    ))
+}
+
+// Names of the Alacritty normal, bright and dim colors, in ANSI order
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// Alacritty color schemes keep their colors in the colors table
+fn parse_alacritty_toml(data: &str) -> Result<ColorScheme, String> {
+    let table = data.parse::<toml::Table>().map_err(|err| err.to_string())?;
+    let mut color_scheme = ColorScheme::default();
+    let Some(colors) = table.get("colors") else {
+        return Ok(color_scheme);
+    };
+    let color =
+        |section: &str, key: &str| colors.get(section)?.get(key)?.as_str().and_then(parse_hex);
+    color_scheme.foreground = color("primary", "foreground");
+    color_scheme.background = color("primary", "background");
+    color_scheme.bright_foreground = color("primary", "bright_foreground");
+    color_scheme.dim_foreground = color("primary", "dim_foreground");
+    color_scheme.cursor = color("cursor", "cursor");
+    color_scheme.cursor_text = color("cursor", "text");
+    for (index, name) in ANSI_NAMES.into_iter().enumerate() {
+        *color_scheme.normal.color_mut(index) = color("normal", name);
+        *color_scheme.bright.color_mut(index) = color("bright", name);
+        *color_scheme.dim.color_mut(index) = color("dim", name);
+    }
+    Ok(color_scheme)
+}
+
+// Remove a YAML comment, which starts with # at the start of a line or after whitespace and
+// outside of quotes
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote_opt = None;
+    let mut after_space = true;
+    for (i, c) in line.char_indices() {
+        match (quote_opt, c) {
+            (None, '#') if after_space => return &line[..i],
+            (None, '"' | '\'') => quote_opt = Some(c),
+            (Some(quote), c) if c == quote => quote_opt = None,
+            _ => {}
+        }
+        after_space = c.is_whitespace();
+    }
+    line
+}
+
+// Flat reading of YAML files, which is enough for the simple layout of color schemes. Nested
+// mappings only matter for the Alacritty primary/normal/bright/dim/cursor sections.
+fn parse_yaml(data: &str) -> ColorScheme {
+    let mut color_scheme = ColorScheme::default();
+    let mut base16 = HashMap::new();
+    let mut section = String::new();
+    for line in data.lines() {
+        let line = strip_yaml_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().trim_matches('"');
+        let value = value.trim();
+        if value.is_empty() {
+            // Start of a nested YAML mapping
+            section = key.to_string();
+            continue;
+        }
+
+        let color_opt = parse_hex(value);
+        match (section.as_str(), key) {
+            // Gogh and base16 names
+            (_, "name") | (_, "scheme") if color_opt.is_none() => {
+                color_scheme.name = value.trim_matches(|c| c == '"' || c == '\'').to_string();
+            }
+            (_, key) if key.starts_with("color_") => {
+                if let Some(color) =
+                    key["color_".len()..]
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|number| {
+                            color_scheme.color_mut(ColorSchemeField::Ansi(number.wrapping_sub(1)))
+                        })
+                {
+                    *color = color_opt;
+                }
+            }
+            (_, key) if key.len() == 6 && key.starts_with("base") => {
+                if let Some(color) = color_opt {
+                    base16.insert(key[4..].to_uppercase(), color);
+                }
+            }
+            // Alacritty sections
+            ("cursor", "cursor") => color_scheme.cursor = color_opt,
+            ("cursor", "text") => color_scheme.cursor_text = color_opt,
+            ("normal", name) | ("bright", name) | ("dim", name) => {
+                let ansi = match section.as_str() {
+                    "normal" => &mut color_scheme.normal,
+                    "bright" => &mut color_scheme.bright,
+                    _ => &mut color_scheme.dim,
+                };
+                if let Some(index) = ANSI_NAMES.iter().position(|ansi_name| *ansi_name == name) {
+                    *ansi.color_mut(index) = color_opt;
+                }
+            }
+            // Other Alacritty sections like selection and search have their own colors
+            ("" | "primary", "foreground") => color_scheme.foreground = color_opt,
+            ("" | "primary", "background") => color_scheme.background = color_opt,
+            ("", "cursor") => color_scheme.cursor = color_opt,
+            ("primary", "bright_foreground") => color_scheme.bright_foreground = color_opt,
+            ("primary", "dim_foreground") => color_scheme.dim_foreground = color_opt,
+            _ => {}
+        }
+    }
+
+    // Map base16 colors to the terminal like base16-shell does
+    if !base16.is_empty() {
+        let base = |name: &str| base16.get(name).copied();
+        color_scheme.background = base("00");
+        color_scheme.foreground = base("05");
+        color_scheme.cursor = base("05");
+        for (index, name) in [
+            "00", "08", "0B", "0A", "0D", "0E", "0C", "05", "03", "08", "0B", "0A", "0D", "0E",
+            "0C", "07",
+        ]
+        .iter()
+        .enumerate()
+        {
+            if let Some(color) = color_scheme.color_mut(ColorSchemeField::Ansi(index)) {
+                *color = base(name);
+            }
+        }
+    }
+
+    color_scheme
+}
+
+// iTerm2 color presets are property lists with a dictionary of float components per color
+fn parse_itermcolors(data: &str) -> Result<ColorScheme, String> {
+    // Property lists declare an external DTD, which is not loaded
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document =
+        roxmltree::Document::parse_with_options(data, options).map_err(|err| err.to_string())?;
+    let Some(dict) = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("dict"))
+    else {
+        return Err("invalid property list".to_string());
+    };
+
+    let mut color_scheme = ColorScheme::default();
+    for (color_name, color_dict) in dict_entries(dict) {
+        if !color_dict.has_tag_name("dict") {
+            continue;
+        }
+        let mut rgb = [0.0f32; 3];
+        for (component, value) in dict_entries(color_dict) {
+            let value = value
+                .text()
+                .and_then(|text| text.trim().parse::<f32>().ok())
+                .unwrap_or_default();
+            match component {
+                "Red Component" => rgb[0] = value,
+                "Green Component" => rgb[1] = value,
+                "Blue Component" => rgb[2] = value,
+                _ => {}
+            }
+        }
+        let to_u8 = |component: f32| (component.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let color = Some(HexColor::rgb(to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2])));
+        match color_name {
+            "Background Color" => color_scheme.background = color,
+            "Foreground Color" => color_scheme.foreground = color,
+            "Cursor Color" => color_scheme.cursor = color,
+            "Cursor Text Color" => color_scheme.cursor_text = color,
+            "Bold Color" => color_scheme.bright_foreground = color,
+            name => {
+                if let Some(slot) = name
+                    .strip_prefix("Ansi ")
+                    .and_then(|name| name.strip_suffix(" Color"))
+                    .and_then(|number| number.parse::<usize>().ok())
+                    .and_then(|index| color_scheme.color_mut(ColorSchemeField::Ansi(index)))
+                {
+                    *slot = color;
+                }
+            }
+        }
+    }
+    Ok(color_scheme)
+}
+
+// Property list dictionaries alternate key elements with their value elements
+fn dict_entries<'a, 'input>(
+    dict: roxmltree::Node<'a, 'input>,
+) -> impl Iterator<Item = (&'a str, roxmltree::Node<'a, 'input>)> {
+    let mut elements = dict.children().filter(|node| node.is_element());
+    std::iter::from_fn(move || loop {
+        let key = elements.next()?;
+        let value = elements.next()?;
+        if key.has_tag_name("key") {
+            return Some((key.text().unwrap_or_default().trim(), value));
+        }
+    })
+}
+
 // Helper function to export builtin themes to theme files
 #[allow(dead_code)]
 pub fn export() {
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Triggers, where each line of output read from the PTY is matched against patterns from the
+// config, with escape sequences removed. Matches are queued for the app, which runs the actions.
+// Highlight triggers are drawn like profile highlights instead, so they are not matched here.
+// Each trigger runs at most once per cooldown, further matches in that time are dropped.
+
+use regex::Regex;
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::{Trigger, TriggerAction},
+    output_log::{strip_byte, StripState},
+};
+
+// Longest line matched, the rest of a longer line is ignored
+const LINE_MAX_BYTES: usize = 4096;
+// Shortest time between two runs of the same trigger
+const COOLDOWN: Duration = Duration::from_secs(1);
+
+/// A trigger that matched a line of output
+#[derive(Clone, Debug)]
+pub struct TriggerMatch {
+    pub action: TriggerAction,
+    pub line: String,
+}
+
+#[derive(Debug)]
+pub struct TriggerMatcher {
+    triggers: Vec<(Regex, TriggerAction)>,
+    // When each trigger last matched, in the order of triggers
+    last_matched: Vec<Option<Instant>>,
+    state: StripState,
+    line: Vec<u8>,
+}
+
+impl TriggerMatcher {
+    pub fn new(triggers: &[Trigger]) -> Self {
+        let triggers: Vec<_> = triggers
+            .iter()
+            .filter(|trigger| !matches!(trigger.action, TriggerAction::Highlight(_)))
+            .filter_map(|trigger| match Regex::new(&trigger.regex) {
+                Ok(regex) => Some((regex, trigger.action.clone())),
+                Err(err) => {
+                    log::warn!("failed to parse trigger regex {:?}: {}", trigger.regex, err);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            last_matched: vec![None; triggers.len()],
+            triggers,
+            state: StripState::default(),
+            line: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    /// Match the lines completed by output read from the PTY
+    pub fn output(&mut self, bytes: &[u8], matches: &mut Vec<TriggerMatch>) {
+        self.output_at(bytes, Instant::now(), matches);
+    }
+
+    fn output_at(&mut self, bytes: &[u8], now: Instant, matches: &mut Vec<TriggerMatch>) {
+        let mut stripped = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            self.state = strip_byte(self.state, byte, &mut stripped);
+        }
+        for byte in stripped {
+            if byte == b'\n' {
+                let line = String::from_utf8_lossy(&self.line).into_owned();
+                self.line.clear();
+                for ((regex, action), last_matched) in
+                    self.triggers.iter().zip(self.last_matched.iter_mut())
+                {
+                    let cooling_down = last_matched
+                        .is_some_and(|last_matched| now.duration_since(last_matched) < COOLDOWN);
+                    if !cooling_down && regex.is_match(&line) {
+                        *last_matched = Some(now);
+                        matches.push(TriggerMatch {
+                            action: action.clone(),
+                            line: line.clone(),
+                        });
+                    }
+                }
+            } else if self.line.len() < LINE_MAX_BYTES {
+                self.line.push(byte);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_matches_wait_for_cooldown() {
+        let mut matcher = TriggerMatcher::new(&[Trigger {
+            regex: "finished".to_string(),
+            action: TriggerAction::Bell,
+        }]);
+        let start = Instant::now();
+        let mut matches = Vec::new();
+        matcher.output_at(b"finished\nfinished\n", start, &mut matches);
+        assert_eq!(matches.len(), 1);
+        matcher.output_at(b"finished\n", start + COOLDOWN / 2, &mut matches);
+        assert_eq!(matches.len(), 1);
+        matcher.output_at(b"finished\n", start + COOLDOWN, &mut matches);
+        assert_eq!(matches.len(), 2);
+    }
+}